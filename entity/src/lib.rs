@@ -1 +1,11 @@
+pub mod access_token;
+pub mod attachment;
+pub mod comment;
+pub mod list_member;
+pub mod reminder;
+pub mod sea_orm_active_enums;
 pub mod task;
+pub mod task_event;
+pub mod task_list;
+pub mod webhook_delivery;
+pub mod webhook_subscription;
@@ -1,3 +1,12 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.9.1
 
+pub use super::access_token::Entity as AccessToken;
+pub use super::attachment::Entity as Attachment;
+pub use super::comment::Entity as Comment;
+pub use super::list_member::Entity as ListMember;
+pub use super::reminder::Entity as Reminder;
 pub use super::task::Entity as Task;
+pub use super::task_event::Entity as TaskEvent;
+pub use super::task_list::Entity as TaskList;
+pub use super::webhook_delivery::Entity as WebhookDelivery;
+pub use super::webhook_subscription::Entity as WebhookSubscription;
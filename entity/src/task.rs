@@ -1,5 +1,6 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.9.1
 
+use super::sea_orm_active_enums::Priority;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
@@ -13,6 +14,10 @@ pub fn default_dealine() -> Option<DateTime<Utc>> {
     Option::None
 }
 
+pub fn default_occurrence() -> i32 {
+    1
+}
+
 fn get_current_time() -> DateTime<Utc> {
     chrono::Utc::now()
 }
@@ -42,16 +47,69 @@ pub struct Model {
         default = "default_dealine"
     )]
     pub deadline: Option<DateTime<Utc>>,
+
+    #[serde(rename = "listId", default, skip_serializing_if = "Option::is_none")]
+    pub list_id: Option<Uuid>,
+
+    #[serde(default)]
+    pub priority: Priority,
+
+    /// RFC 5545 recurrence rule, e.g. `FREQ=WEEKLY;BYDAY=MO`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rrule: Option<String>,
+
+    /// IANA timezone in which `rrule` is evaluated, UTC if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+
+    /// Id of the first task of the recurring series
+    #[serde(rename = "seriesId", skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub series_id: Option<Uuid>,
+
+    #[serde(skip_deserializing, default = "default_occurrence")]
+    pub occurrence: i32,
+
+    /// Task this one is a subtask of
+    #[serde(rename = "parentId", default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<Uuid>,
+
+    /// When `completed` last became true, `None` while it is false
+    #[serde(
+        rename = "completedAt",
+        skip_deserializing,
+        skip_serializing_if = "Option::is_none",
+        with = "ts_milliseconds_option"
+    )]
+    pub completed_at: Option<DateTime<Utc>>,
+
+    /// Key of the task in the manual order of its list, see `POST /todo/{id}/move`
+    #[serde(skip_deserializing, default)]
+    pub position: String,
+
+    /// Description in Markdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
 }
 
 impl Model {
     pub fn new(title: String, completed: bool, deadline: Option<DateTime<Utc>>) -> Self {
+        let now = Utc::now();
         Model {
             id: Uuid::new_v4(),
             title,
             completed,
-            created_at: Utc::now(),
+            created_at: now,
             deadline,
+            list_id: None,
+            priority: Priority::None,
+            rrule: None,
+            timezone: None,
+            series_id: None,
+            occurrence: default_occurrence(),
+            parent_id: None,
+            completed_at: completed.then_some(now),
+            position: String::new(),
+            notes: None,
         }
     }
 }
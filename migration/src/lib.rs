@@ -1,12 +1,46 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20220101_000002_create_access_token_table;
+mod m20220101_000003_create_task_list_tables;
+mod m20220101_000004_add_task_priority;
+mod m20220101_000005_add_task_recurrence;
+mod m20220101_000006_create_reminder_table;
+mod m20220101_000007_create_webhook_tables;
+mod m20220101_000008_create_task_event_table;
+mod m20220101_000009_add_task_event_mutation;
+mod m20220101_000010_add_task_parent;
+mod m20220101_000011_add_task_completed_at;
+mod m20220101_000012_backfill_task_completed_at;
+mod m20220101_000013_add_task_position;
+mod m20220101_000014_add_task_notes;
+mod m20220101_000015_create_attachment_table;
+mod m20220101_000016_create_comment_table;
+mod m20220101_000017_add_reminder_attempts;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20220101_000002_create_access_token_table::Migration),
+            Box::new(m20220101_000003_create_task_list_tables::Migration),
+            Box::new(m20220101_000004_add_task_priority::Migration),
+            Box::new(m20220101_000005_add_task_recurrence::Migration),
+            Box::new(m20220101_000006_create_reminder_table::Migration),
+            Box::new(m20220101_000007_create_webhook_tables::Migration),
+            Box::new(m20220101_000008_create_task_event_table::Migration),
+            Box::new(m20220101_000009_add_task_event_mutation::Migration),
+            Box::new(m20220101_000010_add_task_parent::Migration),
+            Box::new(m20220101_000011_add_task_completed_at::Migration),
+            Box::new(m20220101_000012_backfill_task_completed_at::Migration),
+            Box::new(m20220101_000013_add_task_position::Migration),
+            Box::new(m20220101_000014_add_task_notes::Migration),
+            Box::new(m20220101_000015_create_attachment_table::Migration),
+            Box::new(m20220101_000016_create_comment_table::Migration),
+            Box::new(m20220101_000017_add_reminder_attempts::Migration),
+        ]
     }
 }
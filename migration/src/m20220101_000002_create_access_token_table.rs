@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AccessToken::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AccessToken::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AccessToken::Name).string().not_null())
+                    .col(ColumnDef::new(AccessToken::Owner).string().not_null())
+                    .col(
+                        ColumnDef::new(AccessToken::TokenHash)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(AccessToken::Scopes).string().not_null())
+                    .col(
+                        ColumnDef::new(AccessToken::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .col(ColumnDef::new(AccessToken::LastUsedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(AccessToken::RevokedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AccessToken::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum AccessToken {
+    Table,
+    Id,
+    Name,
+    Owner,
+    TokenHash,
+    Scopes,
+    CreatedAt,
+    LastUsedAt,
+    RevokedAt,
+}
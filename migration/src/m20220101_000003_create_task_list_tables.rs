@@ -0,0 +1,127 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TaskList::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TaskList::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TaskList::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(TaskList::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ListMember::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ListMember::ListId).uuid().not_null())
+                    .col(ColumnDef::new(ListMember::Member).string().not_null())
+                    .col(ColumnDef::new(ListMember::Role).string_len(16).not_null())
+                    .col(ColumnDef::new(ListMember::InvitedBy).string().not_null())
+                    .col(
+                        ColumnDef::new(ListMember::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .col(ColumnDef::new(ListMember::AcceptedAt).timestamp_with_time_zone())
+                    .primary_key(
+                        Index::create()
+                            .col(ListMember::ListId)
+                            .col(ListMember::Member),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-list_member-list_id")
+                            .from(ListMember::Table, ListMember::ListId)
+                            .to(TaskList::Table, TaskList::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Task::Table)
+                    .add_column(ColumnDef::new(Task::ListId).uuid())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-task-list_id")
+                            .from_tbl(Task::Table)
+                            .from_col(Task::ListId)
+                            .to_tbl(TaskList::Table)
+                            .to_col(TaskList::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Task::Table)
+                    .drop_foreign_key(Alias::new("fk-task-list_id"))
+                    .drop_column(Task::ListId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ListMember::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(TaskList::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Task {
+    Table,
+    ListId,
+}
+
+#[derive(Iden)]
+enum TaskList {
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum ListMember {
+    Table,
+    ListId,
+    Member,
+    Role,
+    InvitedBy,
+    CreatedAt,
+    AcceptedAt,
+}
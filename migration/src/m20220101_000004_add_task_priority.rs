@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_query::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Variants are declared by increasing severity, which is the order
+        // Postgres uses when sorting by an enum column
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(Priority::Type)
+                    .values([
+                        Priority::None,
+                        Priority::Low,
+                        Priority::Medium,
+                        Priority::High,
+                        Priority::Urgent,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Task::Table)
+                    .add_column(
+                        ColumnDef::new(Task::Priority)
+                            .custom(Priority::Type)
+                            .not_null()
+                            .default("none"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Task::Table)
+                    .drop_column(Task::Priority)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(Priority::Type).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Task {
+    Table,
+    Priority,
+}
+
+#[derive(Iden)]
+enum Priority {
+    #[iden = "priority"]
+    Type,
+    #[iden = "none"]
+    None,
+    #[iden = "low"]
+    Low,
+    #[iden = "medium"]
+    Medium,
+    #[iden = "high"]
+    High,
+    #[iden = "urgent"]
+    Urgent,
+}
@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Task::Table)
+                    .add_column(ColumnDef::new(Task::Rrule).text())
+                    .add_column(ColumnDef::new(Task::Timezone).string())
+                    .add_column(ColumnDef::new(Task::SeriesId).uuid())
+                    .add_column(
+                        ColumnDef::new(Task::Occurrence)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-task-series_id")
+                    .table(Task::Table)
+                    .col(Task::SeriesId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-task-series_id")
+                    .table(Task::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Task::Table)
+                    .drop_column(Task::Rrule)
+                    .drop_column(Task::Timezone)
+                    .drop_column(Task::SeriesId)
+                    .drop_column(Task::Occurrence)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Task {
+    Table,
+    Rrule,
+    Timezone,
+    SeriesId,
+    Occurrence,
+}
@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Reminder::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Reminder::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Reminder::TaskId).uuid().not_null())
+                    .col(ColumnDef::new(Reminder::OffsetMs).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(Reminder::RemindAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Reminder::SentAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(Reminder::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-reminder-task_id")
+                            .from(Reminder::Table, Reminder::TaskId)
+                            .to(Task::Table, Task::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-reminder-remind_at")
+                    .table(Reminder::Table)
+                    .col(Reminder::RemindAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Reminder::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Task {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Reminder {
+    Table,
+    Id,
+    TaskId,
+    OffsetMs,
+    RemindAt,
+    SentAt,
+    CreatedAt,
+}
@@ -0,0 +1,137 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookSubscription::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WebhookSubscription::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(WebhookSubscription::Url).text().not_null())
+                    .col(ColumnDef::new(WebhookSubscription::Secret).string().not_null())
+                    .col(ColumnDef::new(WebhookSubscription::Events).string().not_null())
+                    .col(ColumnDef::new(WebhookSubscription::Owner).string().not_null())
+                    .col(
+                        ColumnDef::new(WebhookSubscription::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookDelivery::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WebhookDelivery::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDelivery::SubscriptionId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WebhookDelivery::Event).string().not_null())
+                    .col(ColumnDef::new(WebhookDelivery::Payload).text().not_null())
+                    .col(
+                        ColumnDef::new(WebhookDelivery::Status)
+                            .string_len(16)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDelivery::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDelivery::NextAttemptAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WebhookDelivery::ResponseStatus).integer())
+                    .col(ColumnDef::new(WebhookDelivery::LastError).text())
+                    .col(
+                        ColumnDef::new(WebhookDelivery::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .col(ColumnDef::new(WebhookDelivery::DeliveredAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-webhook_delivery-subscription_id")
+                            .from(WebhookDelivery::Table, WebhookDelivery::SubscriptionId)
+                            .to(WebhookSubscription::Table, WebhookSubscription::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-webhook_delivery-status-next_attempt_at")
+                    .table(WebhookDelivery::Table)
+                    .col(WebhookDelivery::Status)
+                    .col(WebhookDelivery::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WebhookDelivery::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(WebhookSubscription::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum WebhookSubscription {
+    Table,
+    Id,
+    Url,
+    Secret,
+    Events,
+    Owner,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum WebhookDelivery {
+    Table,
+    Id,
+    SubscriptionId,
+    Event,
+    Payload,
+    Status,
+    Attempts,
+    NextAttemptAt,
+    ResponseStatus,
+    LastError,
+    CreatedAt,
+    DeliveredAt,
+}
@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Append-only, and deliberately without a foreign key to `task` so
+        // the history of deleted tasks is kept
+        manager
+            .create_table(
+                Table::create()
+                    .table(TaskEvent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TaskEvent::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TaskEvent::TaskId).uuid().not_null())
+                    .col(ColumnDef::new(TaskEvent::ListId).uuid())
+                    .col(ColumnDef::new(TaskEvent::Actor).string().not_null())
+                    .col(ColumnDef::new(TaskEvent::Event).string_len(32).not_null())
+                    .col(ColumnDef::new(TaskEvent::Changes).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(TaskEvent::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-task_event-task_id-created_at")
+                    .table(TaskEvent::Table)
+                    .col(TaskEvent::TaskId)
+                    .col(TaskEvent::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-task_event-created_at")
+                    .table(TaskEvent::Table)
+                    .col(TaskEvent::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TaskEvent::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum TaskEvent {
+    Table,
+    Id,
+    TaskId,
+    ListId,
+    Actor,
+    Event,
+    Changes,
+    CreatedAt,
+}
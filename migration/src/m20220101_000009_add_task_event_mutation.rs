@@ -0,0 +1,100 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaskEvent::Table)
+                    .add_column(ColumnDef::new(TaskEvent::MutationId).uuid())
+                    .add_column(ColumnDef::new(TaskEvent::Reverts).uuid())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Events recorded so far were each a mutation of their own
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(TaskEvent::Table)
+                    .value_expr(TaskEvent::MutationId, Expr::col(TaskEvent::Id).into())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaskEvent::Table)
+                    .modify_column(ColumnDef::new(TaskEvent::MutationId).uuid().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-task_event-actor-created_at")
+                    .table(TaskEvent::Table)
+                    .col(TaskEvent::Actor)
+                    .col(TaskEvent::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-task_event-mutation_id")
+                    .table(TaskEvent::Table)
+                    .col(TaskEvent::MutationId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-task_event-mutation_id")
+                    .table(TaskEvent::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-task_event-actor-created_at")
+                    .table(TaskEvent::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaskEvent::Table)
+                    .drop_column(TaskEvent::MutationId)
+                    .drop_column(TaskEvent::Reverts)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum TaskEvent {
+    Table,
+    Id,
+    Actor,
+    CreatedAt,
+    MutationId,
+    Reverts,
+}
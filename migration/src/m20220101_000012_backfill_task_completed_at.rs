@@ -0,0 +1,39 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Tasks completed before `completed_at` was recorded are dated to their
+        // last completion in the audit log, or else to their creation
+        let sql = r#"
+            UPDATE "task"
+            SET "completed_at" = COALESCE(
+                (
+                    SELECT max("task_event"."created_at")
+                    FROM "task_event"
+                    WHERE "task_event"."task_id" = "task"."id"
+                        AND "task_event"."event" = 'task.completed'
+                ),
+                "task"."created_at"
+            )
+            WHERE "task"."completed" AND "task"."completed_at" IS NULL
+        "#;
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                sql.to_owned(),
+            ))
+            .await
+            .map(|_| ())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // The backfilled dates cannot be told apart from recorded ones
+        Ok(())
+    }
+}
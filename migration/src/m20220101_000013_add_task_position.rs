@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Positions are compared byte by byte, whatever the database locale
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Task::Table)
+                    .add_column(
+                        ColumnDef::new(Task::Position)
+                            .string()
+                            .not_null()
+                            .default("")
+                            .extra(r#"COLLATE "C""#.to_string()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing tasks keep the order they were created in, numbered in
+        // hexadecimal as six digit integer parts
+        let sql = r#"
+            UPDATE "task"
+            SET "position" = "ranked"."position"
+            FROM (
+                SELECT
+                    "id",
+                    'f' || lpad(
+                        to_hex(row_number() OVER (PARTITION BY "list_id" ORDER BY "created_at", "id")),
+                        6,
+                        '0'
+                    ) AS "position"
+                FROM "task"
+            ) AS "ranked"
+            WHERE "task"."id" = "ranked"."id"
+        "#;
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                sql.to_owned(),
+            ))
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-task-list_id-position")
+                    .table(Task::Table)
+                    .col(Task::ListId)
+                    .col(Task::Position)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-task-list_id-position")
+                    .table(Task::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Task::Table)
+                    .drop_column(Task::Position)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Task {
+    Table,
+    ListId,
+    Position,
+}
@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Attachment::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Attachment::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Attachment::TaskId).uuid())
+                    .col(ColumnDef::new(Attachment::Filename).string().not_null())
+                    .col(ColumnDef::new(Attachment::ContentType).string().not_null())
+                    .col(ColumnDef::new(Attachment::Size).big_integer().not_null())
+                    .col(ColumnDef::new(Attachment::Checksum).string().not_null())
+                    .col(ColumnDef::new(Attachment::UploadedBy).string().not_null())
+                    .col(
+                        ColumnDef::new(Attachment::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    // Detached rather than deleted along with the task, so
+                    // that their files can be cleaned up afterwards
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-attachment-task_id")
+                            .from(Attachment::Table, Attachment::TaskId)
+                            .to(Task::Table, Task::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-attachment-task_id")
+                    .table(Attachment::Table)
+                    .col(Attachment::TaskId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-attachment-checksum")
+                    .table(Attachment::Table)
+                    .col(Attachment::Checksum)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Attachment::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Task {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Attachment {
+    Table,
+    Id,
+    TaskId,
+    Filename,
+    ContentType,
+    Size,
+    Checksum,
+    UploadedBy,
+    CreatedAt,
+}
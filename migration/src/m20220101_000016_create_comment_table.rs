@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Comment::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Comment::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Comment::TaskId).uuid().not_null())
+                    .col(ColumnDef::new(Comment::Author).string().not_null())
+                    .col(ColumnDef::new(Comment::Body).text().not_null())
+                    .col(
+                        ColumnDef::new(Comment::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .col(ColumnDef::new(Comment::UpdatedAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-comment-task_id")
+                            .from(Comment::Table, Comment::TaskId)
+                            .to(Task::Table, Task::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Serves both the pages of a task's comments and their counts
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-comment-task_id-created_at")
+                    .table(Comment::Table)
+                    .col(Comment::TaskId)
+                    .col(Comment::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Comment::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Task {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Comment {
+    Table,
+    Id,
+    TaskId,
+    Author,
+    Body,
+    CreatedAt,
+    UpdatedAt,
+}
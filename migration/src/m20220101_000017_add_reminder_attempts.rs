@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Reminder::Table)
+                    .add_column(
+                        ColumnDef::new(Reminder::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(ColumnDef::new(Reminder::NextAttemptAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Reminder::Table)
+                    .drop_column(Reminder::Attempts)
+                    .drop_column(Reminder::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Reminder {
+    Table,
+    Attempts,
+    NextAttemptAt,
+}
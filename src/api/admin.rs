@@ -0,0 +1,110 @@
+use crate::api::auth::authorize_admin;
+use crate::database::DbClient;
+use crate::lib::errors::Error;
+use crate::model::backup::{Backup, BackupSummary, BACKUP_FORMAT_VERSION};
+
+use actix_web::{
+    get,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    post,
+    web::{Data, Payload},
+    HttpRequest, HttpResponse,
+};
+use futures::StreamExt;
+
+pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
+    app.service(get_backup).service(restore_backup);
+}
+
+/// Largest body `POST /admin/restore` accepts, in bytes.
+fn restore_max_bytes() -> usize {
+    std::env::var("RESTORE_MAX_BYTES")
+        .ok()
+        .and_then(|size| size.parse::<usize>().ok())
+        .unwrap_or(64 * 1024 * 1024)
+}
+
+fn schema_error(err: Error) -> HttpResponse {
+    match err {
+        Error::SchemaMismatch(_) => HttpResponse::Conflict().body(err.to_string()),
+        err => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+/// Dumps every table as a versioned JSON document.
+#[get("/admin/backup")]
+pub async fn get_backup(db: Data<DbClient>, req: HttpRequest) -> HttpResponse {
+    if let Err(res) = authorize_admin(&db, &req).await {
+        return res;
+    }
+
+    match db.backup_dao.export().await {
+        Ok(backup) => {
+            let filename = format!("backup-{}.json", backup.created_at.format("%Y%m%dT%H%M%SZ"));
+            HttpResponse::Ok()
+                .insert_header(ContentDisposition {
+                    disposition: DispositionType::Attachment,
+                    parameters: vec![DispositionParam::Filename(filename)],
+                })
+                .json(backup)
+        }
+        Err(err) => schema_error(err),
+    }
+}
+
+/// Replaces all data with that of a backup taken at the same schema version.
+#[post("/admin/restore")]
+pub async fn restore_backup(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    mut payload: Payload,
+) -> HttpResponse {
+    if let Err(res) = authorize_admin(&db, &req).await {
+        return res;
+    }
+
+    let max_bytes = restore_max_bytes();
+    let mut body = Vec::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+        };
+        if body.len() + chunk.len() > max_bytes {
+            return HttpResponse::PayloadTooLarge()
+                .body(format!("Backups are limited to {} bytes", max_bytes));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    let backup: Backup = match serde_json::from_slice(&body) {
+        Ok(backup) => backup,
+        Err(err) => return HttpResponse::BadRequest().body(format!("Invalid backup: {}", err)),
+    };
+    if backup.version != BACKUP_FORMAT_VERSION {
+        return HttpResponse::BadRequest().body(format!(
+            "Unsupported backup version {}, expected {}",
+            backup.version, BACKUP_FORMAT_VERSION
+        ));
+    }
+
+    let schema_version = match db.backup_dao.schema_version().await {
+        Ok(schema_version) => schema_version,
+        Err(err) => return schema_error(err),
+    };
+    if backup.schema_version != schema_version {
+        return HttpResponse::Conflict().body(format!(
+            "The backup was taken at schema `{}`, the database is at `{}`",
+            backup.schema_version, schema_version
+        ));
+    }
+
+    let summary = BackupSummary::new(schema_version, &backup.tables);
+    match db.backup_dao.restore(backup.tables).await {
+        Ok(()) => {
+            info!("Restored the backup of {}", backup.created_at);
+            HttpResponse::Ok().json(summary)
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashSet;
+
+use crate::api::auth::authorize;
+use crate::api::task::find_task;
+use crate::database::DbClient;
+use crate::lib::attachments::{self, StoreError};
+use crate::lib::auth::{Principal, Scope};
+use crate::lib::{errors::Error, uuid};
+use crate::model::attachment::PublicAttachment;
+use crate::model::results::RowsAffected;
+use entity::attachment::Model as Attachment;
+use entity::list_member::Role;
+
+use actix_files::NamedFile;
+use actix_multipart::Multipart;
+use actix_web::http::header::{
+    Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue, HeaderValue,
+    X_CONTENT_TYPE_OPTIONS,
+};
+use actix_web::{
+    delete, get, mime, post,
+    web::{Data, Path},
+    HttpRequest, HttpResponse,
+};
+use futures::StreamExt;
+use serde::Deserialize;
+
+pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
+    app.service(upload_attachments)
+        .service(get_attachments)
+        .service(download_attachment)
+        .service(delete_attachment);
+}
+
+#[derive(Deserialize)]
+pub struct TaskIdentifier {
+    task_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct AttachmentIdentifier {
+    task_id: String,
+    attachment_id: String,
+}
+
+/// The attachment at `path`, as long as it belongs to the task of `path`.
+async fn find_attachment(
+    db: &DbClient,
+    principal: &Principal,
+    path: &AttachmentIdentifier,
+    role: Role,
+) -> Result<Attachment, HttpResponse> {
+    let task = find_task(db, principal, &path.task_id, role).await?;
+    let id = uuid::parse_str(&path.attachment_id)
+        .map_err(|err| HttpResponse::BadRequest().body(err.to_string()))?;
+
+    match db.attachment_dao.find_id(id).await {
+        Ok(attachment) if attachment.task_id == Some(task.id) => Ok(attachment),
+        Ok(_) | Err(Error::NotFound(_)) => {
+            Err(HttpResponse::NotFound().body(format!("Not Found: Attachment <id: {}>", id)))
+        }
+        Err(err) => Err(HttpResponse::InternalServerError().body(err.to_string())),
+    }
+}
+
+/// Attaches the files of a `multipart/form-data` body to the task, each as
+/// soon as it was received. Parts without a filename are ignored.
+#[post("/todo/{task_id}/attachments")]
+pub async fn upload_attachments(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<TaskIdentifier>,
+    mut payload: Multipart,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let task = match find_task(&db, &principal, &path.task_id, Role::Editor).await {
+        Ok(task) => task,
+        Err(res) => return res,
+    };
+
+    let quota = attachments::quota_bytes();
+    let mut used = match db.attachment_dao.size_for_task(task.id).await {
+        Ok(used) => used,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+
+    let mut uploaded = Vec::new();
+    while let Some(field) = payload.next().await {
+        let field = match field {
+            Ok(field) => field,
+            Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+        };
+        let filename = match field
+            .content_disposition()
+            .and_then(|disposition| disposition.get_filename())
+        {
+            Some(filename) => attachments::sanitize_filename(filename),
+            None => continue,
+        };
+        let content_type = field
+            .content_type()
+            .map(|mime| mime.essence_str().to_string())
+            .unwrap_or_else(|| mime::APPLICATION_OCTET_STREAM.to_string());
+
+        let stored = match attachments::store(field, quota.saturating_sub(used)).await {
+            Ok(stored) => stored,
+            Err(err @ StoreError::TooLarge(_)) => {
+                return HttpResponse::PayloadTooLarge().body(err.to_string())
+            }
+            Err(err @ StoreError::Upload(_)) => {
+                return HttpResponse::BadRequest().body(err.to_string())
+            }
+            Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+        };
+
+        let attachment = Attachment::new(
+            task.id,
+            filename,
+            content_type,
+            stored.size as i64,
+            stored.checksum,
+            principal.subject.clone(),
+        );
+        match db.attachment_dao.create(attachment, quota).await {
+            Ok(attachment) => {
+                used += attachment.size as u64;
+                uploaded.push(PublicAttachment::from(attachment));
+            }
+            Err(Error::QuotaExceeded(err)) => return HttpResponse::PayloadTooLarge().body(err),
+            Err(Error::NotFound(err)) => {
+                return HttpResponse::NotFound().body(format!("Not Found: {}", err))
+            }
+            Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+        }
+    }
+
+    if uploaded.is_empty() {
+        return HttpResponse::BadRequest().body("No file was uploaded");
+    }
+    HttpResponse::Created().json(uploaded)
+}
+
+#[get("/todo/{task_id}/attachments")]
+pub async fn get_attachments(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<TaskIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let task = match find_task(&db, &principal, &path.task_id, Role::Viewer).await {
+        Ok(task) => task,
+        Err(res) => return res,
+    };
+
+    match db.attachment_dao.find_for_task(task.id).await {
+        Ok(attachments) => {
+            let public_attachments: Vec<PublicAttachment> = attachments
+                .into_iter()
+                .map(PublicAttachment::from)
+                .collect();
+            HttpResponse::Ok().json(public_attachments)
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+/// Sends the file, or the `Range` of it asked for, always as a download so
+/// that browsers do not render uploaded HTML.
+#[get("/todo/{task_id}/attachments/{attachment_id}")]
+pub async fn download_attachment(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<AttachmentIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let attachment = match find_attachment(&db, &principal, &path, Role::Viewer).await {
+        Ok(attachment) => attachment,
+        Err(res) => return res,
+    };
+
+    let file = match NamedFile::open_async(attachments::blob_path(&attachment.checksum)).await {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            error!("The file of Attachment <id: {}> is missing", attachment.id);
+            return HttpResponse::NotFound().body(format!(
+                "Not Found: File of Attachment <id: {}>",
+                attachment.id
+            ));
+        }
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+
+    let mut parameters = vec![DispositionParam::Filename(attachment.filename.clone())];
+    if !attachment.filename.is_ascii() {
+        parameters.push(DispositionParam::FilenameExt(ExtendedValue {
+            charset: Charset::Ext("UTF-8".to_string()),
+            language_tag: None,
+            value: attachment.filename.into_bytes(),
+        }));
+    }
+    let content_type = attachment
+        .content_type
+        .parse()
+        .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+
+    let mut res = file
+        .set_content_type(content_type)
+        .set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters,
+        })
+        .into_response(&req);
+    res.headers_mut()
+        .insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    res
+}
+
+#[delete("/todo/{task_id}/attachments/{attachment_id}")]
+pub async fn delete_attachment(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<AttachmentIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksDelete).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let attachment = match find_attachment(&db, &principal, &path, Role::Editor).await {
+        Ok(attachment) => attachment,
+        Err(res) => return res,
+    };
+
+    match db.attachment_dao.delete_id(attachment.id).await {
+        Ok(status) => {
+            if let Err(err) = remove_unreferenced(&db, vec![attachment.checksum]).await {
+                error!("Failed to remove the file of an attachment: {}", err);
+            }
+            HttpResponse::Ok().json(RowsAffected::from(status))
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+/// Removes the files of `checksums` that no attachment references anymore.
+/// Returns how many were removed.
+pub(crate) async fn remove_unreferenced(
+    db: &DbClient,
+    checksums: Vec<String>,
+) -> Result<usize, Error> {
+    let checksums: Vec<String> = checksums
+        .into_iter()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let referenced = db.attachment_dao.referenced(&checksums).await?;
+
+    let mut removed = 0;
+    for checksum in checksums
+        .iter()
+        .filter(|checksum| !referenced.contains(*checksum))
+    {
+        if attachments::remove(checksum).await? {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Deletes the attachments of tasks that were deleted, and their files.
+/// Failures are only logged, the deletion of the tasks having succeeded.
+pub(crate) async fn purge_detached(db: &DbClient) {
+    let res = match db.attachment_dao.delete_detached().await {
+        Ok(checksums) => remove_unreferenced(db, checksums).await,
+        Err(err) => Err(err),
+    };
+    match res {
+        Ok(0) => {}
+        Ok(removed) => debug!("Removed the files of {} attachments", removed),
+        Err(err) => error!("Failed to clean up attachments: {}", err),
+    }
+}
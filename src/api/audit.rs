@@ -0,0 +1,389 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::api::attachment;
+use crate::api::auth::{authorize, lists_with_role, require_role, require_scope};
+use crate::database::DbClient;
+use crate::events::bus::EventBus;
+use crate::events::{self, audit, Mutation, TaskEvent};
+use crate::lib::auth::{Principal, Scope};
+use crate::lib::{errors::Error, uuid};
+use crate::model::task::PublicTask;
+use crate::model::task_event::{PublicTaskEvent, RevertResult};
+use entity::list_member::Role;
+use entity::task::Model as Task;
+use entity::task_event::Model as TaskEventRecord;
+
+use actix_web::{
+    get, post,
+    web::{Data, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use chrono::serde::ts_milliseconds_option;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+const DEFAULT_AUDIT_LIMIT: u64 = 100;
+const MAX_AUDIT_LIMIT: u64 = 1000;
+
+/// Removed along with a task, and not part of its history, so that tasks
+/// recreated by a revert or an undo come back without them.
+const NOT_RESTORED: [&str; 3] = ["reminders", "comments", "attachments"];
+
+pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
+    app.service(get_task_history)
+        .service(get_audit_log)
+        .service(revert_task)
+        .service(undo);
+}
+
+fn send_events(events: Vec<TaskEventRecord>) -> HttpResponse {
+    let public_events: Vec<PublicTaskEvent> =
+        events.into_iter().map(PublicTaskEvent::from).collect();
+    HttpResponse::Ok().json(public_events)
+}
+
+#[derive(Deserialize)]
+pub struct TaskIdentifier {
+    task_id: String,
+}
+
+/// Every recorded change of a task, oldest first, also after it was deleted.
+#[get("/todo/{task_id}/history")]
+pub async fn get_task_history(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<TaskIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let id = match uuid::parse_str(&path.task_id) {
+        Ok(id) => id,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+
+    let events = match db.task_event_dao.find_for_task(id).await {
+        Ok(events) => events,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+
+    // Deleted tasks are authorized by the list they were last in
+    let list_id = match db.task_dao.find_id(id).await {
+        Ok(task) => task.list_id,
+        Err(Error::NotFound(err)) => match events.last() {
+            Some(event) => event.list_id,
+            None => return HttpResponse::NotFound().body(format!("Not Found: {}", err)),
+        },
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    if let Some(list_id) = list_id {
+        if let Err(res) = require_role(&db, &principal, list_id, Role::Viewer).await {
+            return res;
+        }
+    }
+
+    send_events(events)
+}
+
+#[derive(Deserialize)]
+pub struct AuditQueryParams {
+    /// Inclusive
+    #[serde(with = "ts_milliseconds_option", default)]
+    from: Option<DateTime<Utc>>,
+
+    /// Exclusive
+    #[serde(with = "ts_milliseconds_option", default)]
+    to: Option<DateTime<Utc>>,
+
+    actor: Option<String>,
+
+    limit: Option<u64>,
+}
+
+/// Changes to every task the caller can see, oldest first.
+#[get("/audit")]
+pub async fn get_audit_log(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    params: Query<AuditQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let lists = match lists_with_role(&db, &principal, Role::Viewer).await {
+        Ok(lists) => lists,
+        Err(res) => return res,
+    };
+
+    if let (Some(from), Some(to)) = (params.from, params.to) {
+        if from >= to {
+            return HttpResponse::BadRequest().body("'from' must be before 'to'");
+        }
+    }
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_AUDIT_LIMIT)
+        .clamp(1, MAX_AUDIT_LIMIT);
+
+    match db
+        .task_event_dao
+        .find_between(
+            params.from,
+            params.to,
+            params.actor.as_deref(),
+            &lists,
+            limit,
+        )
+        .await
+    {
+        Ok(events) => send_events(events),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+async fn find_current(db: &DbClient, id: ::uuid::Uuid) -> Result<Option<Task>, HttpResponse> {
+    match db.task_dao.find_id(id).await {
+        Ok(task) => Ok(Some(task)),
+        Err(Error::NotFound(_)) => Ok(None),
+        Err(err) => Err(HttpResponse::InternalServerError().body(err.to_string())),
+    }
+}
+
+/// Restores the `versions` and deletes the `deletions`, recording them as
+/// `mutation` in the same transaction. Otherwise a failure to record an undo
+/// would leave the undone mutation to be undone a second time.
+async fn apply(
+    db: &DbClient,
+    mutation: &Mutation,
+    current: &HashMap<::uuid::Uuid, Option<Task>>,
+    versions: Vec<Task>,
+    deletions: &[::uuid::Uuid],
+    first_touched: ::uuid::Uuid,
+) -> Result<Vec<(TaskEvent, Task)>, Error> {
+    let txn = db.task_dao.begin().await?;
+    let (restored, detached) = db
+        .task_dao
+        .restore(&txn, versions, deletions.to_vec())
+        .await?;
+
+    // Subtasks of deleted tasks, which are taken for restored as well
+    let mut changes: Vec<(Option<Task>, Task)> = restored
+        .into_iter()
+        .map(|task| (current.get(&task.id).cloned().flatten(), task))
+        .collect();
+    for (subtask, updated_task) in detached {
+        match changes.iter_mut().find(|(_, task)| task.id == subtask.id) {
+            Some((_, task)) => *task = updated_task,
+            None => changes.push((Some(subtask), updated_task)),
+        }
+    }
+
+    let mut records = Vec::new();
+    let mut events = Vec::with_capacity(changes.len());
+    for (before, task) in changes {
+        let event = match before {
+            Some(_) => TaskEvent::Updated,
+            None => TaskEvent::Created,
+        };
+        records.extend(audit::to_record(mutation, event, before.as_ref(), &task));
+        events.push((event, task));
+    }
+    for task in deletions
+        .iter()
+        .filter_map(|id| current.get(id).cloned().flatten())
+    {
+        records.extend(audit::to_record(mutation, TaskEvent::Deleted, None, &task));
+    }
+
+    // An undo whose changes were all overwritten since still has to be
+    // recorded, or the undone mutation would keep coming up
+    if records.is_empty() && mutation.reverts.is_some() {
+        records.push(audit::unchanged_record(mutation, first_touched));
+    }
+
+    db.task_event_dao.create_many(&txn, records).await?;
+    txn.commit().await?;
+    Ok(events)
+}
+
+/// Moves tasks from their `current` to their `target` versions as one
+/// mutation, checking that `principal` may make every single change.
+async fn restore(
+    db: &DbClient,
+    bus: &EventBus,
+    principal: &Principal,
+    mutation: Mutation,
+    mut current: HashMap<::uuid::Uuid, Option<Task>>,
+    target: HashMap<::uuid::Uuid, Option<Task>>,
+) -> Result<RevertResult, HttpResponse> {
+    let mut versions = Vec::new();
+    let mut deletions = Vec::new();
+    let touched: Vec<::uuid::Uuid> = target.keys().copied().collect();
+    for (id, version) in target {
+        let task = current.get(&id).cloned().flatten();
+        match (&task, &version) {
+            (Some(task), Some(version)) if task == version => continue,
+            (Some(_), None) => require_scope(principal, Scope::TasksDelete)?,
+            _ => {}
+        }
+
+        for list_id in [&task, &version]
+            .into_iter()
+            .flatten()
+            .filter_map(|task| task.list_id)
+        {
+            require_role(db, principal, list_id, Role::Editor).await?;
+        }
+
+        match version {
+            Some(version) => versions.push(version),
+            None if task.is_some() => deletions.push(id),
+            None => {}
+        }
+    }
+
+    let restored = match apply(db, &mutation, &current, versions, &deletions, touched[0]).await {
+        Ok(restored) => restored,
+        Err(err) => return Err(HttpResponse::InternalServerError().body(err.to_string())),
+    };
+
+    for (event, task) in &restored {
+        events::announce(db, bus, *event, task).await;
+    }
+    for id in &deletions {
+        if let Some(task) = current.remove(id).flatten() {
+            events::announce(db, bus, TaskEvent::Deleted, &task).await;
+        }
+    }
+    if !deletions.is_empty() {
+        attachment::purge_detached(db).await;
+    }
+
+    let recreated = restored
+        .iter()
+        .any(|(event, _)| *event == TaskEvent::Created);
+    Ok(RevertResult {
+        mutation_id: mutation.id,
+        restored: restored
+            .into_iter()
+            .map(|(_, task)| PublicTask::from(task))
+            .collect(),
+        deleted: deletions,
+        not_restored: if recreated {
+            NOT_RESTORED.to_vec()
+        } else {
+            Vec::new()
+        },
+    })
+}
+
+#[derive(Deserialize)]
+pub struct RevertQueryParams {
+    to: String,
+}
+
+/// Brings a task back to how it was right after one of its events,
+/// recreating it if it was deleted since.
+#[post("/todo/{task_id}/revert")]
+pub async fn revert_task(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    path: Path<TaskIdentifier>,
+    params: Query<RevertQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let (id, event_id) = match (uuid::parse_str(&path.task_id), uuid::parse_str(&params.to)) {
+        (Ok(id), Ok(event_id)) => (id, event_id),
+        (Err(err), _) | (_, Err(err)) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+
+    let history = match db.task_event_dao.find_for_task(id).await {
+        Ok(history) => history,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    let current = match find_current(&db, id).await {
+        Ok(current) => current,
+        Err(res) => return res,
+    };
+
+    let target = match audit::state_after(current.as_ref(), &history, event_id) {
+        Ok(Some(target)) => target,
+        Ok(None) => {
+            return HttpResponse::BadRequest()
+                .body("Cannot revert to a deletion, use an earlier event instead")
+        }
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+
+    match restore(
+        &db,
+        &bus,
+        &principal,
+        Mutation::new(&principal),
+        HashMap::from([(id, current)]),
+        HashMap::from([(id, Some(target))]),
+    )
+    .await
+    {
+        Ok(result) => HttpResponse::Ok().json(result),
+        Err(res) => res,
+    }
+}
+
+/// Reverses the caller's most recent mutation that was not undone yet, such
+/// as an update or clearing a whole list. Repeating it goes further back.
+#[post("/undo")]
+pub async fn undo(db: Data<DbClient>, bus: Data<EventBus>, req: HttpRequest) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let events = match db
+        .task_event_dao
+        .find_last_undoable(&principal.subject)
+        .await
+    {
+        Ok(events) => events,
+        Err(Error::NotFound(_)) => return HttpResponse::NotFound().body("Nothing to undo"),
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+
+    let mut current = HashMap::new();
+    for event in &events {
+        if let Entry::Vacant(entry) = current.entry(event.task_id) {
+            match find_current(&db, event.task_id).await {
+                Ok(task) => entry.insert(task),
+                Err(res) => return res,
+            };
+        }
+    }
+
+    let target = match audit::undo(&events, current.clone()) {
+        Ok(target) => target,
+        Err(err) => return HttpResponse::InternalServerError().body(err),
+    };
+
+    match restore(
+        &db,
+        &bus,
+        &principal,
+        Mutation::undo(&principal, events[0].mutation_id),
+        current,
+        target,
+    )
+    .await
+    {
+        Ok(result) => HttpResponse::Ok().json(result),
+        Err(res) => res,
+    }
+}
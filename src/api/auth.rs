@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+
+use crate::database::DbClient;
+use crate::lib::auth::{self, hash_token, Principal, Scope};
+use crate::lib::errors::Error;
+use crate::lib::query::ListFilter;
+use entity::list_member::Role;
+
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse};
+use uuid::Uuid;
+
+fn bearer_token(req: &HttpRequest) -> Result<Option<String>, Error> {
+    match req.headers().get(header::AUTHORIZATION) {
+        None => Ok(None),
+        Some(value) => {
+            let value = value
+                .to_str()
+                .map_err(|_| Error::Unauthorized("Malformed Authorization header".to_string()))?;
+            match value.strip_prefix("Bearer ") {
+                Some(token) => Ok(Some(token.trim().to_string())),
+                None => Err(Error::Unauthorized("Expected a Bearer token".to_string())),
+            }
+        }
+    }
+}
+
+async fn resolve_principal(db: &DbClient, token: &str) -> Result<Principal, Error> {
+    let token_hash = hash_token(token);
+
+    if let Ok(admin_token) = std::env::var("ADMIN_TOKEN") {
+        if !admin_token.is_empty() && hash_token(&admin_token) == token_hash {
+            return Ok(Principal::admin());
+        }
+    }
+
+    let token = match db.access_token_dao.find_active_by_hash(&token_hash).await {
+        Ok(token) => token,
+        Err(Error::NotFound(_)) => {
+            return Err(Error::Unauthorized("Invalid or revoked token".to_string()))
+        }
+        Err(err) => return Err(err),
+    };
+
+    let scopes = token
+        .scopes
+        .split_whitespace()
+        .filter_map(|scope| scope.parse().ok())
+        .collect();
+    let subject = token.owner.clone();
+
+    if let Err(err) = db.access_token_dao.touch(token).await {
+        warn!("Failed to record Access Token usage: {}", err);
+    }
+
+    Ok(Principal {
+        subject,
+        scopes,
+        admin: false,
+    })
+}
+
+async fn establish(db: &DbClient, req: &HttpRequest, scope: Scope) -> Result<Principal, Error> {
+    let principal = match bearer_token(req)? {
+        Some(token) => resolve_principal(db, &token).await?,
+        None if auth::auth_required() => {
+            return Err(Error::Unauthorized("Missing Bearer token".to_string()))
+        }
+        None => Principal::anonymous(),
+    };
+
+    check_scope(principal, scope)
+}
+
+fn check_scope(principal: Principal, scope: Scope) -> Result<Principal, Error> {
+    if principal.has_scope(scope) {
+        Ok(principal)
+    } else {
+        info!("{} is missing scope `{}`", principal.subject, scope);
+        Err(Error::Forbidden(format!("Scope `{}` is required", scope)))
+    }
+}
+
+fn reject(err: Error) -> HttpResponse {
+    match err {
+        Error::Unauthorized(_) => HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .body(err.to_string()),
+        Error::Forbidden(_) => HttpResponse::Forbidden().body(err.to_string()),
+        err => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+/// Establishes the caller of `req` and checks that it was granted `scope`.
+///
+/// Requests without credentials are let through as [`Principal::anonymous`]
+/// unless `AUTH_REQUIRED` is set.
+pub async fn authorize(
+    db: &DbClient,
+    req: &HttpRequest,
+    scope: Scope,
+) -> Result<Principal, HttpResponse> {
+    establish(db, req, scope).await.map_err(reject)
+}
+
+/// Establishes the caller of `req` and checks that it presented the
+/// `ADMIN_TOKEN`, for operations spanning every user's data.
+pub async fn authorize_admin(db: &DbClient, req: &HttpRequest) -> Result<Principal, HttpResponse> {
+    let principal = match bearer_token(req).map_err(reject)? {
+        Some(token) => resolve_principal(db, &token).await.map_err(reject)?,
+        None => {
+            return Err(reject(Error::Unauthorized(
+                "Missing Bearer token".to_string(),
+            )))
+        }
+    };
+
+    if principal.admin {
+        Ok(principal)
+    } else {
+        info!("{} is not the admin", principal.subject);
+        Err(reject(Error::Forbidden(
+            "The admin token is required".to_string(),
+        )))
+    }
+}
+
+/// Establishes the caller of a feed that clients subscribe to by URL, such
+/// as calendar apps which cannot send headers.
+///
+/// A `token` taken from the URL must hold `calendar:read` and nothing else,
+/// so that a leaked URL does not grant anything more. Otherwise the request
+/// is authorized like any other with `tasks:read`.
+pub async fn authorize_feed(
+    db: &DbClient,
+    req: &HttpRequest,
+    token: Option<&str>,
+) -> Result<Principal, HttpResponse> {
+    match token {
+        Some(token) => match resolve_principal(db, token).await {
+            Ok(principal) if principal.scopes == HashSet::from([Scope::CalendarRead]) => {
+                Ok(principal)
+            }
+            Ok(principal) => {
+                info!(
+                    "{} passed a token beyond `calendar:read` in a URL",
+                    principal.subject
+                );
+                Err(reject(Error::Forbidden(format!(
+                    "Tokens in a URL must hold `{}` only",
+                    Scope::CalendarRead
+                ))))
+            }
+            Err(err) => Err(reject(err)),
+        },
+        None => authorize(db, req, Scope::TasksRead).await,
+    }
+}
+
+/// Checks the scope of an already established `principal`, e.g. for every
+/// message of a long lived connection.
+pub fn require_scope(principal: &Principal, scope: Scope) -> Result<(), HttpResponse> {
+    if principal.has_scope(scope) {
+        Ok(())
+    } else {
+        Err(HttpResponse::Forbidden().body(format!("Scope `{}` is required", scope)))
+    }
+}
+
+/// Checks that `principal` holds at least `role` in the list and returns
+/// the role it actually holds.
+pub async fn require_role(
+    db: &DbClient,
+    principal: &Principal,
+    list_id: Uuid,
+    role: Role,
+) -> Result<Role, HttpResponse> {
+    if principal.admin {
+        return Ok(Role::Owner);
+    }
+
+    match db
+        .list_member_dao
+        .find_role(list_id, &principal.subject)
+        .await
+    {
+        Ok(Some(held)) if held >= role => Ok(held),
+        Ok(Some(held)) => {
+            info!(
+                "{} holds {:?} on List <id: {}>, {:?} required",
+                principal.subject, held, list_id, role
+            );
+            Err(HttpResponse::Forbidden().body(
+                Error::Forbidden(format!(
+                    "{:?} role on List <id: {}> is required",
+                    role, list_id
+                ))
+                .to_string(),
+            ))
+        }
+        // Lists the caller is not a member of are indistinguishable from missing ones
+        Ok(None) => Err(HttpResponse::NotFound().body(format!(
+            "Not Found: {}",
+            Error::NotFound(format!("List <id: {}>", list_id))
+        ))),
+        Err(err) => Err(HttpResponse::InternalServerError().body(err.to_string())),
+    }
+}
+
+/// Lists in which `principal` holds at least `role`, as a filter for `TaskDao`.
+pub async fn lists_with_role(
+    db: &DbClient,
+    principal: &Principal,
+    role: Role,
+) -> Result<ListFilter, HttpResponse> {
+    if principal.admin {
+        return Ok(ListFilter::All);
+    }
+
+    match db
+        .list_member_dao
+        .find_for_member(&principal.subject, role)
+        .await
+    {
+        Ok(memberships) => Ok(ListFilter::Accessible(
+            memberships.into_iter().map(|m| m.list_id).collect(),
+        )),
+        Err(err) => Err(HttpResponse::InternalServerError().body(err.to_string())),
+    }
+}
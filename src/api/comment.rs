@@ -0,0 +1,204 @@
+use crate::api::auth::authorize;
+use crate::api::task::find_task;
+use crate::database::DbClient;
+use crate::lib::auth::{Principal, Scope};
+use crate::lib::{errors::Error, uuid};
+use crate::model::comment::{NewComment, PublicComment};
+use crate::model::results::RowsAffected;
+use entity::comment::Model as Comment;
+use entity::list_member::Role;
+
+use actix_web::{
+    delete, get, post, put,
+    web::{Data, Json, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use serde::Deserialize;
+
+const DEFAULT_COMMENT_LIMIT: u64 = 50;
+const MAX_COMMENT_LIMIT: u64 = 200;
+
+pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
+    app.service(get_comments)
+        .service(create_comment)
+        .service(update_comment)
+        .service(delete_comment);
+}
+
+/// Longest comment, in bytes.
+fn max_bytes() -> usize {
+    std::env::var("COMMENT_MAX_BYTES")
+        .ok()
+        .and_then(|size| size.parse::<usize>().ok())
+        .unwrap_or(16 * 1024)
+}
+
+/// The trimmed body, unless it is empty or too long.
+fn validate_body(body: &str) -> Result<String, String> {
+    let body = body.trim();
+    let max_bytes = max_bytes();
+    if body.is_empty() {
+        return Err("Comments must not be empty".to_string());
+    }
+    if body.len() > max_bytes {
+        return Err(format!("Comments are limited to {} bytes", max_bytes));
+    }
+    Ok(body.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct TaskIdentifier {
+    task_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct CommentIdentifier {
+    task_id: String,
+    comment_id: String,
+}
+
+/// The comment at `path`, as long as it belongs to the task of `path` and,
+/// if `author_only`, was written by `principal`.
+async fn find_comment(
+    db: &DbClient,
+    principal: &Principal,
+    path: &CommentIdentifier,
+    author_only: bool,
+) -> Result<Comment, HttpResponse> {
+    let task = find_task(db, principal, &path.task_id, Role::Viewer).await?;
+    let id = uuid::parse_str(&path.comment_id)
+        .map_err(|err| HttpResponse::BadRequest().body(err.to_string()))?;
+
+    let comment = match db.comment_dao.find_id(id).await {
+        Ok(comment) if comment.task_id == task.id => comment,
+        Ok(_) | Err(Error::NotFound(_)) => {
+            return Err(HttpResponse::NotFound().body(format!("Not Found: Comment <id: {}>", id)))
+        }
+        Err(err) => return Err(HttpResponse::InternalServerError().body(err.to_string())),
+    };
+    if author_only && comment.author != principal.subject {
+        return Err(HttpResponse::Forbidden().body("Only the author may edit or delete a comment"));
+    }
+    Ok(comment)
+}
+
+#[derive(Deserialize)]
+pub struct CommentQueryParams {
+    limit: Option<u64>,
+
+    #[serde(default)]
+    offset: u64,
+}
+
+/// A page of the task's comments, oldest first.
+#[get("/todo/{task_id}/comments")]
+pub async fn get_comments(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<TaskIdentifier>,
+    params: Query<CommentQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let task = match find_task(&db, &principal, &path.task_id, Role::Viewer).await {
+        Ok(task) => task,
+        Err(res) => return res,
+    };
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_COMMENT_LIMIT)
+        .clamp(1, MAX_COMMENT_LIMIT);
+
+    match db
+        .comment_dao
+        .find_for_task(task.id, limit, params.offset)
+        .await
+    {
+        Ok(comments) => {
+            let public_comments: Vec<PublicComment> =
+                comments.into_iter().map(PublicComment::from).collect();
+            HttpResponse::Ok().json(public_comments)
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+/// Comments on a task. Viewers of its list may comment too, since that
+/// leaves the task itself unchanged.
+#[post("/todo/{task_id}/comments")]
+pub async fn create_comment(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<TaskIdentifier>,
+    new_comment: Json<NewComment>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let task = match find_task(&db, &principal, &path.task_id, Role::Viewer).await {
+        Ok(task) => task,
+        Err(res) => return res,
+    };
+    let body = match validate_body(&new_comment.body) {
+        Ok(body) => body,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+
+    let comment = Comment::new(task.id, principal.subject.clone(), body);
+    match db.comment_dao.create(comment).await {
+        Ok(comment) => HttpResponse::Created().json(PublicComment::from(comment)),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[put("/todo/{task_id}/comments/{comment_id}")]
+pub async fn update_comment(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<CommentIdentifier>,
+    new_comment: Json<NewComment>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let comment = match find_comment(&db, &principal, &path, true).await {
+        Ok(comment) => comment,
+        Err(res) => return res,
+    };
+    let body = match validate_body(&new_comment.body) {
+        Ok(body) => body,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+
+    match db.comment_dao.update_body(comment, body).await {
+        Ok(comment) => HttpResponse::Ok().json(PublicComment::from(comment)),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+/// Deletes a comment, which only its author and admins may do.
+#[delete("/todo/{task_id}/comments/{comment_id}")]
+pub async fn delete_comment(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<CommentIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksDelete).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let comment = match find_comment(&db, &principal, &path, !principal.admin).await {
+        Ok(comment) => comment,
+        Err(res) => return res,
+    };
+
+    match db.comment_dao.delete_id(comment.id).await {
+        Ok(status) => HttpResponse::Ok().json(RowsAffected::from(status)),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
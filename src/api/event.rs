@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::api::auth::authorize;
+use crate::api::task::{parse_date_range, resolve_task_filter, GetAllQueryParams};
+use crate::database::DbClient;
+use crate::events::bus::{ChangeEvent, EventBus, Replay};
+use crate::lib::auth::Scope;
+use crate::lib::query::{DateRange, TaskFilter};
+use crate::model::task::PublicTask;
+
+use actix_web::{
+    get,
+    web::{Bytes, Data, Query},
+    HttpRequest, HttpResponse,
+};
+use futures::StreamExt;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Comments sent in between events, so proxies keep idle streams open and
+/// closed connections are noticed.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Milliseconds a client waits before reconnecting.
+const RETRY_MS: u64 = 3000;
+
+pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
+    app.service(stream_task_events);
+}
+
+fn format_event(change: &ChangeEvent) -> Bytes {
+    let data = serde_json::to_string(&PublicTask::from(change.task.clone()))
+        .expect("task is serializable");
+    Bytes::from(format!(
+        "id: {}\nevent: {}\ndata: {}\n\n",
+        change.id, change.event, data
+    ))
+}
+
+/// Tells the client that events were missed and it has to fetch the tasks
+/// again, e.g. after a restart or falling too far behind.
+fn format_resync() -> Bytes {
+    Bytes::from_static(b"event: resync\ndata: {}\n\n")
+}
+
+struct Subscription {
+    replay: VecDeque<Arc<ChangeEvent>>,
+    receiver: broadcast::Receiver<Arc<ChangeEvent>>,
+    keep_alive: tokio::time::Interval,
+    filter: TaskFilter,
+    date_range: Option<DateRange>,
+    /// Id of the last event passed on, to skip replayed ones when they
+    /// arrive again through the receiver.
+    last_id: u64,
+}
+
+impl Subscription {
+    fn matches(&self, change: &ChangeEvent) -> bool {
+        self.filter.matches(&change.task)
+            && self
+                .date_range
+                .as_ref()
+                .is_none_or(|range| range.contains(&change.task))
+    }
+
+    /// Waits for the next frame to send, or `None` once the bus is gone.
+    async fn next_frame(&mut self) -> Option<Bytes> {
+        loop {
+            let change = match self.replay.pop_front() {
+                Some(change) => change,
+                None => tokio::select! {
+                    received = self.receiver.recv() => match received {
+                        Ok(change) => change,
+                        Err(RecvError::Lagged(skipped)) => {
+                            debug!("Event stream lagged behind by {} events", skipped);
+                            return Some(format_resync());
+                        }
+                        Err(RecvError::Closed) => return None,
+                    },
+                    _ = self.keep_alive.tick() => return Some(Bytes::from_static(b": keep-alive\n\n")),
+                },
+            };
+
+            if change.id <= self.last_id {
+                continue;
+            }
+            self.last_id = change.id;
+            if self.matches(&change) {
+                return Some(format_event(&change));
+            }
+        }
+    }
+}
+
+/// Streams changes to the tasks matching the same filters as `GET /todo`,
+/// resuming after the `Last-Event-ID` header if it is still in the replay buffer.
+#[get("/todo/events")]
+pub async fn stream_task_events(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    params: Query<GetAllQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let filter = match resolve_task_filter(&db, &principal, &params).await {
+        Ok(filter) => filter,
+        Err(res) => return res,
+    };
+    let date_range = match parse_date_range(&params) {
+        Ok(date_range) => date_range,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+
+    let last_event_id = match req.headers().get("Last-Event-ID") {
+        None => None,
+        Some(header) => match header.to_str().ok().and_then(|id| id.trim().parse().ok()) {
+            Some(id) => Some(id),
+            None => return HttpResponse::BadRequest().body("Invalid 'Last-Event-ID'"),
+        },
+    };
+
+    let (replay, receiver) = bus.subscribe(last_event_id);
+    let mut prelude = format!("retry: {}\n\n", RETRY_MS).into_bytes();
+    let (replay, last_id) = match replay {
+        Replay::Events(events) => (events.into(), last_event_id.unwrap_or(0)),
+        Replay::Gone => {
+            prelude.extend_from_slice(&format_resync());
+            (VecDeque::new(), 0)
+        }
+    };
+
+    let mut keep_alive = tokio::time::interval(KEEP_ALIVE_INTERVAL);
+    keep_alive.reset();
+
+    let subscription = Subscription {
+        replay,
+        receiver,
+        keep_alive,
+        filter,
+        date_range,
+        last_id,
+    };
+
+    let prelude =
+        futures::stream::once(async move { Ok::<_, actix_web::Error>(Bytes::from(prelude)) });
+    let events = futures::stream::unfold(subscription, |mut subscription| async move {
+        let frame = subscription.next_frame().await?;
+        Some((Ok(frame), subscription))
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(prelude.chain(events))
+}
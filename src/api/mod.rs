@@ -1 +1,14 @@
+pub mod admin;
+pub mod attachment;
+pub mod audit;
+pub mod auth;
+pub mod comment;
+pub mod event;
+pub mod quick;
+pub mod stats;
 pub mod task;
+pub mod task_list;
+pub mod token;
+pub mod transfer;
+pub mod webhook;
+pub mod ws;
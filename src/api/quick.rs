@@ -0,0 +1,99 @@
+use crate::api::auth::{authorize, require_role};
+use crate::api::task::{create, validate_recurrence};
+use crate::api::transfer::editable_projects;
+use crate::database::DbClient;
+use crate::events::bus::EventBus;
+use crate::lib::auth::Scope;
+use crate::lib::quick;
+use crate::lib::recurrence::parse_timezone;
+use crate::lib::time::get_current_time;
+use crate::model::quick::{Interpretation, QuickTask, QuickTaskResult};
+use crate::model::task::{NewTask, PublicTask};
+use entity::list_member::Role;
+
+use actix_web::{
+    post,
+    web::{Data, Json, Query},
+    HttpRequest, HttpResponse,
+};
+use serde::Deserialize;
+
+pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
+    app.service(create_quick_task);
+}
+
+#[derive(Deserialize)]
+pub struct QuickQueryParams {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Creates a task from a line of text such as `Pay rent tomorrow 9am #home
+/// !high every month`, reporting how the text was understood.
+#[post("/todo/quick")]
+pub async fn create_quick_task(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    params: Query<QuickQueryParams>,
+    quick_task: Json<QuickTask>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let timezone = quick_task.timezone.as_deref().unwrap_or("UTC");
+    let tz = match parse_timezone(timezone) {
+        Ok(tz) => tz,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    let projects = match editable_projects(&db, &principal).await {
+        Ok(projects) => projects,
+        Err(res) => return res,
+    };
+
+    let parsed = match quick::parse(&quick_task.text, get_current_time(), &tz, &projects) {
+        Ok(parsed) => parsed,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    let interpretation = Interpretation {
+        title: parsed.task.title.clone(),
+        timezone: timezone.to_string(),
+        tokens: parsed.tokens,
+    };
+    let mut new_task = NewTask {
+        task: parsed.task,
+        reminders: Vec::new(),
+    };
+    new_task.task.list_id = new_task.task.list_id.or(quick_task.list_id);
+
+    let task = if params.dry_run {
+        // The same checks as creating it, short of saving
+        if let Some(list_id) = new_task.task.list_id {
+            if let Err(res) = require_role(&db, &principal, list_id, Role::Editor).await {
+                return res;
+            }
+        }
+        let mut task = new_task.task;
+        if let Err(err) = validate_recurrence(&mut task) {
+            return HttpResponse::BadRequest().body(err);
+        }
+        task
+    } else {
+        match create(&db, &bus, &principal, &new_task).await {
+            Ok(task) => task,
+            Err(res) => return res,
+        }
+    };
+
+    let result = QuickTaskResult {
+        dry_run: params.dry_run,
+        task: PublicTask::from(task),
+        interpretation,
+    };
+    if params.dry_run {
+        HttpResponse::Ok().json(result)
+    } else {
+        HttpResponse::Created().json(result)
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use crate::api::auth::authorize;
+use crate::api::task::resolve_list_filter;
+use crate::database::DbClient;
+use crate::lib::auth::Scope;
+use crate::lib::query::{Bucket, TaskFilter};
+use crate::lib::recurrence::parse_timezone;
+use crate::model::stats::{Stats, StatsBucket};
+use entity::list_member::Role;
+use entity::task;
+
+use actix_web::{
+    get,
+    web::{Data, Query},
+    HttpRequest, HttpResponse,
+};
+use chrono::serde::ts_milliseconds_option;
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+const DEFAULT_STATS_DAYS: i64 = 30;
+const MAX_STATS_BUCKETS: i64 = 1000;
+
+pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
+    app.service(get_stats);
+}
+
+#[derive(Deserialize)]
+pub struct StatsQueryParams {
+    /// Inclusive, 30 days before `to` if unset
+    #[serde(with = "ts_milliseconds_option", default)]
+    from: Option<DateTime<Utc>>,
+
+    /// Exclusive, now if unset
+    #[serde(with = "ts_milliseconds_option", default)]
+    to: Option<DateTime<Utc>>,
+
+    /// `day` or `week`
+    bucket: Option<String>,
+
+    /// Timezone the buckets start at midnight in, UTC if unset
+    tz: Option<String>,
+
+    list: Option<String>,
+}
+
+/// Task counts of the caller's lists, and how many tasks were created and
+/// completed per day or week.
+#[get("/stats")]
+pub async fn get_stats(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    params: Query<StatsQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let bucket = match params.bucket.as_deref().map(str::parse::<Bucket>) {
+        Some(Ok(bucket)) => bucket,
+        Some(Err(err)) => return HttpResponse::BadRequest().body(err),
+        None => Bucket::Day,
+    };
+    let tz = match params.tz.as_deref().map(parse_timezone) {
+        Some(Ok(tz)) => tz,
+        Some(Err(err)) => return HttpResponse::BadRequest().body(err),
+        None => Tz::UTC,
+    };
+
+    let now = Utc::now();
+    let to = params.to.unwrap_or(now);
+    let from = params
+        .from
+        .unwrap_or_else(|| to - Duration::days(DEFAULT_STATS_DAYS));
+    if from >= to {
+        return HttpResponse::BadRequest().body("'from' must be before 'to'");
+    }
+    let bucket_days = match bucket {
+        Bucket::Day => 1,
+        Bucket::Week => 7,
+    };
+    if (to - from).num_days() / bucket_days >= MAX_STATS_BUCKETS {
+        return HttpResponse::BadRequest().body(format!(
+            "At most {} buckets are allowed. Use a shorter range or a larger bucket",
+            MAX_STATS_BUCKETS
+        ));
+    }
+
+    let filter = match resolve_list_filter(&db, &principal, &params.list, Role::Viewer).await {
+        Ok(lists) => TaskFilter::new(lists),
+        Err(res) => return res,
+    };
+
+    let counts = match db.stats_dao.count_by_state(&filter, now).await {
+        Ok(counts) => counts,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    let created = db
+        .stats_dao
+        .count_per_bucket(task::Column::CreatedAt, bucket, &tz, from, to, &filter)
+        .await;
+    let completed = db
+        .stats_dao
+        .count_per_bucket(task::Column::CompletedAt, bucket, &tz, from, to, &filter)
+        .await;
+    let average_completion_seconds = db
+        .stats_dao
+        .average_completion_seconds(from, to, &filter)
+        .await;
+    let (created, completed, average_completion_seconds) =
+        match (created, completed, average_completion_seconds) {
+            (Ok(created), Ok(completed), Ok(average)) => (created, completed, average),
+            (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+                return HttpResponse::InternalServerError().body(err.to_string())
+            }
+        };
+
+    // Empty buckets are not returned by the database
+    let mut buckets: BTreeMap<DateTime<Utc>, StatsBucket> = bucket
+        .starts(from, to, &tz)
+        .into_iter()
+        .map(|start| {
+            (
+                start,
+                StatsBucket {
+                    start,
+                    ..StatsBucket::default()
+                },
+            )
+        })
+        .collect();
+    for count in created {
+        buckets
+            .entry(count.bucket)
+            .or_insert_with(|| StatsBucket {
+                start: count.bucket,
+                ..StatsBucket::default()
+            })
+            .created = count.count;
+    }
+    for count in completed {
+        buckets
+            .entry(count.bucket)
+            .or_insert_with(|| StatsBucket {
+                start: count.bucket,
+                ..StatsBucket::default()
+            })
+            .completed = count.count;
+    }
+
+    HttpResponse::Ok().json(Stats {
+        from,
+        to,
+        bucket: bucket.as_str().to_string(),
+        timezone: tz.name().to_string(),
+        open: counts.open,
+        completed: counts.completed,
+        overdue: counts.overdue,
+        average_completion_seconds,
+        buckets: buckets.into_values().collect(),
+    })
+}
@@ -1,40 +1,202 @@
+use crate::api::attachment;
+use crate::api::auth::{authorize, lists_with_role, require_role};
 use crate::database::DbClient;
+use crate::events::{self, bus::EventBus, Mutation, TaskEvent};
+use crate::lib::auth::{Principal, Scope};
+use crate::lib::notes::{self, Rendering};
+use crate::lib::recurrence::{parse_timezone, RRule};
 use crate::lib::{errors::Error, query, uuid};
+use crate::model::reminder::PublicReminder;
 use crate::model::results::RowsAffected;
-use crate::model::task::{OptionalTask, PublicTask};
+use crate::model::task::{MoveTask, NewTask, OptionalTask, PublicTask};
+use entity::list_member::Role;
+use entity::sea_orm_active_enums::Priority;
 use entity::task::Model as Task;
 
+use ::uuid::Uuid;
 use actix_web::web::Query;
 use actix_web::{
     delete, get, post, put,
     web::{Data, Json, Path},
-    HttpResponse,
+    HttpRequest, HttpResponse,
 };
 use chrono::serde::ts_milliseconds_option;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use sea_orm::{ActiveEnum, DeleteResult};
 use serde::Deserialize;
 
+/// Longest time a reminder may come before its deadline, 366 days.
+const MAX_REMINDER_OFFSET_MS: i64 = 366 * 24 * 60 * 60 * 1000;
+
 pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
     app.service(create_task)
         .service(get_task)
         .service(get_all_tasks)
         .service(update_task)
+        .service(update_future_occurrences)
+        .service(move_task)
+        .service(get_task_reminders)
         .service(delete_task)
         .service(delete_all_tasks);
 }
 
+#[derive(Deserialize)]
+pub struct RenderQueryParams {
+    /// `html` adds `notesHtml` to the task
+    render: Option<String>,
+}
+
+/// The renderings of the notes asked for with `render`.
+fn parse_rendering(render: &Option<String>) -> Result<Option<Rendering>, HttpResponse> {
+    notes::parse_rendering(render).map_err(|err| HttpResponse::BadRequest().body(err))
+}
+
 #[post("/todo")]
-pub async fn create_task(db: Data<DbClient>, new_task: Json<Task>) -> HttpResponse {
-    let task_to_insert = Task::new(
-        new_task.title.clone(),
-        new_task.completed,
-        new_task.deadline,
+pub async fn create_task(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    params: Query<RenderQueryParams>,
+    new_task: Json<NewTask>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let rendering = match parse_rendering(&params.render) {
+        Ok(rendering) => rendering,
+        Err(res) => return res,
+    };
+
+    match create(&db, &bus, &principal, &new_task).await {
+        Ok(task) => HttpResponse::Created().json(PublicTask::from(task).rendered(rendering)),
+        Err(res) => res,
+    }
+}
+
+/// Creates a task on behalf of `principal`, shared by `POST /todo` and the
+/// WebSocket channel.
+pub(crate) async fn create(
+    db: &DbClient,
+    bus: &EventBus,
+    principal: &Principal,
+    new_task: &NewTask,
+) -> Result<Task, HttpResponse> {
+    if let Some(list_id) = new_task.task.list_id {
+        require_role(db, principal, list_id, Role::Editor).await?;
+    }
+
+    let mut task_to_insert = Task::new(
+        new_task.task.title.clone(),
+        new_task.task.completed,
+        new_task.task.deadline,
     );
-    let task = db.task_dao.create(task_to_insert).await;
+    task_to_insert.list_id = new_task.task.list_id;
+    task_to_insert.priority = new_task.task.priority;
+    task_to_insert.rrule = new_task.task.rrule.clone();
+    task_to_insert.timezone = new_task.task.timezone.clone();
+    task_to_insert.notes = new_task.task.notes.clone();
+    if let Err(err) = notes::validate(task_to_insert.notes.as_deref()) {
+        return Err(HttpResponse::BadRequest().body(err));
+    }
+    if let Some(parent_id) = new_task.task.parent_id {
+        match db.task_dao.find_id(parent_id).await {
+            Ok(parent) if parent.list_id == task_to_insert.list_id => {}
+            Ok(_) => {
+                return Err(
+                    HttpResponse::BadRequest().body("A subtask must be in the list of its parent")
+                )
+            }
+            Err(Error::NotFound(_)) => {
+                return Err(HttpResponse::BadRequest()
+                    .body(format!("Parent Task <id: {}> not found", parent_id)))
+            }
+            Err(err) => return Err(HttpResponse::InternalServerError().body(err.to_string())),
+        }
+        task_to_insert.parent_id = Some(parent_id);
+    }
+    if let Err(err) = validate_recurrence(&mut task_to_insert) {
+        return Err(HttpResponse::BadRequest().body(err));
+    }
+    if let Err(err) = validate_reminders(&new_task.reminders, task_to_insert.deadline) {
+        return Err(HttpResponse::BadRequest().body(err));
+    }
 
-    match task {
-        Ok(task) => HttpResponse::Created().json(PublicTask::from(task)),
-        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    let task = match db.task_dao.create(task_to_insert).await {
+        Ok(task) => task,
+        Err(err) => return Err(HttpResponse::InternalServerError().body(err.to_string())),
+    };
+    if let Err(err) = save_reminders(db, &task, &new_task.reminders).await {
+        return Err(HttpResponse::InternalServerError().body(err.to_string()));
+    }
+    events::publish(
+        db,
+        bus,
+        &Mutation::new(principal),
+        TaskEvent::Created,
+        None,
+        &task,
+    )
+    .await;
+
+    Ok(task)
+}
+
+fn validate_reminders(offsets: &[i64], deadline: Option<DateTime<Utc>>) -> Result<(), String> {
+    if offsets.is_empty() {
+        return Ok(());
+    }
+    let deadline = match deadline {
+        Some(deadline) => deadline,
+        None => return Err("Reminders require a 'deadline'".to_string()),
+    };
+    if offsets.iter().any(|offset| *offset < 0) {
+        return Err("Reminder offsets must not be negative".to_string());
+    }
+    if offsets
+        .iter()
+        .any(|offset| *offset > MAX_REMINDER_OFFSET_MS)
+    {
+        return Err(format!(
+            "Reminder offsets must not exceed {} ms (366 days)",
+            MAX_REMINDER_OFFSET_MS
+        ));
+    }
+    if offsets.iter().any(|offset| {
+        deadline
+            .checked_sub_signed(Duration::milliseconds(*offset))
+            .is_none()
+    }) {
+        return Err("Reminders must not come before the earliest supported time".to_string());
+    }
+    Ok(())
+}
+
+async fn save_reminders(db: &DbClient, task: &Task, offsets: &[i64]) -> Result<(), Error> {
+    match task.deadline {
+        Some(deadline) => db
+            .reminder_dao
+            .replace_for_task(task.id, deadline, offsets)
+            .await
+            .map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// Keeps the reminders of an updated task in line with its `deadline`.
+async fn sync_reminders(
+    db: &DbClient,
+    old_task: &Task,
+    task: &Task,
+    offsets: &Option<Vec<i64>>,
+) -> Result<(), Error> {
+    match (offsets, task.deadline) {
+        (Some(offsets), _) => save_reminders(db, task, offsets).await,
+        (None, Some(deadline)) if old_task.deadline != task.deadline => {
+            db.reminder_dao.reschedule(task.id, deadline).await
+        }
+        _ => Ok(()),
     }
 }
 
@@ -49,6 +211,20 @@ pub struct GetAllQueryParams {
 
     sort: Option<String>,
 
+    list: Option<String>,
+
+    /// Comma separated, e.g. `high,urgent`
+    priority: Option<String>,
+
+    /// `overdue`, `today`, `tomorrow`, `this_week` or `no_deadline`
+    due: Option<String>,
+
+    /// Timezone the days of `due` are evaluated in, UTC if unset
+    tz: Option<String>,
+
+    /// `html` adds `notesHtml` to the tasks
+    render: Option<String>,
+
     #[serde(
         with = "ts_milliseconds_option",
         default = "get_default_query_param_option"
@@ -74,6 +250,191 @@ pub struct GetAllQueryParams {
     end: Option<DateTime<Utc>>,
 }
 
+/// Restricts a query to `list` if given, otherwise to every list in which
+/// `principal` holds at least `role`.
+pub(crate) async fn resolve_list_filter(
+    db: &DbClient,
+    principal: &Principal,
+    list: &Option<String>,
+    role: Role,
+) -> Result<query::ListFilter, HttpResponse> {
+    match list {
+        Some(list_id) => {
+            let list_id = uuid::parse_str(list_id)
+                .map_err(|err| HttpResponse::BadRequest().body(err.to_string()))?;
+            require_role(db, principal, list_id, role).await?;
+            Ok(query::ListFilter::Only(list_id))
+        }
+        None => lists_with_role(db, principal, role).await,
+    }
+}
+
+fn parse_priorities(priorities: &Option<String>) -> Result<Vec<Priority>, String> {
+    match priorities {
+        Some(priorities) => priorities
+            .split(',')
+            .map(|priority| {
+                Priority::try_from_value(&priority.trim().to_lowercase()).map_err(|_| {
+                    format!(
+                        "Invalid priority: {}. Valid priorities are: none, low, medium, high, urgent",
+                        priority
+                    )
+                })
+            })
+            .collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn parse_due(
+    due: &Option<String>,
+    tz: &Option<String>,
+) -> Result<Option<query::DueFilter>, String> {
+    let tz = match tz {
+        Some(tz) => parse_timezone(tz)?,
+        None => Tz::UTC,
+    };
+    match due {
+        Some(due) => Ok(Some(
+            due.parse::<query::DueWindow>()?.compile(Utc::now(), &tz),
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Builds the list, priority and due filters of `params` for `principal`.
+pub(crate) async fn resolve_task_filter(
+    db: &DbClient,
+    principal: &Principal,
+    params: &GetAllQueryParams,
+) -> Result<query::TaskFilter, HttpResponse> {
+    let mut filter = query::TaskFilter::new(
+        resolve_list_filter(db, principal, &params.list, Role::Viewer).await?,
+    );
+    filter.priorities =
+        parse_priorities(&params.priority).map_err(|err| HttpResponse::BadRequest().body(err))?;
+    filter.due =
+        parse_due(&params.due, &params.tz).map_err(|err| HttpResponse::BadRequest().body(err))?;
+    Ok(filter)
+}
+
+/// The date filters of `params`, checked by the same rules as `GET /todo`.
+pub(crate) fn parse_date_range(
+    params: &GetAllQueryParams,
+) -> Result<Option<query::DateRange>, String> {
+    let attribute = match &params.attribute {
+        Some(attribute) => attribute,
+        None if params.before.is_some() || params.after.is_some() => {
+            return Err("'attrib' is required when using before or after".to_string())
+        }
+        None => return Ok(None),
+    };
+
+    match attribute.as_str() {
+        "created_at" | "deadline" | "completed_at" => {}
+        "title" | "priority" | "position" => {
+            if params.before.is_some()
+                || params.after.is_some()
+                || params.start.is_some()
+                || params.end.is_some()
+            {
+                return Err(format!(
+                    "Cannot use before, after, start, or end with {}",
+                    attribute
+                ));
+            }
+            return Ok(None);
+        }
+        _ => {
+            return Err(format!(
+            "Invalid attribute: {}. Valid attributes are: title, created_at, deadline, priority, completed_at, position",
+            attribute
+        ))
+        }
+    }
+
+    let (start, end) = match (params.before, params.after, params.start, params.end) {
+        (None, None, None, None) => return Ok(None),
+        (Some(_), Some(_), _, _) => return Err("Cannot use 'before' and 'after'".to_string()),
+        (Some(_), None, Some(_), _) | (Some(_), None, _, Some(_)) => {
+            return Err("Cannot use 'before' with 'start' or 'end'".to_string())
+        }
+        (None, Some(_), Some(_), _) | (None, Some(_), _, Some(_)) => {
+            return Err("Cannot use 'after' with 'start' or 'end'".to_string())
+        }
+        (Some(before), None, None, None) => (None, Some(before)),
+        (None, Some(after), None, None) => (Some(after), None),
+        (None, None, Some(start), Some(end)) if start > end => {
+            return Err("'start' must be before 'end'".to_string())
+        }
+        (None, None, Some(start), Some(end)) => (Some(start), Some(end)),
+        (None, None, Some(_), None) => {
+            return Err(
+                "No 'end' specified. 'start' requires 'end'. Try using 'after' instead".to_string(),
+            )
+        }
+        (None, None, None, Some(_)) => {
+            return Err(
+                "No 'start' specified. 'end' requires 'start'. Try using 'before' instead"
+                    .to_string(),
+            )
+        }
+    };
+
+    Ok(Some(query::DateRange {
+        attribute: attribute.to_string(),
+        start,
+        end,
+    }))
+}
+
+/// Checks the recurrence of `task`, making it the start of a new series
+/// when it becomes recurring.
+pub(crate) fn validate_recurrence(task: &mut Task) -> Result<(), String> {
+    if let Some(timezone) = &task.timezone {
+        parse_timezone(timezone)?;
+    }
+
+    if let Some(rrule) = &task.rrule {
+        rrule.parse::<RRule>()?;
+        if task.deadline.is_none() {
+            return Err("A recurring task requires a 'deadline'".to_string());
+        }
+        if task.series_id.is_none() {
+            task.series_id = Some(task.id);
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds the occurrence following `task`, if its recurrence has one.
+fn next_occurrence(task: &Task) -> Result<Option<Task>, String> {
+    let (rrule, deadline) = match (&task.rrule, task.deadline) {
+        (Some(rrule), Some(deadline)) => (rrule.parse::<RRule>()?, deadline),
+        _ => return Ok(None),
+    };
+    let timezone = match &task.timezone {
+        Some(timezone) => parse_timezone(timezone)?,
+        None => Tz::UTC,
+    };
+
+    Ok(rrule
+        .next_after(deadline, task.occurrence as u32, &timezone)
+        .map(|next_deadline| {
+            let mut next_task = Task::new(task.title.clone(), false, Some(next_deadline));
+            next_task.list_id = task.list_id;
+            next_task.priority = task.priority;
+            next_task.rrule = task.rrule.clone();
+            next_task.timezone = task.timezone.clone();
+            next_task.series_id = task.series_id;
+            next_task.occurrence = task.occurrence + 1;
+            next_task.parent_id = task.parent_id;
+            next_task.notes = task.notes.clone();
+            next_task
+        }))
+}
+
 fn send_data(data: Result<Vec<Task>, Error>) -> HttpResponse {
     match data {
         Ok(tasks) => {
@@ -85,8 +446,53 @@ fn send_data(data: Result<Vec<Task>, Error>) -> HttpResponse {
 }
 
 #[get("/todo")]
-pub async fn get_all_tasks(db: Data<DbClient>, params: Query<GetAllQueryParams>) -> HttpResponse {
-    let sort_attrib: String;
+pub async fn get_all_tasks(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    params: Query<GetAllQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let rendering = match parse_rendering(&params.render) {
+        Ok(rendering) => rendering,
+        Err(res) => return res,
+    };
+
+    let tasks = match find_tasks(&db, &principal, &params).await {
+        Ok(tasks) => tasks,
+        Err(res) => return res,
+    };
+    // Counted for all tasks at once
+    let ids: Vec<Uuid> = tasks.iter().map(|task| task.id).collect();
+    let comment_counts = match db.comment_dao.count_for_tasks(&ids).await {
+        Ok(comment_counts) => comment_counts,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+
+    let public_tasks: Vec<PublicTask> = tasks
+        .into_iter()
+        .map(|task| {
+            let comment_count = comment_counts.get(&task.id).copied().unwrap_or(0);
+            PublicTask::from(task)
+                .rendered(rendering)
+                .with_comment_count(comment_count)
+        })
+        .collect();
+    HttpResponse::Ok().json(public_tasks)
+}
+
+/// Runs the listing described by `params`, shared by `GET /todo` and the
+/// exports.
+pub(crate) async fn find_tasks(
+    db: &DbClient,
+    principal: &Principal,
+    params: &GetAllQueryParams,
+) -> Result<Vec<Task>, HttpResponse> {
+    let filter = resolve_task_filter(db, principal, params).await?;
+
     let sort_order: query::SortOrder;
 
     match &params.sort {
@@ -98,137 +504,68 @@ pub async fn get_all_tasks(db: Data<DbClient>, params: Query<GetAllQueryParams>)
                 sort_order = query::SortOrder::Desc;
             }
             _ => {
-                return HttpResponse::BadRequest()
-                    .body("Invalid sort order. Must be either 1 or -1")
+                return Err(
+                    HttpResponse::BadRequest().body("Invalid sort order. Must be either 1 or -1")
+                )
             }
         },
+        // The manual order reads from the top, everything else newest first
+        None if params.attribute.as_deref() == Some("position") => {
+            sort_order = query::SortOrder::Asc;
+        }
         None => {
             sort_order = query::SortOrder::Desc;
         }
     }
 
-    match &params.attribute {
-        Some(attribute) => {
-            match attribute.as_str() {
-                "title" | "created_at" | "deadline" => {
-                    sort_attrib = attribute.to_string();
-                }
-                _ => {
-                    info!("Invalid attribute: {}", attribute);
-                    return HttpResponse::BadRequest().body(format!(
-                        "Invalid attribute: {}. Valid attributes are: title, created_at, deadline",
-                        attribute
-                    ));
-                }
-            }
+    let date_range = parse_date_range(params).map_err(|err| {
+        info!("{}", err);
+        HttpResponse::BadRequest().body(err)
+    })?;
 
-            match attribute.as_str() {
-                "title" => {
-                    if params.before.is_some()
-                        || params.after.is_some()
-                        || params.start.is_some()
-                        || params.end.is_some()
-                    {
-                        return HttpResponse::BadRequest()
-                            .body("Cannot use before, after, start, or end with title");
-                    }
-                }
-                _ => {
-                    match params.before {
-                        Some(date) => {
-                            if params.after.is_some() {
-                                return HttpResponse::BadRequest()
-                                    .body("Cannot use 'before' and 'after'");
-                            }
-                            if params.start.is_some() || params.end.is_some() {
-                                return HttpResponse::BadRequest()
-                                    .body("Cannot use 'before' with 'start' or 'end'");
-                            }
-                            let data = db
-                                .task_dao
-                                .find_with_params(
-                                    attribute.to_string(),
-                                    query::FilterOps::Lte,
-                                    date,
-                                    sort_order,
-                                )
-                                .await;
-                            return send_data(data);
-                        }
-                        None => {}
-                    }
-
-                    match params.after {
-                        Some(date) => {
-                            if params.before.is_some() {
-                                return HttpResponse::BadRequest()
-                                    .body("Cannot use before and after");
-                            }
-                            if params.start.is_some() || params.end.is_some() {
-                                return HttpResponse::BadRequest()
-                                    .body("Cannot use 'after' with 'start' or 'end'");
-                            }
-                            let data = db
-                                .task_dao
-                                .find_with_params(
-                                    attribute.to_string(),
-                                    query::FilterOps::Gte,
-                                    date,
-                                    sort_order,
-                                )
-                                .await;
-                            return send_data(data);
-                        }
-                        None => {}
-                    }
-
-                    match params.start {
-                        Some(start_date) => match params.end {
-                            Some(end_date) => {
-                                if start_date > end_date {
-                                    return HttpResponse::BadRequest()
-                                        .body("'start' must be before 'end'");
-                                }
-                                let data = db
-                                    .task_dao
-                                    .find_between(
-                                        attribute.to_string(),
-                                        start_date,
-                                        end_date,
-                                        sort_order,
-                                    )
-                                    .await;
-                                return send_data(data);
-                            }
-                            None => {
-                                return HttpResponse::BadRequest().body("No 'end' specified. 'start' requires 'end'. Try using 'after' instead");
-                            }
-                        },
-                        None => {}
-                    }
-
-                    match params.end {
-                        Some(_) => {
-                            return HttpResponse::BadRequest().body("No 'start' specified. 'end' requires 'start'. Try using 'before' instead");
-                        }
-                        None => {}
-                    }
-                }
-            }
+    let data = match date_range {
+        Some(query::DateRange {
+            attribute,
+            start: Some(start),
+            end: Some(end),
+        }) => {
+            db.task_dao
+                .find_between(attribute, start, end, sort_order, &filter)
+                .await
         }
-        None => {
-            if params.before.is_some() || params.after.is_some() {
-                info!("'attrib' is required when using before or after");
-                return HttpResponse::BadRequest()
-                    .body("'attrib' is required when using before or after");
-            } else {
-                sort_attrib = "created_at".to_string();
-            }
+        Some(query::DateRange {
+            attribute,
+            start: None,
+            end: Some(before),
+        }) => {
+            db.task_dao
+                .find_with_params(
+                    attribute,
+                    query::FilterOps::Lte,
+                    before,
+                    sort_order,
+                    &filter,
+                )
+                .await
         }
-    }
-
-    let data = db.task_dao.find_all(sort_attrib, sort_order).await;
-    send_data(data)
+        Some(query::DateRange {
+            attribute,
+            start: Some(after),
+            end: None,
+        }) => {
+            db.task_dao
+                .find_with_params(attribute, query::FilterOps::Gte, after, sort_order, &filter)
+                .await
+        }
+        _ => {
+            let sort_attrib = params
+                .attribute
+                .clone()
+                .unwrap_or_else(|| "created_at".to_string());
+            db.task_dao.find_all(sort_attrib, sort_order, &filter).await
+        }
+    };
+    data.map_err(|err| HttpResponse::InternalServerError().body(err.to_string()))
 }
 
 #[derive(Deserialize)]
@@ -236,8 +573,46 @@ pub struct TaskIdentifier {
     task_id: String,
 }
 
+/// The task at `task_id`, once `principal` was found to hold `role` in its
+/// list, for the endpoints nested beneath `/todo/{task_id}`.
+pub(crate) async fn find_task(
+    db: &DbClient,
+    principal: &Principal,
+    task_id: &str,
+    role: Role,
+) -> Result<Task, HttpResponse> {
+    let id =
+        uuid::parse_str(task_id).map_err(|err| HttpResponse::BadRequest().body(err.to_string()))?;
+
+    let task = match db.task_dao.find_id(id).await {
+        Ok(task) => task,
+        Err(Error::NotFound(err)) => {
+            return Err(HttpResponse::NotFound().body(format!("Not Found: {}", err)))
+        }
+        Err(err) => return Err(HttpResponse::InternalServerError().body(err.to_string())),
+    };
+    if let Some(list_id) = task.list_id {
+        require_role(db, principal, list_id, role).await?;
+    }
+    Ok(task)
+}
+
 #[get("/todo/{task_id}")]
-pub async fn get_task(db: Data<DbClient>, path: Path<TaskIdentifier>) -> HttpResponse {
+pub async fn get_task(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<TaskIdentifier>,
+    params: Query<RenderQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let rendering = match parse_rendering(&params.render) {
+        Ok(rendering) => rendering,
+        Err(res) => return res,
+    };
+
     let id = uuid::parse_str(&path.task_id);
 
     match id {
@@ -245,7 +620,25 @@ pub async fn get_task(db: Data<DbClient>, path: Path<TaskIdentifier>) -> HttpRes
         Ok(id) => {
             let task = db.task_dao.find_id(id).await;
             match task {
-                Ok(task) => HttpResponse::Found().json(PublicTask::from(task)),
+                Ok(task) => {
+                    if let Some(list_id) = task.list_id {
+                        if let Err(res) = require_role(&db, &principal, list_id, Role::Viewer).await
+                        {
+                            return res;
+                        }
+                    }
+                    let comment_count = match db.comment_dao.count_for_tasks(&[id]).await {
+                        Ok(comment_counts) => comment_counts.get(&id).copied().unwrap_or(0),
+                        Err(err) => {
+                            return HttpResponse::InternalServerError().body(err.to_string())
+                        }
+                    };
+                    HttpResponse::Found().json(
+                        PublicTask::from(task)
+                            .rendered(rendering)
+                            .with_comment_count(comment_count),
+                    )
+                }
                 Err(Error::NotFound(err)) => {
                     HttpResponse::NotFound().body(format!("Not Found: {}", err))
                 }
@@ -258,68 +651,529 @@ pub async fn get_task(db: Data<DbClient>, path: Path<TaskIdentifier>) -> HttpRes
 #[put("/todo/{task_id}")]
 pub async fn update_task(
     db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
     path: Path<TaskIdentifier>,
+    params: Query<RenderQueryParams>,
     new_task: Json<OptionalTask>,
 ) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let rendering = match parse_rendering(&params.render) {
+        Ok(rendering) => rendering,
+        Err(res) => return res,
+    };
+
     let id = uuid::parse_str(&path.task_id);
 
     match id {
         Err(err) => HttpResponse::BadRequest().body(err.to_string()),
-        Ok(id) => {
-            let data = db.task_dao.find_id(id).await;
+        Ok(id) => match update(&db, &bus, &principal, id, &new_task).await {
+            Ok(updated_task) => {
+                HttpResponse::Ok().json(PublicTask::from(updated_task).rendered(rendering))
+            }
+            Err(res) => res,
+        },
+    }
+}
 
-            match data {
-                Err(err) => HttpResponse::NotFound().body(err.to_string()),
-                Ok(task) => {
-                    let mut new_data = Task::new(task.title.clone(), task.completed, task.deadline);
+/// Updates a task on behalf of `principal`, shared by `PUT /todo/{task_id}`
+/// and the WebSocket channel.
+pub(crate) async fn update(
+    db: &DbClient,
+    bus: &EventBus,
+    principal: &Principal,
+    id: Uuid,
+    new_task: &OptionalTask,
+) -> Result<Task, HttpResponse> {
+    let task = match db.task_dao.find_id(id).await {
+        Ok(task) => task,
+        Err(err) => return Err(HttpResponse::NotFound().body(err.to_string())),
+    };
 
-                    match new_task.task_title.clone() {
-                        Some(title) => new_data.title = title,
-                        None => (),
-                    }
-                    match new_task.task_state {
-                        Some(state) => new_data.completed = state,
-                        None => (),
-                    }
-                    match new_task.task_deadline {
-                        Some(deadline) => new_data.deadline = Option::from(deadline),
-                        None => (),
-                    }
+    if let Some(list_id) = task.list_id {
+        require_role(db, principal, list_id, Role::Editor).await?;
+    }
 
-                    let task = db.task_dao.update_task(id, new_data).await;
+    let mut new_data = task.clone();
+    new_task.apply_to(&mut new_data);
 
-                    match task {
-                        Ok(task) => HttpResponse::Ok().json(PublicTask::from(task)),
-                        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
-                    }
+    if let Some(list_id) = new_task.task_list {
+        require_role(db, principal, list_id, Role::Editor).await?;
+        new_data.list_id = Some(list_id);
+    }
+    check_subtask_list(&task, &new_data)?;
+    if let Err(err) = validate_recurrence(&mut new_data) {
+        return Err(HttpResponse::BadRequest().body(err));
+    }
+    if let Err(err) = notes::validate(new_data.notes.as_deref()) {
+        return Err(HttpResponse::BadRequest().body(err));
+    }
+    if let Some(offsets) = &new_task.task_reminders {
+        if let Err(err) = validate_reminders(offsets, new_data.deadline) {
+            return Err(HttpResponse::BadRequest().body(err));
+        }
+    }
+
+    // Completing an occurrence of a recurring task schedules the next one
+    let next_task = match (task.completed, new_data.completed) {
+        (false, true) => match next_occurrence(&new_data) {
+            Ok(next_task) => next_task,
+            Err(err) => return Err(HttpResponse::BadRequest().body(err)),
+        },
+        _ => None,
+    };
+
+    let updated = match next_task {
+        Some(next_task) => {
+            db.task_dao
+                .update_with_next_occurrence(id, new_data, next_task)
+                .await
+        }
+        None => db
+            .task_dao
+            .update_task(id, new_data)
+            .await
+            .map(|task| (task, None)),
+    };
+
+    let (updated_task, next_task) = match updated {
+        Ok(updated) => updated,
+        Err(err) => return Err(HttpResponse::InternalServerError().body(err.to_string())),
+    };
+
+    let mutation = Mutation::new(principal);
+
+    if let Err(err) = sync_reminders(db, &task, &updated_task, &new_task.task_reminders).await {
+        return Err(HttpResponse::InternalServerError().body(err.to_string()));
+    }
+    // The next occurrence is reminded just like this one
+    if let Some(next_task) = next_task {
+        let offsets: Vec<i64> = match db.reminder_dao.find_for_task(updated_task.id).await {
+            Ok(reminders) => reminders.iter().map(|r| r.offset_ms).collect(),
+            Err(err) => return Err(HttpResponse::InternalServerError().body(err.to_string())),
+        };
+        if let Err(err) = save_reminders(db, &next_task, &offsets).await {
+            return Err(HttpResponse::InternalServerError().body(err.to_string()));
+        }
+        events::publish(db, bus, &mutation, TaskEvent::Created, None, &next_task).await;
+    }
+
+    let event = match (task.completed, updated_task.completed) {
+        (false, true) => TaskEvent::Completed,
+        _ => TaskEvent::Updated,
+    };
+    events::publish(db, bus, &mutation, event, Some(&task), &updated_task).await;
+    if new_task.task_list.is_some() {
+        move_subtasks(db, bus, &mutation, &updated_task).await?;
+    }
+
+    Ok(updated_task)
+}
+
+/// Refuses to move a subtask away from the list of its parent, which the
+/// parent has to be moved for instead.
+fn check_subtask_list(task: &Task, new_data: &Task) -> Result<(), HttpResponse> {
+    if task.parent_id.is_some() && task.list_id != new_data.list_id {
+        return Err(HttpResponse::BadRequest()
+            .body("A subtask must be in the list of its parent, move the parent instead"));
+    }
+    Ok(())
+}
+
+/// Brings the subtasks of `task` along to its list, as part of `mutation`.
+/// Also catches up on subtasks left behind by an earlier, failed attempt.
+async fn move_subtasks(
+    db: &DbClient,
+    bus: &EventBus,
+    mutation: &Mutation,
+    task: &Task,
+) -> Result<(), HttpResponse> {
+    let moved = match db.task_dao.move_subtasks(task.id, task.list_id).await {
+        Ok(moved) => moved,
+        Err(err) => return Err(HttpResponse::InternalServerError().body(err.to_string())),
+    };
+    for (subtask, updated_task) in &moved {
+        events::publish(
+            db,
+            bus,
+            mutation,
+            TaskEvent::Updated,
+            Some(subtask),
+            updated_task,
+        )
+        .await;
+    }
+    Ok(())
+}
+
+/// Applies the changes to this and every later, not yet completed, occurrence
+/// of a recurring task. A new `deadline` shifts all of them by the same amount.
+#[put("/todo/{task_id}/future")]
+pub async fn update_future_occurrences(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    path: Path<TaskIdentifier>,
+    new_task: Json<OptionalTask>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    if new_task.task_state.is_some() {
+        return HttpResponse::BadRequest()
+            .body("'completed' can only be changed for a single occurrence");
+    }
+
+    let id = match uuid::parse_str(&path.task_id) {
+        Ok(id) => id,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+
+    let task = match db.task_dao.find_id(id).await {
+        Ok(task) => task,
+        Err(Error::NotFound(err)) => {
+            return HttpResponse::NotFound().body(format!("Not Found: {}", err))
+        }
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+
+    if let Some(list_id) = task.list_id {
+        if let Err(res) = require_role(&db, &principal, list_id, Role::Editor).await {
+            return res;
+        }
+    }
+    if let Some(list_id) = new_task.task_list {
+        if let Err(res) = require_role(&db, &principal, list_id, Role::Editor).await {
+            return res;
+        }
+    }
+
+    let series_id = match task.series_id {
+        Some(series_id) => series_id,
+        None => return HttpResponse::BadRequest().body("Task is not part of a recurring series"),
+    };
+
+    let mut occurrences = match db
+        .task_dao
+        .find_future_occurrences(series_id, task.occurrence)
+        .await
+    {
+        Ok(occurrences) => occurrences,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    if !occurrences
+        .iter()
+        .any(|occurrence| occurrence.id == task.id)
+    {
+        occurrences.insert(0, task.clone());
+    }
+
+    let shift = match (new_task.task_deadline, task.deadline) {
+        (Some(new_deadline), Some(deadline)) => new_deadline - deadline,
+        _ => chrono::Duration::zero(),
+    };
+
+    let mut updates = Vec::with_capacity(occurrences.len());
+    for occurrence in occurrences {
+        let mut new_data = occurrence.clone();
+        new_task.apply_to(&mut new_data);
+        new_data.deadline = match occurrence
+            .deadline
+            .map(|deadline| deadline.checked_add_signed(shift))
+        {
+            Some(None) => {
+                return HttpResponse::BadRequest()
+                    .body("The new 'deadline' moves a later occurrence out of range")
+            }
+            deadline => deadline.flatten(),
+        };
+        if let Some(list_id) = new_task.task_list {
+            new_data.list_id = Some(list_id);
+        }
+        if let Err(res) = check_subtask_list(&occurrence, &new_data) {
+            return res;
+        }
+        if let Err(err) = validate_recurrence(&mut new_data) {
+            return HttpResponse::BadRequest().body(err);
+        }
+        if let Err(err) = notes::validate(new_data.notes.as_deref()) {
+            return HttpResponse::BadRequest().body(err);
+        }
+        if let Some(offsets) = &new_task.task_reminders {
+            if let Err(err) = validate_reminders(offsets, new_data.deadline) {
+                return HttpResponse::BadRequest().body(err);
+            }
+        }
+        updates.push((occurrence, new_data));
+    }
+
+    let updated_tasks = match db
+        .task_dao
+        .update_many(
+            updates
+                .iter()
+                .map(|(occurrence, new_data)| (occurrence.id, new_data.clone()))
+                .collect(),
+        )
+        .await
+    {
+        Ok(updated_tasks) => updated_tasks,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+
+    let mutation = Mutation::new(&principal);
+    for ((occurrence, _), updated_task) in updates.iter().zip(&updated_tasks) {
+        if let Err(err) =
+            sync_reminders(&db, occurrence, updated_task, &new_task.task_reminders).await
+        {
+            return HttpResponse::InternalServerError().body(err.to_string());
+        }
+        events::publish(
+            &db,
+            &bus,
+            &mutation,
+            TaskEvent::Updated,
+            Some(occurrence),
+            updated_task,
+        )
+        .await;
+        if new_task.task_list.is_some() {
+            if let Err(res) = move_subtasks(&db, &bus, &mutation, updated_task).await {
+                return res;
+            }
+        }
+    }
+
+    send_data(Ok(updated_tasks))
+}
+
+/// Moves a task in the manual order of its list, right after and/or right
+/// before other tasks of the list.
+#[post("/todo/{task_id}/move")]
+pub async fn move_task(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    path: Path<TaskIdentifier>,
+    neighbours: Json<MoveTask>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let id = match uuid::parse_str(&path.task_id) {
+        Ok(id) => id,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+    if neighbours.after.is_none() && neighbours.before.is_none() {
+        return HttpResponse::BadRequest().body("'after' or 'before' is required");
+    }
+    if neighbours.after == Some(id) || neighbours.before == Some(id) {
+        return HttpResponse::BadRequest().body("A task cannot be moved next to itself");
+    }
+
+    let task = match db.task_dao.find_id(id).await {
+        Ok(task) => task,
+        Err(err) => return HttpResponse::NotFound().body(err.to_string()),
+    };
+    if let Some(list_id) = task.list_id {
+        if let Err(res) = require_role(&db, &principal, list_id, Role::Editor).await {
+            return res;
+        }
+    }
+
+    let changes = match db
+        .task_dao
+        .move_task(id, neighbours.after, neighbours.before)
+        .await
+    {
+        Ok(changes) => changes,
+        Err(err @ Error::NotFound(_)) => return HttpResponse::BadRequest().body(err.to_string()),
+        Err(err @ Error::InvalidPosition(_)) => {
+            return HttpResponse::BadRequest().body(err.to_string())
+        }
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+
+    // Tasks spread out to make room are moved by the same mutation
+    let mutation = Mutation::new(&principal);
+    for (before, after) in &changes {
+        events::publish(
+            &db,
+            &bus,
+            &mutation,
+            TaskEvent::Updated,
+            Some(before),
+            after,
+        )
+        .await;
+    }
+
+    match changes.into_iter().next() {
+        Some((_, moved)) => HttpResponse::Ok().json(PublicTask::from(moved)),
+        None => HttpResponse::Ok().json(PublicTask::from(task)),
+    }
+}
+
+#[get("/todo/{task_id}/reminders")]
+pub async fn get_task_reminders(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<TaskIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let id = match uuid::parse_str(&path.task_id) {
+        Ok(id) => id,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+
+    match db.task_dao.find_id(id).await {
+        Ok(task) => {
+            if let Some(list_id) = task.list_id {
+                if let Err(res) = require_role(&db, &principal, list_id, Role::Viewer).await {
+                    return res;
                 }
             }
         }
+        Err(Error::NotFound(err)) => {
+            return HttpResponse::NotFound().body(format!("Not Found: {}", err))
+        }
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    }
+
+    match db.reminder_dao.find_for_task(id).await {
+        Ok(reminders) => {
+            let public_reminders: Vec<PublicReminder> =
+                reminders.into_iter().map(PublicReminder::from).collect();
+            HttpResponse::Ok().json(public_reminders)
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
     }
 }
 
 #[delete("/todo/{task_id}")]
-pub async fn delete_task(db: Data<DbClient>, path: Path<TaskIdentifier>) -> HttpResponse {
+pub async fn delete_task(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    path: Path<TaskIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksDelete).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
     let id = uuid::parse_str(&path.task_id);
 
     match id {
         Err(err) => HttpResponse::BadRequest().body(err.to_string()),
-        Ok(id) => {
-            let res = db.task_dao.delete_id(id).await;
-            match res {
-                Ok(status) => HttpResponse::Ok().json(RowsAffected::from(status)),
-                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        Ok(id) => match delete(&db, &bus, &principal, id).await {
+            Ok(status) => HttpResponse::Ok().json(RowsAffected::from(status)),
+            Err(res) => res,
+        },
+    }
+}
+
+/// Deletes a task on behalf of `principal`, shared by `DELETE /todo/{task_id}`
+/// and the WebSocket channel.
+pub(crate) async fn delete(
+    db: &DbClient,
+    bus: &EventBus,
+    principal: &Principal,
+    id: Uuid,
+) -> Result<DeleteResult, HttpResponse> {
+    let task = match db.task_dao.find_id(id).await {
+        Ok(task) => Some(task),
+        Err(Error::NotFound(_)) => None,
+        Err(err) => return Err(HttpResponse::InternalServerError().body(err.to_string())),
+    };
+    if let Some(list_id) = task.as_ref().and_then(|task| task.list_id) {
+        require_role(db, principal, list_id, Role::Editor).await?;
+    }
+
+    match db.task_dao.delete_id(id).await {
+        Ok((status, detached)) => {
+            let mutation = Mutation::new(principal);
+            for (subtask, updated_task) in &detached {
+                events::publish(
+                    db,
+                    bus,
+                    &mutation,
+                    TaskEvent::Updated,
+                    Some(subtask),
+                    updated_task,
+                )
+                .await;
             }
+            if let Some(task) = task.filter(|_| status.rows_affected > 0) {
+                events::publish(db, bus, &mutation, TaskEvent::Deleted, None, &task).await;
+                attachment::purge_detached(db).await;
+            }
+            Ok(status)
         }
+        Err(err) => Err(HttpResponse::InternalServerError().body(err.to_string())),
     }
 }
 
+#[derive(Deserialize)]
+pub struct DeleteAllQueryParams {
+    list: Option<String>,
+}
+
 #[delete("/todo")]
-pub async fn delete_all_tasks(db: Data<DbClient>) -> HttpResponse {
-    let res = db.task_dao.delete_all().await;
+pub async fn delete_all_tasks(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    params: Query<DeleteAllQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksDelete).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    // Tasks without a list are shared by everyone, so only admins may clear
+    // them along with every list they own
+    if params.list.is_none() && !principal.admin {
+        return HttpResponse::BadRequest().body("'list' is required to delete tasks in bulk");
+    }
+    // Clearing a list is reserved to its owners
+    let lists = match resolve_list_filter(&db, &principal, &params.list, Role::Owner).await {
+        Ok(lists) => lists,
+        Err(res) => return res,
+    };
+
+    // Fetched beforehand to announce their deletion
+    let filter = query::TaskFilter::new(lists);
+    let tasks = match db
+        .task_dao
+        .find_all("created_at".to_string(), query::SortOrder::Asc, &filter)
+        .await
+    {
+        Ok(tasks) => tasks,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+
+    let res = db.task_dao.delete_all(&filter.lists).await;
 
     match res {
-        Ok(status) => HttpResponse::Ok().json(RowsAffected::from(status)),
+        Ok(status) => {
+            let mutation = Mutation::new(&principal);
+            for task in &tasks {
+                events::publish(&db, &bus, &mutation, TaskEvent::Deleted, None, task).await;
+            }
+            attachment::purge_detached(&db).await;
+            HttpResponse::Ok().json(RowsAffected::from(status))
+        }
         Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
     }
 }
@@ -0,0 +1,340 @@
+use crate::api::attachment;
+use crate::api::auth::{authorize, require_role};
+use crate::database::DbClient;
+use crate::events::bus::EventBus;
+use crate::events::{self, Mutation, TaskEvent};
+use crate::lib::auth::Scope;
+use crate::lib::{errors::Error, query, uuid};
+use crate::model::results::RowsAffected;
+use crate::model::task_list::{NewListMember, NewTaskList, PublicListMember, PublicTaskList};
+use entity::list_member::{Model as ListMember, Role};
+use entity::task_list::Model as TaskList;
+
+use actix_web::{
+    delete, get, post,
+    web::{Data, Json, Path},
+    HttpRequest, HttpResponse,
+};
+use serde::Deserialize;
+
+pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
+    app.service(create_list)
+        .service(get_all_lists)
+        .service(get_invitations)
+        .service(delete_list)
+        .service(get_members)
+        .service(invite_member)
+        .service(accept_invitation)
+        .service(revoke_member);
+}
+
+#[post("/lists")]
+pub async fn create_list(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    new_list: Json<NewTaskList>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    if new_list.name.trim().is_empty() {
+        return HttpResponse::BadRequest().body("'name' must not be empty");
+    }
+
+    let list = db
+        .task_list_dao
+        .create(TaskList::new(new_list.name.clone()), principal.subject)
+        .await;
+
+    match list {
+        Ok(list) => HttpResponse::Created().json(PublicTaskList::new(list, Role::Owner)),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[get("/lists")]
+pub async fn get_all_lists(db: Data<DbClient>, req: HttpRequest) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    if principal.admin {
+        return match db.task_list_dao.find_all().await {
+            Ok(lists) => HttpResponse::Ok().json(
+                lists
+                    .into_iter()
+                    .map(|list| PublicTaskList::new(list, Role::Owner))
+                    .collect::<Vec<PublicTaskList>>(),
+            ),
+            Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        };
+    }
+
+    let memberships = match db
+        .list_member_dao
+        .find_for_member(&principal.subject, Role::Viewer)
+        .await
+    {
+        Ok(memberships) => memberships,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+
+    let ids = memberships.iter().map(|m| m.list_id).collect();
+    match db.task_list_dao.find_by_ids(ids).await {
+        Ok(lists) => {
+            let public_lists: Vec<PublicTaskList> = lists
+                .into_iter()
+                .filter_map(|list| {
+                    let membership = memberships.iter().find(|m| m.list_id == list.id)?;
+                    Some(PublicTaskList::new(list, membership.role))
+                })
+                .collect();
+            HttpResponse::Ok().json(public_lists)
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[get("/lists/invitations")]
+pub async fn get_invitations(db: Data<DbClient>, req: HttpRequest) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    match db
+        .list_member_dao
+        .find_invitations(&principal.subject)
+        .await
+    {
+        Ok(invitations) => {
+            let public_invitations: Vec<PublicListMember> = invitations
+                .into_iter()
+                .map(PublicListMember::from)
+                .collect();
+            HttpResponse::Ok().json(public_invitations)
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListIdentifier {
+    list_id: String,
+}
+
+#[delete("/lists/{list_id}")]
+pub async fn delete_list(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    path: Path<ListIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksDelete).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let id = uuid::parse_str(&path.list_id);
+
+    match id {
+        Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+        Ok(id) => {
+            if let Err(res) = require_role(&db, &principal, id, Role::Owner).await {
+                return res;
+            }
+
+            // Deleted along with the list, fetched beforehand to announce it
+            let filter = query::TaskFilter::new(query::ListFilter::Only(id));
+            let tasks = match db
+                .task_dao
+                .find_all("created_at".to_string(), query::SortOrder::Asc, &filter)
+                .await
+            {
+                Ok(tasks) => tasks,
+                Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+            };
+
+            match db.task_list_dao.delete_id(id).await {
+                Ok(status) => {
+                    let mutation = Mutation::new(&principal);
+                    for task in &tasks {
+                        events::publish(&db, &bus, &mutation, TaskEvent::Deleted, None, task).await;
+                    }
+                    attachment::purge_detached(&db).await;
+                    HttpResponse::Ok().json(RowsAffected::from(status))
+                }
+                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+            }
+        }
+    }
+}
+
+#[get("/lists/{list_id}/members")]
+pub async fn get_members(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<ListIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let id = uuid::parse_str(&path.list_id);
+
+    match id {
+        Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+        Ok(id) => {
+            if let Err(res) = require_role(&db, &principal, id, Role::Viewer).await {
+                return res;
+            }
+
+            match db.list_member_dao.find_for_list(id).await {
+                Ok(members) => {
+                    let public_members: Vec<PublicListMember> =
+                        members.into_iter().map(PublicListMember::from).collect();
+                    HttpResponse::Ok().json(public_members)
+                }
+                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+            }
+        }
+    }
+}
+
+#[post("/lists/{list_id}/members")]
+pub async fn invite_member(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<ListIdentifier>,
+    new_member: Json<NewListMember>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let id = uuid::parse_str(&path.list_id);
+
+    match id {
+        Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+        Ok(id) => {
+            if let Err(res) = require_role(&db, &principal, id, Role::Owner).await {
+                return res;
+            }
+            match db.task_list_dao.find_id(id).await {
+                Ok(_) => {}
+                Err(Error::NotFound(err)) => {
+                    return HttpResponse::NotFound().body(format!("Not Found: {}", err))
+                }
+                Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+            }
+
+            match db.list_member_dao.find(id, &new_member.member).await {
+                Ok(_) => {
+                    return HttpResponse::Conflict()
+                        .body(format!("{} is already invited", new_member.member))
+                }
+                Err(Error::NotFound(_)) => {}
+                Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+            }
+
+            let membership = ListMember::new(
+                id,
+                new_member.member.clone(),
+                new_member.role,
+                principal.subject,
+            );
+
+            match db.list_member_dao.invite(membership).await {
+                Ok(membership) => HttpResponse::Created().json(PublicListMember::from(membership)),
+                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+            }
+        }
+    }
+}
+
+#[post("/lists/{list_id}/accept")]
+pub async fn accept_invitation(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<ListIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let id = uuid::parse_str(&path.list_id);
+
+    match id {
+        Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+        Ok(id) => match db.list_member_dao.accept(id, &principal.subject).await {
+            Ok(membership) => HttpResponse::Ok().json(PublicListMember::from(membership)),
+            Err(Error::NotFound(err)) => {
+                HttpResponse::NotFound().body(format!("Not Found: {}", err))
+            }
+            Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        },
+    }
+}
+
+#[derive(Deserialize)]
+pub struct MemberIdentifier {
+    list_id: String,
+    member: String,
+}
+
+#[delete("/lists/{list_id}/members/{member}")]
+pub async fn revoke_member(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<MemberIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let id = uuid::parse_str(&path.list_id);
+
+    match id {
+        Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+        Ok(id) => {
+            // Members may always leave; removing others is reserved to owners
+            if path.member != principal.subject {
+                if let Err(res) = require_role(&db, &principal, id, Role::Owner).await {
+                    return res;
+                }
+            }
+
+            let membership = match db.list_member_dao.find(id, &path.member).await {
+                Ok(membership) => membership,
+                Err(Error::NotFound(err)) => {
+                    return HttpResponse::NotFound().body(format!("Not Found: {}", err))
+                }
+                Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+            };
+
+            if membership.role == Role::Owner && membership.accepted_at.is_some() {
+                match db.list_member_dao.count_owners(id).await {
+                    Ok(owners) if owners <= 1 => {
+                        return HttpResponse::Conflict().body(
+                            "Cannot remove the last owner of a list. Delete the list instead",
+                        )
+                    }
+                    Ok(_) => {}
+                    Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+                }
+            }
+
+            match db.list_member_dao.revoke(id, &path.member).await {
+                Ok(status) => HttpResponse::Ok().json(RowsAffected::from(status)),
+                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::api::auth::authorize;
+use crate::database::DbClient;
+use crate::lib::auth::{self, generate_token, hash_token, Principal, Scope};
+use crate::lib::{errors::Error, uuid};
+use crate::model::access_token::{IssuedAccessToken, NewAccessToken, PublicAccessToken};
+use entity::access_token::Model as AccessToken;
+
+use actix_web::{
+    delete, get, post,
+    web::{Data, Json, Path},
+    HttpRequest, HttpResponse,
+};
+use serde::Deserialize;
+
+pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
+    app.service(issue_token)
+        .service(get_all_tokens)
+        .service(revoke_token);
+}
+
+#[post("/tokens")]
+pub async fn issue_token(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    new_token: Json<NewAccessToken>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TokensManage).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    if new_token.name.trim().is_empty() {
+        return HttpResponse::BadRequest().body("'name' must not be empty");
+    }
+
+    let scopes = match auth::parse_scopes(&new_token.scopes.join(" ")) {
+        Ok(scopes) if scopes.is_empty() => {
+            return HttpResponse::BadRequest().body("At least one scope is required")
+        }
+        Ok(scopes) => scopes,
+        Err(scope) => {
+            return HttpResponse::BadRequest().body(format!("Invalid scope: {}", scope));
+        }
+    };
+    if !scopes.is_subset(&principal.scopes) {
+        return HttpResponse::Forbidden()
+            .body("Cannot issue a token with scopes the caller does not hold");
+    }
+
+    // Only admins may issue tokens on behalf of another subject
+    let owner = match &new_token.owner {
+        Some(owner) if principal.admin => owner.clone(),
+        Some(owner) if *owner != principal.subject => {
+            return HttpResponse::Forbidden()
+                .body("Cannot issue a token for a subject other than the caller");
+        }
+        _ => principal.subject.clone(),
+    };
+
+    let token = generate_token();
+    let token_to_insert = AccessToken::new(
+        new_token.name.clone(),
+        owner,
+        hash_token(&token),
+        auth::join_scopes(&scopes),
+    );
+
+    match db.access_token_dao.create(token_to_insert).await {
+        Ok(details) => HttpResponse::Created().json(IssuedAccessToken {
+            details: PublicAccessToken::from(details),
+            token,
+        }),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+/// Tokens are only visible to their owner, or to admins.
+fn visible_owner(principal: &Principal) -> Option<&str> {
+    (!principal.admin).then_some(principal.subject.as_str())
+}
+
+#[get("/tokens")]
+pub async fn get_all_tokens(db: Data<DbClient>, req: HttpRequest) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TokensManage).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    match db
+        .access_token_dao
+        .find_all(visible_owner(&principal))
+        .await
+    {
+        Ok(tokens) => {
+            let public_tokens: Vec<PublicAccessToken> =
+                tokens.into_iter().map(PublicAccessToken::from).collect();
+            HttpResponse::Ok().json(public_tokens)
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TokenIdentifier {
+    token_id: String,
+}
+
+#[delete("/tokens/{token_id}")]
+pub async fn revoke_token(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<TokenIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TokensManage).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let id = uuid::parse_str(&path.token_id);
+
+    match id {
+        Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+        Ok(id) => match db
+            .access_token_dao
+            .revoke(id, visible_owner(&principal))
+            .await
+        {
+            Ok(token) => HttpResponse::Ok().json(PublicAccessToken::from(token)),
+            Err(Error::NotFound(err)) => {
+                HttpResponse::NotFound().body(format!("Not Found: {}", err))
+            }
+            Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        },
+    }
+}
@@ -0,0 +1,564 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::api::auth::{authorize, authorize_feed, lists_with_role, require_role};
+use crate::api::task::{find_tasks, validate_recurrence, GetAllQueryParams};
+use crate::database::DbClient;
+use crate::events::{self, bus::EventBus, Mutation, TaskEvent};
+use crate::lib::auth::{Principal, Scope};
+use crate::lib::csv::{self, ColumnMapping};
+use crate::lib::icalendar::{self, Components};
+use crate::lib::markdown::{self, Grouping};
+use crate::lib::query::ListFilter;
+use crate::lib::recurrence::parse_timezone;
+use crate::lib::todotxt;
+use crate::lib::uuid;
+use crate::model::import::{IcsImportReport, ImportReport, ImportedRow, RowError};
+use crate::model::task::PublicTask;
+use entity::list_member::Role;
+use entity::task::Model as Task;
+
+use ::uuid::Uuid;
+use actix_web::{
+    get, post,
+    web::{Bytes, Data, Query},
+    HttpRequest, HttpResponse,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
+    app.service(export_csv)
+        .service(import_csv)
+        .service(import_ics)
+        .service(export_calendar)
+        .service(export_todotxt)
+        .service(import_todotxt)
+        .service(export_markdown)
+        .service(import_markdown);
+}
+
+/// Streams the tasks `GET /todo` would return with the same parameters as CSV.
+#[get("/todo/export.csv")]
+pub async fn export_csv(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    params: Query<GetAllQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let tasks = match find_tasks(&db, &principal, &params).await {
+        Ok(tasks) => tasks,
+        Err(res) => return res,
+    };
+
+    let records = std::iter::once(csv::header())
+        .chain(tasks.into_iter().map(|task| csv::row(&task)))
+        .map(|record| Ok::<_, actix_web::Error>(Bytes::from(record)));
+
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(("Content-Disposition", "attachment; filename=\"tasks.csv\""))
+        .streaming(futures::stream::iter(records))
+}
+
+#[derive(Deserialize)]
+pub struct CsvImportQueryParams {
+    /// List of the rows without a `listId`
+    list: Option<String>,
+
+    /// e.g. `title:Name,deadline:Due Date`
+    columns: Option<String>,
+
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Parses the `list` of an import, to which the caller must be allowed to
+/// add tasks.
+async fn resolve_import_list(
+    db: &DbClient,
+    principal: &Principal,
+    list: &Option<String>,
+) -> Result<Option<Uuid>, HttpResponse> {
+    match list {
+        Some(list_id) => {
+            let list_id = uuid::parse_str(list_id)
+                .map_err(|err| HttpResponse::BadRequest().body(err.to_string()))?;
+            require_role(db, principal, list_id, Role::Editor).await?;
+            Ok(Some(list_id))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Creates the tasks of an import in one transaction and one mutation, so
+/// that `POST /undo` reverts the whole import.
+async fn create_imported(
+    db: &DbClient,
+    bus: &EventBus,
+    principal: &Principal,
+    tasks: Vec<Task>,
+) -> Result<Vec<Task>, HttpResponse> {
+    let tasks = db
+        .task_dao
+        .create_many(tasks)
+        .await
+        .map_err(|err| HttpResponse::InternalServerError().body(err.to_string()))?;
+
+    let mutation = Mutation::new(principal);
+    for task in &tasks {
+        events::publish(db, bus, &mutation, TaskEvent::Created, None, task).await;
+    }
+    Ok(tasks)
+}
+
+/// Creates a task for every valid row of a CSV body, reporting the others.
+#[post("/todo/import")]
+pub async fn import_csv(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    params: Query<CsvImportQueryParams>,
+    body: Bytes,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let default_list = match resolve_import_list(&db, &principal, &params.list).await {
+        Ok(list_id) => list_id,
+        Err(res) => return res,
+    };
+    let mapping = match params.columns.as_deref().map(str::parse::<ColumnMapping>) {
+        Some(Ok(mapping)) => mapping,
+        Some(Err(err)) => return HttpResponse::BadRequest().body(err),
+        None => ColumnMapping::default(),
+    };
+
+    let (rows, mut errors) = match csv::parse(&body, &mapping) {
+        Ok(parsed) => parsed,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    let editable = match lists_with_role(&db, &principal, Role::Editor).await {
+        Ok(editable) => editable,
+        Err(res) => return res,
+    };
+
+    let mut tasks = Vec::with_capacity(rows.len());
+    for ImportedRow { row, mut task } in rows {
+        task.list_id = task.list_id.or(default_list);
+        if let Some(list_id) = task
+            .list_id
+            .filter(|list_id| !editable.matches(Some(*list_id)))
+        {
+            errors.push(RowError::new(
+                row,
+                Some("listId"),
+                format!("Editor role on List <id: {}> is required", list_id),
+            ));
+            continue;
+        }
+        if let Err(err) = validate_recurrence(&mut task) {
+            errors.push(RowError::new(row, Some("rrule"), err));
+            continue;
+        }
+        tasks.push(task);
+    }
+    errors.sort_by_key(|error| error.row);
+
+    if !params.dry_run {
+        tasks = match create_imported(&db, &bus, &principal, tasks).await {
+            Ok(tasks) => tasks,
+            Err(res) => return res,
+        };
+    }
+
+    let report = ImportReport {
+        dry_run: params.dry_run,
+        imported: tasks.len(),
+        tasks: tasks.into_iter().map(PublicTask::from).collect(),
+        errors,
+    };
+    if params.dry_run {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::Created().json(report)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct IcsImportQueryParams {
+    /// List to add the tasks to
+    list: Option<String>,
+
+    /// Timezone of times without one, UTC if unset
+    timezone: Option<String>,
+
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Creates a task for every `VTODO` of an iCalendar body. Components whose
+/// `UID` was imported before are skipped, so a file can be imported again
+/// after it was extended.
+#[post("/todo/import/ics")]
+pub async fn import_ics(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    params: Query<IcsImportQueryParams>,
+    body: Bytes,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let list_id = match resolve_import_list(&db, &principal, &params.list).await {
+        Ok(list_id) => list_id,
+        Err(res) => return res,
+    };
+
+    let floating = params.timezone.as_deref().unwrap_or("UTC");
+    let (todos, mut skipped) = match icalendar::parse(&body, floating) {
+        Ok(parsed) => parsed,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    let ids = todos.iter().map(|todo| todo.task.id).collect();
+    let existing: HashSet<Uuid> = match db.task_dao.find_existing_ids(ids).await {
+        Ok(existing) => existing.into_iter().collect(),
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+
+    let mut tasks = Vec::with_capacity(todos.len());
+    for mut todo in todos {
+        if existing.contains(&todo.task.id) {
+            skipped.push(todo.skip("A task with this UID already exists".to_string()));
+            continue;
+        }
+        todo.task.list_id = list_id;
+        if let Err(err) = validate_recurrence(&mut todo.task) {
+            skipped.push(todo.skip(err));
+            continue;
+        }
+        tasks.push(todo.task);
+    }
+    skipped.sort_by(|a, b| (&a.component, a.index).cmp(&(&b.component, b.index)));
+
+    if !params.dry_run {
+        tasks = match create_imported(&db, &bus, &principal, tasks).await {
+            Ok(tasks) => tasks,
+            Err(res) => return res,
+        };
+    }
+
+    let report = IcsImportReport {
+        dry_run: params.dry_run,
+        imported: tasks.len(),
+        tasks: tasks.into_iter().map(PublicTask::from).collect(),
+        skipped,
+    };
+    if params.dry_run {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::Created().json(report)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CalendarQueryParams {
+    /// Access token with the `calendar:read` scope, for clients that cannot
+    /// send an `Authorization` header
+    token: Option<String>,
+
+    /// e.g. `vtodo,vevent`
+    components: Option<String>,
+}
+
+/// Renders the deadlines of the tasks `GET /todo` would return with the same
+/// parameters as an iCalendar feed that calendar apps can subscribe to.
+#[get("/todo/calendar.ics")]
+pub async fn export_calendar(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    params: Query<GetAllQueryParams>,
+    calendar_params: Query<CalendarQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize_feed(&db, &req, calendar_params.token.as_deref()).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let components = match calendar_params
+        .components
+        .as_deref()
+        .map(str::parse::<Components>)
+    {
+        Some(Ok(components)) => components,
+        Some(Err(err)) => return HttpResponse::BadRequest().body(err),
+        None => Components::default(),
+    };
+    let tasks = match find_tasks(&db, &principal, &params).await {
+        Ok(tasks) => tasks,
+        Err(res) => return res,
+    };
+
+    HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .insert_header(("Content-Disposition", "inline; filename=\"tasks.ics\""))
+        .body(icalendar::calendar(&tasks, components, Utc::now()))
+}
+
+/// Names of the lists of `tasks`.
+async fn list_names(db: &DbClient, tasks: &[Task]) -> Result<HashMap<Uuid, String>, HttpResponse> {
+    let mut list_ids: Vec<Uuid> = tasks.iter().filter_map(|task| task.list_id).collect();
+    list_ids.sort();
+    list_ids.dedup();
+
+    match db.task_list_dao.find_by_ids(list_ids).await {
+        Ok(lists) => Ok(lists.into_iter().map(|list| (list.id, list.name)).collect()),
+        Err(err) => Err(HttpResponse::InternalServerError().body(err.to_string())),
+    }
+}
+
+/// Lists the caller may add tasks to, by their name as a `+project`. Of lists
+/// with the same name, the oldest one is used.
+pub(crate) async fn editable_projects(
+    db: &DbClient,
+    principal: &Principal,
+) -> Result<HashMap<String, Uuid>, HttpResponse> {
+    let lists = match lists_with_role(db, principal, Role::Editor).await? {
+        ListFilter::All => db.task_list_dao.find_all().await,
+        ListFilter::Accessible(ids) => db.task_list_dao.find_by_ids(ids).await,
+        ListFilter::Only(id) => db.task_list_dao.find_by_ids(vec![id]).await,
+    }
+    .map_err(|err| HttpResponse::InternalServerError().body(err.to_string()))?;
+
+    let mut projects = HashMap::new();
+    for list in lists {
+        projects
+            .entry(todotxt::project(&list.name))
+            .or_insert(list.id);
+    }
+    Ok(projects)
+}
+
+/// Renders the tasks `GET /todo` would return with the same parameters in
+/// the todo.txt format.
+#[get("/todo/export.txt")]
+pub async fn export_todotxt(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    params: Query<GetAllQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let tasks = match find_tasks(&db, &principal, &params).await {
+        Ok(tasks) => tasks,
+        Err(res) => return res,
+    };
+    let list_names = match list_names(&db, &tasks).await {
+        Ok(list_names) => list_names,
+        Err(res) => return res,
+    };
+
+    let mut body = String::new();
+    for task in &tasks {
+        let project = task
+            .list_id
+            .and_then(|list_id| list_names.get(&list_id))
+            .map(|name| todotxt::project(name));
+        body.push_str(&todotxt::line(task, project.as_deref()));
+        body.push('\n');
+    }
+
+    HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .insert_header(("Content-Disposition", "attachment; filename=\"todo.txt\""))
+        .body(body)
+}
+
+#[derive(Deserialize)]
+pub struct ImportQueryParams {
+    /// List of the tasks without one
+    list: Option<String>,
+
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Creates a task for every valid line of a todo.txt body, reporting the
+/// others.
+#[post("/todo/import/todotxt")]
+pub async fn import_todotxt(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    params: Query<ImportQueryParams>,
+    body: Bytes,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let default_list = match resolve_import_list(&db, &principal, &params.list).await {
+        Ok(list_id) => list_id,
+        Err(res) => return res,
+    };
+    let text = match std::str::from_utf8(&body) {
+        Ok(text) => text,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+    let projects = match editable_projects(&db, &principal).await {
+        Ok(projects) => projects,
+        Err(res) => return res,
+    };
+
+    let (rows, mut errors) = todotxt::parse(text, &projects);
+    let mut tasks = Vec::with_capacity(rows.len());
+    for ImportedRow { row, mut task } in rows {
+        task.list_id = task.list_id.or(default_list);
+        if let Err(err) = validate_recurrence(&mut task) {
+            errors.push(RowError::new(row, Some("rrule"), err));
+            continue;
+        }
+        tasks.push(task);
+    }
+    errors.sort_by_key(|error| error.row);
+
+    if !params.dry_run {
+        tasks = match create_imported(&db, &bus, &principal, tasks).await {
+            Ok(tasks) => tasks,
+            Err(res) => return res,
+        };
+    }
+
+    let report = ImportReport {
+        dry_run: params.dry_run,
+        imported: tasks.len(),
+        tasks: tasks.into_iter().map(PublicTask::from).collect(),
+        errors,
+    };
+    if params.dry_run {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::Created().json(report)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct MarkdownQueryParams {
+    /// `list` or `day`
+    group: Option<String>,
+
+    /// Timezone of the deadlines, UTC if unset
+    timezone: Option<String>,
+}
+
+/// Renders the tasks `GET /todo` would return with the same parameters as
+/// Markdown checklists.
+#[get("/todo/export.md")]
+pub async fn export_markdown(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    params: Query<GetAllQueryParams>,
+    markdown_params: Query<MarkdownQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let grouping = match markdown_params.group.as_deref().map(str::parse::<Grouping>) {
+        Some(Ok(grouping)) => grouping,
+        Some(Err(err)) => return HttpResponse::BadRequest().body(err),
+        None => Grouping::List,
+    };
+    let tz = match parse_timezone(markdown_params.timezone.as_deref().unwrap_or("UTC")) {
+        Ok(tz) => tz,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    let tasks = match find_tasks(&db, &principal, &params).await {
+        Ok(tasks) => tasks,
+        Err(res) => return res,
+    };
+    let list_names = match list_names(&db, &tasks).await {
+        Ok(list_names) => list_names,
+        Err(res) => return res,
+    };
+
+    HttpResponse::Ok()
+        .content_type("text/markdown; charset=utf-8")
+        .body(markdown::checklist(&tasks, grouping, &list_names, &tz))
+}
+
+#[derive(Deserialize)]
+pub struct MarkdownImportQueryParams {
+    /// List of the tasks without one
+    list: Option<String>,
+
+    /// Timezone of the deadlines, UTC if unset
+    timezone: Option<String>,
+
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Creates a task for every checklist item of a Markdown body, nested items
+/// becoming subtasks.
+#[post("/todo/import/markdown")]
+pub async fn import_markdown(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    params: Query<MarkdownImportQueryParams>,
+    body: Bytes,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksWrite).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+    let default_list = match resolve_import_list(&db, &principal, &params.list).await {
+        Ok(list_id) => list_id,
+        Err(res) => return res,
+    };
+    let tz = match parse_timezone(params.timezone.as_deref().unwrap_or("UTC")) {
+        Ok(tz) => tz,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    let text = match std::str::from_utf8(&body) {
+        Ok(text) => text,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+    let projects = match editable_projects(&db, &principal).await {
+        Ok(projects) => projects,
+        Err(res) => return res,
+    };
+
+    // Subtasks share the heading, and so the list, of their parent
+    let (rows, errors) = markdown::parse(text, &projects, &tz);
+    let mut tasks: Vec<Task> = rows.into_iter().map(|row| row.task).collect();
+    for task in &mut tasks {
+        task.list_id = task.list_id.or(default_list);
+    }
+
+    if !params.dry_run {
+        tasks = match create_imported(&db, &bus, &principal, tasks).await {
+            Ok(tasks) => tasks,
+            Err(res) => return res,
+        };
+    }
+
+    let report = ImportReport {
+        dry_run: params.dry_run,
+        imported: tasks.len(),
+        tasks: tasks.into_iter().map(PublicTask::from).collect(),
+        errors,
+    };
+    if params.dry_run {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::Created().json(report)
+    }
+}
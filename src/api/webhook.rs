@@ -0,0 +1,282 @@
+use std::str::FromStr;
+
+use crate::api::auth::authorize;
+use crate::database::DbClient;
+use crate::events::TaskEvent;
+use crate::lib::auth::{generate_webhook_secret, Principal, Scope};
+use crate::lib::{errors::Error, uuid};
+use crate::model::webhook::{
+    CreatedWebhookSubscription, NewWebhookSubscription, PublicWebhookDelivery,
+    PublicWebhookSubscription,
+};
+use entity::webhook_subscription::Model as WebhookSubscription;
+
+use actix_web::{
+    delete, get, post,
+    web::{Data, Json, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use serde::Deserialize;
+
+const DEFAULT_DELIVERY_LIMIT: u64 = 50;
+const MAX_DELIVERY_LIMIT: u64 = 500;
+
+pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
+    app.service(create_webhook)
+        .service(get_all_webhooks)
+        .service(get_dead_letters)
+        .service(delete_webhook)
+        .service(get_webhook_deliveries)
+        .service(retry_delivery);
+}
+
+/// Fetches a subscription, hiding those of other owners unless the caller is
+/// an admin.
+async fn find_subscription(
+    db: &DbClient,
+    principal: &Principal,
+    webhook_id: &str,
+) -> Result<WebhookSubscription, HttpResponse> {
+    let id = match uuid::parse_str(webhook_id) {
+        Ok(id) => id,
+        Err(err) => return Err(HttpResponse::BadRequest().body(err.to_string())),
+    };
+
+    match db.webhook_subscription_dao.find_id(id).await {
+        Ok(subscription) if principal.admin || subscription.owner == principal.subject => {
+            Ok(subscription)
+        }
+        Ok(_) => {
+            Err(HttpResponse::NotFound()
+                .body(format!("Not Found: Webhook Subscription <id: {}>", id)))
+        }
+        Err(Error::NotFound(err)) => {
+            Err(HttpResponse::NotFound().body(format!("Not Found: {}", err)))
+        }
+        Err(err) => Err(HttpResponse::InternalServerError().body(err.to_string())),
+    }
+}
+
+/// Subscriptions visible to the caller: their own, or all for admins.
+async fn visible_subscriptions(
+    db: &DbClient,
+    principal: &Principal,
+) -> Result<Vec<WebhookSubscription>, Error> {
+    let owner = (!principal.admin).then_some(principal.subject.as_str());
+    db.webhook_subscription_dao.find_all(owner).await
+}
+
+#[post("/webhooks")]
+pub async fn create_webhook(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    new_webhook: Json<NewWebhookSubscription>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::WebhooksManage).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    match reqwest::Url::parse(&new_webhook.url) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+        _ => return HttpResponse::BadRequest().body("'url' must be an absolute http(s) URL"),
+    }
+
+    if new_webhook.events.is_empty() {
+        return HttpResponse::BadRequest().body("At least one event is required");
+    }
+    let mut events = Vec::new();
+    for event in &new_webhook.events {
+        match TaskEvent::from_str(event) {
+            Ok(event) if !events.contains(&event) => events.push(event),
+            Ok(_) => {}
+            Err(_) => return HttpResponse::BadRequest().body(format!("Invalid event: {}", event)),
+        }
+    }
+    let events: Vec<String> = events.iter().map(TaskEvent::to_string).collect();
+
+    let secret = match &new_webhook.secret {
+        Some(secret) if secret.trim().is_empty() => {
+            return HttpResponse::BadRequest().body("'secret' must not be empty")
+        }
+        Some(secret) => secret.clone(),
+        None => generate_webhook_secret(),
+    };
+
+    let subscription_to_insert = WebhookSubscription::new(
+        new_webhook.url.clone(),
+        secret.clone(),
+        events.join(" "),
+        principal.subject,
+    );
+
+    match db
+        .webhook_subscription_dao
+        .create(subscription_to_insert)
+        .await
+    {
+        Ok(subscription) => HttpResponse::Created().json(CreatedWebhookSubscription {
+            subscription: PublicWebhookSubscription::from(subscription),
+            secret,
+        }),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[get("/webhooks")]
+pub async fn get_all_webhooks(db: Data<DbClient>, req: HttpRequest) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::WebhooksManage).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    match visible_subscriptions(&db, &principal).await {
+        Ok(subscriptions) => {
+            let public_subscriptions: Vec<PublicWebhookSubscription> = subscriptions
+                .into_iter()
+                .map(PublicWebhookSubscription::from)
+                .collect();
+            HttpResponse::Ok().json(public_subscriptions)
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct WebhookIdentifier {
+    webhook_id: String,
+}
+
+#[delete("/webhooks/{webhook_id}")]
+pub async fn delete_webhook(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<WebhookIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::WebhooksManage).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let subscription = match find_subscription(&db, &principal, &path.webhook_id).await {
+        Ok(subscription) => subscription,
+        Err(res) => return res,
+    };
+
+    match db.webhook_subscription_dao.delete_id(subscription.id).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DeliveryQueryParams {
+    limit: Option<u64>,
+}
+
+#[get("/webhooks/{webhook_id}/deliveries")]
+pub async fn get_webhook_deliveries(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<WebhookIdentifier>,
+    params: Query<DeliveryQueryParams>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::WebhooksManage).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let subscription = match find_subscription(&db, &principal, &path.webhook_id).await {
+        Ok(subscription) => subscription,
+        Err(res) => return res,
+    };
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_DELIVERY_LIMIT)
+        .clamp(1, MAX_DELIVERY_LIMIT);
+
+    match db
+        .webhook_delivery_dao
+        .find_for_subscription(subscription.id, limit)
+        .await
+    {
+        Ok(deliveries) => {
+            let public_deliveries: Vec<PublicWebhookDelivery> = deliveries
+                .into_iter()
+                .map(PublicWebhookDelivery::from)
+                .collect();
+            HttpResponse::Ok().json(public_deliveries)
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+/// Deliveries that ran out of attempts, across the caller's subscriptions.
+#[get("/webhooks/dead-letters")]
+pub async fn get_dead_letters(db: Data<DbClient>, req: HttpRequest) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::WebhooksManage).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let subscriptions = match visible_subscriptions(&db, &principal).await {
+        Ok(subscriptions) => subscriptions,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    let subscription_ids = subscriptions
+        .iter()
+        .map(|subscription| subscription.id)
+        .collect();
+
+    match db.webhook_delivery_dao.find_dead(subscription_ids).await {
+        Ok(deliveries) => {
+            let public_deliveries: Vec<PublicWebhookDelivery> = deliveries
+                .into_iter()
+                .map(PublicWebhookDelivery::from)
+                .collect();
+            HttpResponse::Ok().json(public_deliveries)
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DeliveryIdentifier {
+    delivery_id: String,
+}
+
+#[post("/webhooks/deliveries/{delivery_id}/retry")]
+pub async fn retry_delivery(
+    db: Data<DbClient>,
+    req: HttpRequest,
+    path: Path<DeliveryIdentifier>,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::WebhooksManage).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let delivery = match uuid::parse_str(&path.delivery_id) {
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+        Ok(id) => match db.webhook_delivery_dao.find_id(id).await {
+            Ok(delivery) => delivery,
+            Err(Error::NotFound(err)) => {
+                return HttpResponse::NotFound().body(format!("Not Found: {}", err))
+            }
+            Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+        },
+    };
+
+    // Deliveries are only visible through their subscription
+    if let Err(res) =
+        find_subscription(&db, &principal, &delivery.subscription_id.to_string()).await
+    {
+        return res;
+    }
+
+    match db.webhook_delivery_dao.retry(delivery).await {
+        Ok(delivery) => HttpResponse::Ok().json(PublicWebhookDelivery::from(delivery)),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::api::auth::{authorize, require_role, require_scope};
+use crate::api::task;
+use crate::database::DbClient;
+use crate::events::bus::{ChangeEvent, EventBus};
+use crate::lib::auth::{Principal, Scope};
+use crate::model::task::PublicTask;
+use crate::model::ws::{ClientMessage, ServerMessage};
+use entity::list_member::Role;
+
+use actix_web::{
+    get,
+    web::{Data, Payload},
+    HttpRequest, HttpResponse,
+};
+use actix_ws::{AggregatedMessage, AggregatedMessageStream, CloseCode, CloseReason, Session};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+/// How often the server pings idle clients.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Clients that stay silent for longer, not even answering pings, are dropped.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Clients that do not take a message within this time are too slow to keep.
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+pub fn attach_service(app: &mut actix_web::web::ServiceConfig) {
+    app.service(connect);
+}
+
+/// Upgrades to a WebSocket on which clients subscribe to lists, receive
+/// their changes and send mutations. The caller is authenticated once, while
+/// every message is checked against its scopes and roles.
+#[get("/ws")]
+pub async fn connect(
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    req: HttpRequest,
+    body: Payload,
+) -> HttpResponse {
+    let principal = match authorize(&db, &req, Scope::TasksRead).await {
+        Ok(principal) => principal,
+        Err(res) => return res,
+    };
+
+    let (res, session, stream) = match actix_ws::handle(&req, body) {
+        Ok(handshake) => handshake,
+        Err(err) => return HttpResponse::from_error(err),
+    };
+    let stream = stream
+        .max_frame_size(MAX_MESSAGE_SIZE)
+        .aggregate_continuations()
+        .max_continuation_size(MAX_MESSAGE_SIZE);
+
+    let connection = Connection {
+        db,
+        bus,
+        principal,
+        session,
+        subscriptions: HashSet::new(),
+    };
+    actix_web::rt::spawn(connection.run(stream));
+
+    res
+}
+
+struct Connection {
+    db: Data<DbClient>,
+    bus: Data<EventBus>,
+    principal: Principal,
+    session: Session,
+    /// Lists whose changes are forwarded, `None` standing for tasks outside
+    /// of any list
+    subscriptions: HashSet<Option<Uuid>>,
+}
+
+/// The connection is gone or too slow to keep.
+struct Disconnected;
+
+impl Connection {
+    async fn run(mut self, mut stream: AggregatedMessageStream) {
+        info!("{} connected to the WebSocket", self.principal.subject);
+
+        let (_, mut receiver) = self.bus.subscribe(None);
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        let mut last_seen = Instant::now();
+
+        let reason = loop {
+            tokio::select! {
+                message = stream.recv() => {
+                    let message = match message {
+                        Some(Ok(message)) => message,
+                        Some(Err(err)) => {
+                            debug!("WebSocket protocol error: {}", err);
+                            break Some(CloseReason::from(CloseCode::Protocol));
+                        }
+                        None => break None,
+                    };
+                    last_seen = Instant::now();
+
+                    let sent = match message {
+                        AggregatedMessage::Text(text) => {
+                            let reply = self.handle(&text).await;
+                            self.send(&reply).await
+                        }
+                        AggregatedMessage::Binary(_) => {
+                            self.send(&ServerMessage::Error {
+                                request_id: None,
+                                status: 400,
+                                message: "Only text messages are supported".to_string(),
+                            })
+                            .await
+                        }
+                        AggregatedMessage::Ping(bytes) => {
+                            self.session.pong(&bytes).await.map_err(|_| Disconnected)
+                        }
+                        AggregatedMessage::Pong(_) => Ok(()),
+                        AggregatedMessage::Close(reason) => break reason,
+                    };
+                    if sent.is_err() {
+                        break None;
+                    }
+                }
+                received = receiver.recv() => {
+                    let sent = match received {
+                        Ok(change) if self.is_subscribed(&change) => {
+                            self.send(&ServerMessage::Event {
+                                id: change.id,
+                                event: change.event.to_string(),
+                                task: PublicTask::from(change.task.clone()),
+                            })
+                            .await
+                        }
+                        Ok(_) => Ok(()),
+                        Err(RecvError::Lagged(_)) => self.send(&ServerMessage::Resync).await,
+                        Err(RecvError::Closed) => break Some(CloseReason::from(CloseCode::Restart)),
+                    };
+                    if sent.is_err() {
+                        break Some(CloseReason::from(CloseCode::Policy));
+                    }
+                }
+                _ = heartbeat.tick() => {
+                    if last_seen.elapsed() > CLIENT_TIMEOUT {
+                        debug!("{} timed out", self.principal.subject);
+                        break Some(CloseReason::from(CloseCode::Away));
+                    }
+                    if self.session.ping(b"").await.is_err() {
+                        break None;
+                    }
+                }
+            }
+        };
+
+        info!("{} disconnected from the WebSocket", self.principal.subject);
+        let _ = self.session.close(reason).await;
+    }
+
+    fn is_subscribed(&self, change: &ChangeEvent) -> bool {
+        self.subscriptions.contains(&change.task.list_id)
+    }
+
+    /// Sends a message, giving up on clients that stop reading. Events keep
+    /// queueing in the bus meanwhile, until the client is told to resync.
+    async fn send(&mut self, message: &ServerMessage) -> Result<(), Disconnected> {
+        let text = serde_json::to_string(message).expect("message is serializable");
+        match tokio::time::timeout(SEND_TIMEOUT, self.session.text(text)).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(_)) | Err(_) => Err(Disconnected),
+        }
+    }
+
+    async fn handle(&mut self, text: &str) -> ServerMessage {
+        let message: ClientMessage = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(err) => {
+                return ServerMessage::Error {
+                    request_id: None,
+                    status: 400,
+                    message: format!("Invalid message: {}", err),
+                }
+            }
+        };
+
+        let db = &self.db;
+        let bus = &self.bus;
+        let principal = &self.principal;
+
+        let (request_id, outcome) = match message {
+            ClientMessage::Subscribe {
+                request_id,
+                list_id,
+            } => {
+                if let Some(list_id) = list_id {
+                    if let Err(res) = require_role(db, principal, list_id, Role::Viewer).await {
+                        return error_message(request_id, res).await;
+                    }
+                }
+                self.subscriptions.insert(list_id);
+                return ServerMessage::Subscribed {
+                    request_id,
+                    list_id,
+                };
+            }
+            ClientMessage::Unsubscribe {
+                request_id,
+                list_id,
+            } => {
+                self.subscriptions.remove(&list_id);
+                return ServerMessage::Unsubscribed {
+                    request_id,
+                    list_id,
+                };
+            }
+            ClientMessage::Create { request_id, task } => {
+                let outcome = match require_scope(principal, Scope::TasksWrite) {
+                    Ok(()) => task::create(db, bus, principal, &task).await,
+                    Err(res) => Err(res),
+                };
+                (request_id, outcome.map(|task| (201, Some(task), None)))
+            }
+            ClientMessage::Update {
+                request_id,
+                task_id,
+                changes,
+            } => {
+                let outcome = match require_scope(principal, Scope::TasksWrite) {
+                    Ok(()) => task::update(db, bus, principal, task_id, &changes).await,
+                    Err(res) => Err(res),
+                };
+                (request_id, outcome.map(|task| (200, Some(task), None)))
+            }
+            ClientMessage::Delete {
+                request_id,
+                task_id,
+            } => {
+                let outcome = match require_scope(principal, Scope::TasksDelete) {
+                    Ok(()) => task::delete(db, bus, principal, task_id).await,
+                    Err(res) => Err(res),
+                };
+                (
+                    request_id,
+                    outcome.map(|status| (200, None, Some(status.rows_affected))),
+                )
+            }
+        };
+
+        match outcome {
+            Ok((status, task, rows_affected)) => ServerMessage::Result {
+                request_id,
+                status,
+                task: task.map(PublicTask::from),
+                rows_affected,
+            },
+            Err(res) => error_message(request_id, res).await,
+        }
+    }
+}
+
+/// Turns an error response of the REST handlers into a message.
+async fn error_message(request_id: Option<String>, res: HttpResponse) -> ServerMessage {
+    let status = res.status().as_u16();
+    let message = match actix_web::body::to_bytes(res.into_body()).await {
+        Ok(body) => String::from_utf8_lossy(&body).into_owned(),
+        Err(_) => String::new(),
+    };
+
+    ServerMessage::Error {
+        request_id,
+        status,
+        message,
+    }
+}
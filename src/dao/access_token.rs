@@ -0,0 +1,102 @@
+use crate::lib::errors::Error;
+use crate::lib::time::get_current_time;
+use entity::access_token;
+
+use sea_orm::entity::prelude::*;
+use sea_orm::{DatabaseConnection, QueryOrder};
+
+pub struct AccessTokenDao {
+    pub db_connection: DatabaseConnection,
+}
+
+impl AccessTokenDao {
+    pub fn init(db_connection: DatabaseConnection) -> Self {
+        AccessTokenDao { db_connection }
+    }
+
+    pub async fn create(
+        &self,
+        new_token: access_token::Model,
+    ) -> Result<access_token::Model, Error> {
+        debug!("access_token: create(<id: {}>)", new_token.id);
+
+        let active_model: access_token::ActiveModel = new_token.into();
+        let token = active_model.insert(&self.db_connection).await?;
+
+        Ok(token)
+    }
+
+    /// Tokens of `owner`, or all of them if `None`.
+    pub async fn find_all(&self, owner: Option<&str>) -> Result<Vec<access_token::Model>, Error> {
+        debug!("access_token: find_all({:?})", owner);
+
+        let mut select = access_token::Entity::find();
+        if let Some(owner) = owner {
+            select = select.filter(access_token::Column::Owner.eq(owner));
+        }
+
+        let tokens = select
+            .order_by_desc(access_token::Column::CreatedAt)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(tokens)
+    }
+
+    /// Looks up a token that has not been revoked by the hash of its secret.
+    pub async fn find_active_by_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<access_token::Model, Error> {
+        debug!("access_token: find_active_by_hash()");
+
+        let token = access_token::Entity::find()
+            .filter(access_token::Column::TokenHash.eq(token_hash))
+            .filter(access_token::Column::RevokedAt.is_null())
+            .one(&self.db_connection)
+            .await?;
+
+        match token {
+            Some(token) => Ok(token),
+            None => Err(Error::NotFound("Access Token".to_string())),
+        }
+    }
+
+    pub async fn touch(&self, token: access_token::Model) -> Result<access_token::Model, Error> {
+        debug!("access_token: touch(<id: {}>)", token.id);
+
+        let mut active_model: access_token::ActiveModel = token.into();
+        active_model.last_used_at = sea_orm::Set(Some(get_current_time()));
+
+        let token = active_model.update(&self.db_connection).await?;
+        Ok(token)
+    }
+
+    /// Revokes a token of `owner`, or of anyone if `None`. Tokens of other
+    /// owners are not found.
+    pub async fn revoke(
+        &self,
+        id: Uuid,
+        owner: Option<&str>,
+    ) -> Result<access_token::Model, Error> {
+        debug!("access_token: revoke({}, {:?})", id, owner);
+
+        let mut select = access_token::Entity::find_by_id(id);
+        if let Some(owner) = owner {
+            select = select.filter(access_token::Column::Owner.eq(owner));
+        }
+        let token = select.one(&self.db_connection).await?;
+
+        match token {
+            Some(token) if token.revoked_at.is_some() => Ok(token),
+            Some(token) => {
+                let mut active_model: access_token::ActiveModel = token.into();
+                active_model.revoked_at = sea_orm::Set(Some(get_current_time()));
+
+                let token = active_model.update(&self.db_connection).await?;
+                Ok(token)
+            }
+            None => Err(Error::NotFound(format!("Access Token <id: {}>", id))),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+
+use crate::lib::errors::Error;
+use entity::{attachment, task};
+
+use sea_orm::entity::prelude::*;
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, QueryOrder, QuerySelect, Statement,
+    TransactionTrait,
+};
+
+/// Checksums per `IN` list, well below the bind parameter limit of PostgreSQL.
+const CHECKSUM_CHUNK_SIZE: usize = 1000;
+
+pub struct AttachmentDao {
+    pub db_connection: DatabaseConnection,
+}
+
+impl AttachmentDao {
+    pub fn init(db_connection: DatabaseConnection) -> Self {
+        AttachmentDao { db_connection }
+    }
+
+    /// Inserts the attachment unless it would take its task beyond `quota`
+    /// bytes. The task is locked meanwhile, so that concurrent uploads are
+    /// counted against each other.
+    pub async fn create(
+        &self,
+        new_attachment: attachment::Model,
+        quota: u64,
+    ) -> Result<attachment::Model, Error> {
+        debug!("attachment: create({:?})", new_attachment);
+
+        let txn = self.db_connection.begin().await?;
+
+        let task_id = new_attachment.task_id.unwrap_or_default();
+        let task = task::Entity::find_by_id(task_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?;
+        if task.is_none() {
+            return Err(Error::NotFound(format!("Task <id: {}>", task_id)));
+        }
+
+        let used: i64 = attachment::Entity::find()
+            .filter(attachment::Column::TaskId.eq(task_id))
+            .all(&txn)
+            .await?
+            .iter()
+            .map(|attachment| attachment.size)
+            .sum();
+        if (used + new_attachment.size) as u64 > quota {
+            return Err(Error::QuotaExceeded(format!(
+                "Attachments of a task are limited to {} bytes, {} are left",
+                quota,
+                quota.saturating_sub(used as u64)
+            )));
+        }
+
+        let active_model: attachment::ActiveModel = new_attachment.into();
+        let attachment = active_model.insert(&txn).await?;
+
+        txn.commit().await?;
+        Ok(attachment)
+    }
+
+    pub async fn find_id(&self, id: Uuid) -> Result<attachment::Model, Error> {
+        debug!("attachment: find_id({})", id);
+
+        let attachment = attachment::Entity::find_by_id(id)
+            .one(&self.db_connection)
+            .await?;
+
+        match attachment {
+            Some(attachment) => Ok(attachment),
+            None => Err(Error::NotFound(format!("Attachment <id: {}>", id))),
+        }
+    }
+
+    pub async fn find_for_task(&self, task_id: Uuid) -> Result<Vec<attachment::Model>, Error> {
+        debug!("attachment: find_for_task({})", task_id);
+
+        let attachments = attachment::Entity::find()
+            .filter(attachment::Column::TaskId.eq(task_id))
+            .order_by_asc(attachment::Column::CreatedAt)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(attachments)
+    }
+
+    /// Combined size of the attachments of the task, in bytes.
+    pub async fn size_for_task(&self, task_id: Uuid) -> Result<u64, Error> {
+        debug!("attachment: size_for_task({})", task_id);
+
+        let size: i64 = self
+            .find_for_task(task_id)
+            .await?
+            .iter()
+            .map(|attachment| attachment.size)
+            .sum();
+        Ok(size as u64)
+    }
+
+    pub async fn delete_id(&self, id: Uuid) -> Result<sea_orm::DeleteResult, Error> {
+        debug!("attachment: delete_id({})", id);
+
+        let res: sea_orm::DeleteResult = attachment::Entity::delete_by_id(id)
+            .exec(&self.db_connection)
+            .await?;
+        Ok(res)
+    }
+
+    /// Deletes the attachments whose task was deleted, returning the
+    /// checksums of their files.
+    pub async fn delete_detached(&self) -> Result<Vec<String>, Error> {
+        debug!("attachment: delete_detached()");
+
+        let rows = self
+            .db_connection
+            .query_all(Statement::from_string(
+                DbBackend::Postgres,
+                "DELETE FROM attachment WHERE task_id IS NULL RETURNING checksum".to_owned(),
+            ))
+            .await?;
+
+        let checksums = rows
+            .iter()
+            .map(|row| row.try_get::<String>("", "checksum"))
+            .collect::<Result<_, _>>()?;
+        Ok(checksums)
+    }
+
+    /// Those of `checksums` still referenced by an attachment.
+    pub async fn referenced(&self, checksums: &[String]) -> Result<HashSet<String>, Error> {
+        debug!("attachment: referenced({} checksums)", checksums.len());
+
+        let mut referenced = HashSet::new();
+        for chunk in checksums.chunks(CHECKSUM_CHUNK_SIZE) {
+            let attachments = attachment::Entity::find()
+                .filter(attachment::Column::Checksum.is_in(chunk.iter().cloned()))
+                .all(&self.db_connection)
+                .await?;
+            referenced.extend(
+                attachments
+                    .into_iter()
+                    .map(|attachment| attachment.checksum),
+            );
+        }
+
+        Ok(referenced)
+    }
+}
@@ -0,0 +1,207 @@
+use crate::lib::errors::Error;
+use crate::model::backup::{Backup, BackupTables, BACKUP_FORMAT_VERSION};
+use entity::{
+    access_token, attachment, comment, list_member, reminder, task, task_event, task_list,
+    webhook_delivery, webhook_subscription,
+};
+
+use chrono::Utc;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::entity::prelude::*;
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbBackend, IntoActiveModel,
+    QueryOrder, Statement, TransactionTrait,
+};
+
+/// Rows per `INSERT`, well below the bind parameter limit of PostgreSQL.
+const RESTORE_CHUNK_SIZE: usize = 500;
+
+pub struct BackupDao {
+    pub db_connection: DatabaseConnection,
+}
+
+/// Inserts the rows of one table, converted from their backup records.
+async fn insert_all<A, R>(txn: &DatabaseTransaction, records: Vec<R>) -> Result<(), Error>
+where
+    A: ActiveModelTrait,
+    <A::Entity as EntityTrait>::Model: From<R> + IntoActiveModel<A>,
+{
+    let mut models: Vec<A> = records
+        .into_iter()
+        .map(|record| <A::Entity as EntityTrait>::Model::from(record).into_active_model())
+        .collect();
+    while !models.is_empty() {
+        let rest = models.split_off(models.len().min(RESTORE_CHUNK_SIZE));
+        <A::Entity as EntityTrait>::insert_many(models)
+            .exec(txn)
+            .await?;
+        models = rest;
+    }
+    Ok(())
+}
+
+impl BackupDao {
+    pub fn init(db_connection: DatabaseConnection) -> Self {
+        BackupDao { db_connection }
+    }
+
+    /// Name of the last migration, as long as the database has exactly the
+    /// migrations of `Migrator` applied. Its tables would not match the
+    /// entities otherwise.
+    pub async fn schema_version(&self) -> Result<String, Error> {
+        debug!("backup: schema_version()");
+
+        let applied: Vec<String> = Migrator::get_migration_models(&self.db_connection)
+            .await?
+            .into_iter()
+            .map(|migration| migration.version)
+            .collect();
+        let known: Vec<String> = Migrator::migrations()
+            .iter()
+            .map(|migration| migration.name().to_string())
+            .collect();
+
+        if applied != known {
+            return Err(Error::SchemaMismatch(format!(
+                "The database is at `{}`, this server expects `{}`",
+                applied.last().map(String::as_str).unwrap_or("no migration"),
+                known.last().map(String::as_str).unwrap_or("no migration"),
+            )));
+        }
+        Ok(known.last().cloned().unwrap_or_default())
+    }
+
+    /// Reads every table within one transaction, so that the backup is a
+    /// consistent snapshot.
+    pub async fn export(&self) -> Result<Backup, Error> {
+        debug!("backup: export()");
+
+        let schema_version = self.schema_version().await?;
+        let txn = self.db_connection.begin().await?;
+        // Every statement of a READ COMMITTED transaction sees the changes
+        // committed before it, rather than those before the first one
+        txn.execute(Statement::from_string(
+            DbBackend::Postgres,
+            "SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY".to_owned(),
+        ))
+        .await?;
+
+        let tables = BackupTables {
+            access_tokens: access_token::Entity::find()
+                .order_by_asc(access_token::Column::CreatedAt)
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            task_lists: task_list::Entity::find()
+                .order_by_asc(task_list::Column::CreatedAt)
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            list_members: list_member::Entity::find()
+                .order_by_asc(list_member::Column::CreatedAt)
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            tasks: task::Entity::find()
+                .order_by_asc(task::Column::CreatedAt)
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            reminders: reminder::Entity::find()
+                .order_by_asc(reminder::Column::CreatedAt)
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            attachments: attachment::Entity::find()
+                .order_by_asc(attachment::Column::CreatedAt)
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            comments: comment::Entity::find()
+                .order_by_asc(comment::Column::CreatedAt)
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            task_events: task_event::Entity::find()
+                .order_by_asc(task_event::Column::CreatedAt)
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            webhook_subscriptions: webhook_subscription::Entity::find()
+                .order_by_asc(webhook_subscription::Column::CreatedAt)
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            webhook_deliveries: webhook_delivery::Entity::find()
+                .order_by_asc(webhook_delivery::Column::CreatedAt)
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        };
+
+        txn.commit().await?;
+        Ok(Backup {
+            version: BACKUP_FORMAT_VERSION,
+            schema_version,
+            created_at: Utc::now(),
+            tables,
+        })
+    }
+
+    /// Replaces the contents of every table with `tables` in one
+    /// transaction, keeping their ids and timestamps.
+    pub async fn restore(&self, tables: BackupTables) -> Result<(), Error> {
+        debug!("backup: restore()");
+
+        let txn = self.db_connection.begin().await?;
+
+        // Referencing tables first, then the ones they reference
+        webhook_delivery::Entity::delete_many().exec(&txn).await?;
+        webhook_subscription::Entity::delete_many()
+            .exec(&txn)
+            .await?;
+        task_event::Entity::delete_many().exec(&txn).await?;
+        reminder::Entity::delete_many().exec(&txn).await?;
+        attachment::Entity::delete_many().exec(&txn).await?;
+        comment::Entity::delete_many().exec(&txn).await?;
+        task::Entity::delete_many().exec(&txn).await?;
+        list_member::Entity::delete_many().exec(&txn).await?;
+        task_list::Entity::delete_many().exec(&txn).await?;
+        access_token::Entity::delete_many().exec(&txn).await?;
+
+        insert_all::<access_token::ActiveModel, _>(&txn, tables.access_tokens).await?;
+        insert_all::<task_list::ActiveModel, _>(&txn, tables.task_lists).await?;
+        insert_all::<list_member::ActiveModel, _>(&txn, tables.list_members).await?;
+        insert_all::<task::ActiveModel, _>(&txn, tables.tasks).await?;
+        insert_all::<reminder::ActiveModel, _>(&txn, tables.reminders).await?;
+        insert_all::<attachment::ActiveModel, _>(&txn, tables.attachments).await?;
+        insert_all::<comment::ActiveModel, _>(&txn, tables.comments).await?;
+        insert_all::<task_event::ActiveModel, _>(&txn, tables.task_events).await?;
+        insert_all::<webhook_subscription::ActiveModel, _>(&txn, tables.webhook_subscriptions)
+            .await?;
+        insert_all::<webhook_delivery::ActiveModel, _>(&txn, tables.webhook_deliveries).await?;
+
+        txn.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use crate::lib::errors::Error;
+use crate::lib::time::get_current_time;
+use entity::comment;
+
+use sea_orm::entity::prelude::*;
+use sea_orm::{DatabaseConnection, FromQueryResult, QueryOrder, QuerySelect};
+
+/// Task ids per `IN` list, well below the bind parameter limit of PostgreSQL.
+const TASK_CHUNK_SIZE: usize = 1000;
+
+#[derive(Debug, FromQueryResult)]
+struct CommentCount {
+    task_id: Uuid,
+    count: i64,
+}
+
+pub struct CommentDao {
+    pub db_connection: DatabaseConnection,
+}
+
+impl CommentDao {
+    pub fn init(db_connection: DatabaseConnection) -> Self {
+        CommentDao { db_connection }
+    }
+
+    pub async fn create(&self, new_comment: comment::Model) -> Result<comment::Model, Error> {
+        debug!("comment: create({:?})", new_comment);
+
+        let active_model: comment::ActiveModel = new_comment.into();
+        let comment = active_model.insert(&self.db_connection).await?;
+        Ok(comment)
+    }
+
+    pub async fn find_id(&self, id: Uuid) -> Result<comment::Model, Error> {
+        debug!("comment: find_id({})", id);
+
+        let comment = comment::Entity::find_by_id(id)
+            .one(&self.db_connection)
+            .await?;
+
+        match comment {
+            Some(comment) => Ok(comment),
+            None => Err(Error::NotFound(format!("Comment <id: {}>", id))),
+        }
+    }
+
+    /// A page of the task's comments, oldest first.
+    pub async fn find_for_task(
+        &self,
+        task_id: Uuid,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<comment::Model>, Error> {
+        debug!(
+            "comment: find_for_task({}, limit: {}, offset: {})",
+            task_id, limit, offset
+        );
+
+        let comments = comment::Entity::find()
+            .filter(comment::Column::TaskId.eq(task_id))
+            .order_by_asc(comment::Column::CreatedAt)
+            .order_by_asc(comment::Column::Id)
+            .limit(limit)
+            .offset(offset)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(comments)
+    }
+
+    /// Number of comments of each of the tasks, in one query per thousand
+    /// tasks rather than one per task. Tasks without comments are left out.
+    pub async fn count_for_tasks(&self, task_ids: &[Uuid]) -> Result<HashMap<Uuid, u64>, Error> {
+        debug!("comment: count_for_tasks({} tasks)", task_ids.len());
+
+        let mut counts = HashMap::new();
+        for chunk in task_ids.chunks(TASK_CHUNK_SIZE) {
+            let chunk_counts = comment::Entity::find()
+                .select_only()
+                .column(comment::Column::TaskId)
+                .column_as(comment::Column::Id.count(), "count")
+                .filter(comment::Column::TaskId.is_in(chunk.iter().copied()))
+                .group_by(comment::Column::TaskId)
+                .into_model::<CommentCount>()
+                .all(&self.db_connection)
+                .await?;
+            counts.extend(
+                chunk_counts
+                    .into_iter()
+                    .map(|count| (count.task_id, count.count as u64)),
+            );
+        }
+
+        Ok(counts)
+    }
+
+    pub async fn update_body(
+        &self,
+        comment: comment::Model,
+        body: String,
+    ) -> Result<comment::Model, Error> {
+        debug!("comment: update_body({})", comment.id);
+
+        let mut active_model: comment::ActiveModel = comment.into();
+        active_model.body = sea_orm::Set(body);
+        active_model.updated_at = sea_orm::Set(Some(get_current_time()));
+
+        let comment = active_model.update(&self.db_connection).await?;
+        Ok(comment)
+    }
+
+    pub async fn delete_id(&self, id: Uuid) -> Result<sea_orm::DeleteResult, Error> {
+        debug!("comment: delete_id({})", id);
+
+        let res: sea_orm::DeleteResult = comment::Entity::delete_by_id(id)
+            .exec(&self.db_connection)
+            .await?;
+        Ok(res)
+    }
+}
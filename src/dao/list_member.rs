@@ -0,0 +1,138 @@
+use crate::lib::errors::Error;
+use crate::lib::time::get_current_time;
+use entity::list_member::{self, Role};
+
+use sea_orm::entity::prelude::*;
+use sea_orm::{DatabaseConnection, QueryOrder};
+
+pub struct ListMemberDao {
+    pub db_connection: DatabaseConnection,
+}
+
+impl ListMemberDao {
+    pub fn init(db_connection: DatabaseConnection) -> Self {
+        ListMemberDao { db_connection }
+    }
+
+    pub async fn invite(
+        &self,
+        membership: list_member::Model,
+    ) -> Result<list_member::Model, Error> {
+        debug!("list_member: invite({:?})", membership);
+
+        let active_model: list_member::ActiveModel = membership.into();
+        let membership = active_model.insert(&self.db_connection).await?;
+
+        Ok(membership)
+    }
+
+    pub async fn find(&self, list_id: Uuid, member: &str) -> Result<list_member::Model, Error> {
+        debug!("list_member: find({}, {})", list_id, member);
+
+        let membership = list_member::Entity::find_by_id((list_id, member.to_string()))
+            .one(&self.db_connection)
+            .await?;
+
+        match membership {
+            Some(membership) => Ok(membership),
+            None => Err(Error::NotFound(format!(
+                "Membership <list: {}, member: {}>",
+                list_id, member
+            ))),
+        }
+    }
+
+    /// Role of `member` in the list, if they accepted an invitation to it.
+    pub async fn find_role(&self, list_id: Uuid, member: &str) -> Result<Option<Role>, Error> {
+        match self.find(list_id, member).await {
+            Ok(membership) if membership.accepted_at.is_some() => Ok(Some(membership.role)),
+            Ok(_) | Err(Error::NotFound(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn find_for_list(&self, list_id: Uuid) -> Result<Vec<list_member::Model>, Error> {
+        debug!("list_member: find_for_list({})", list_id);
+
+        let members = list_member::Entity::find()
+            .filter(list_member::Column::ListId.eq(list_id))
+            .order_by_asc(list_member::Column::CreatedAt)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(members)
+    }
+
+    /// Accepted memberships of `member` holding at least `min_role`.
+    pub async fn find_for_member(
+        &self,
+        member: &str,
+        min_role: Role,
+    ) -> Result<Vec<list_member::Model>, Error> {
+        debug!("list_member: find_for_member({}, {:?})", member, min_role);
+
+        let memberships = list_member::Entity::find()
+            .filter(list_member::Column::Member.eq(member))
+            .filter(list_member::Column::AcceptedAt.is_not_null())
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(memberships
+            .into_iter()
+            .filter(|membership| membership.role >= min_role)
+            .collect())
+    }
+
+    pub async fn find_invitations(&self, member: &str) -> Result<Vec<list_member::Model>, Error> {
+        debug!("list_member: find_invitations({})", member);
+
+        let invitations = list_member::Entity::find()
+            .filter(list_member::Column::Member.eq(member))
+            .filter(list_member::Column::AcceptedAt.is_null())
+            .order_by_asc(list_member::Column::CreatedAt)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(invitations)
+    }
+
+    pub async fn accept(&self, list_id: Uuid, member: &str) -> Result<list_member::Model, Error> {
+        debug!("list_member: accept({}, {})", list_id, member);
+
+        let membership = self.find(list_id, member).await?;
+        if membership.accepted_at.is_some() {
+            return Ok(membership);
+        }
+
+        let mut active_model: list_member::ActiveModel = membership.into();
+        active_model.accepted_at = sea_orm::Set(Some(get_current_time()));
+
+        let membership = active_model.update(&self.db_connection).await?;
+        Ok(membership)
+    }
+
+    pub async fn count_owners(&self, list_id: Uuid) -> Result<usize, Error> {
+        let owners = list_member::Entity::find()
+            .filter(list_member::Column::ListId.eq(list_id))
+            .filter(list_member::Column::Role.eq(Role::Owner))
+            .filter(list_member::Column::AcceptedAt.is_not_null())
+            .count(&self.db_connection)
+            .await?;
+
+        Ok(owners)
+    }
+
+    pub async fn revoke(
+        &self,
+        list_id: Uuid,
+        member: &str,
+    ) -> Result<sea_orm::DeleteResult, Error> {
+        debug!("list_member: revoke({}, {})", list_id, member);
+
+        let res: sea_orm::DeleteResult =
+            list_member::Entity::delete_by_id((list_id, member.to_string()))
+                .exec(&self.db_connection)
+                .await?;
+        Ok(res)
+    }
+}
@@ -1,2 +1,13 @@
+pub mod access_token;
+pub mod attachment;
+pub mod backup;
+pub mod comment;
+pub mod list_member;
+pub mod reminder;
+pub mod stats;
 pub mod task;
+pub mod task_event;
+pub mod task_list;
 pub mod utils;
+pub mod webhook_delivery;
+pub mod webhook_subscription;
@@ -0,0 +1,155 @@
+use crate::lib::errors::Error;
+use crate::lib::time::get_current_time;
+use entity::reminder;
+
+use chrono::{DateTime, Duration, Utc};
+use migration::{Condition, Expr, LockBehavior, LockType};
+use sea_orm::entity::prelude::*;
+use sea_orm::{DatabaseConnection, QueryOrder, QuerySelect, QueryTrait, TransactionTrait};
+
+pub struct ReminderDao {
+    pub db_connection: DatabaseConnection,
+}
+
+impl ReminderDao {
+    pub fn init(db_connection: DatabaseConnection) -> Self {
+        ReminderDao { db_connection }
+    }
+
+    pub async fn find_for_task(&self, task_id: Uuid) -> Result<Vec<reminder::Model>, Error> {
+        debug!("reminder: find_for_task({})", task_id);
+
+        let reminders = reminder::Entity::find()
+            .filter(reminder::Column::TaskId.eq(task_id))
+            .order_by_asc(reminder::Column::RemindAt)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(reminders)
+    }
+
+    /// Replaces every reminder of the task with ones at `offsets_ms` before
+    /// `deadline`, leaving out those before the earliest representable time.
+    pub async fn replace_for_task(
+        &self,
+        task_id: Uuid,
+        deadline: DateTime<Utc>,
+        offsets_ms: &[i64],
+    ) -> Result<Vec<reminder::Model>, Error> {
+        debug!("reminder: replace_for_task({}, {:?})", task_id, offsets_ms);
+
+        let txn = self.db_connection.begin().await?;
+
+        reminder::Entity::delete_many()
+            .filter(reminder::Column::TaskId.eq(task_id))
+            .exec(&txn)
+            .await?;
+
+        let mut reminders = Vec::with_capacity(offsets_ms.len());
+        for reminder in offsets_ms
+            .iter()
+            .filter_map(|offset_ms| reminder::Model::new(task_id, *offset_ms, deadline))
+        {
+            let active_model: reminder::ActiveModel = reminder.into();
+            reminders.push(active_model.insert(&txn).await?);
+        }
+
+        txn.commit().await?;
+        Ok(reminders)
+    }
+
+    /// Moves the reminders of the task along with its new `deadline`, re-arming
+    /// those that were already sent but are due again in the future.
+    pub async fn reschedule(&self, task_id: Uuid, deadline: DateTime<Utc>) -> Result<(), Error> {
+        debug!("reminder: reschedule({}, {})", task_id, deadline);
+
+        let now = get_current_time();
+        for reminder in self.find_for_task(task_id).await? {
+            let remind_at =
+                match deadline.checked_sub_signed(Duration::milliseconds(reminder.offset_ms)) {
+                    Some(remind_at) if remind_at != reminder.remind_at => remind_at,
+                    _ => continue,
+                };
+
+            let mut active_model: reminder::ActiveModel = reminder.into();
+            active_model.remind_at = sea_orm::Set(remind_at);
+            if remind_at > now {
+                active_model.sent_at = sea_orm::Set(None);
+                active_model.attempts = sea_orm::Set(0);
+                active_model.next_attempt_at = sea_orm::Set(None);
+            }
+            active_model.update(&self.db_connection).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Claims up to `limit` due reminders with fewer than `max_attempts`
+    /// failed attempts, skipping those locked by another instance. Claimed
+    /// reminders are not due again before `lease` has passed, so that they
+    /// can be sent without holding a lock.
+    pub async fn claim_due(
+        &self,
+        limit: u64,
+        max_attempts: i32,
+        lease: Duration,
+    ) -> Result<Vec<reminder::Model>, Error> {
+        let txn = self.db_connection.begin().await?;
+
+        let now = get_current_time();
+        let mut select = reminder::Entity::find()
+            .filter(reminder::Column::SentAt.is_null())
+            .filter(reminder::Column::RemindAt.lte(now))
+            .filter(reminder::Column::Attempts.lt(max_attempts))
+            .filter(
+                Condition::any()
+                    .add(reminder::Column::NextAttemptAt.is_null())
+                    .add(reminder::Column::NextAttemptAt.lte(now)),
+            )
+            .order_by_asc(reminder::Column::RemindAt)
+            .limit(limit);
+        QueryTrait::query(&mut select)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked);
+        let reminders = select.all(&txn).await?;
+
+        if !reminders.is_empty() {
+            reminder::Entity::update_many()
+                .col_expr(reminder::Column::NextAttemptAt, Expr::value(now + lease))
+                .filter(reminder::Column::Id.is_in(reminders.iter().map(|reminder| reminder.id)))
+                .exec(&txn)
+                .await?;
+        }
+
+        txn.commit().await?;
+        Ok(reminders)
+    }
+
+    pub async fn mark_sent(&self, reminder: reminder::Model) -> Result<reminder::Model, Error> {
+        debug!("reminder: mark_sent({})", reminder.id);
+
+        let mut active_model: reminder::ActiveModel = reminder.into();
+        active_model.sent_at = sea_orm::Set(Some(get_current_time()));
+        active_model.next_attempt_at = sea_orm::Set(None);
+
+        let reminder = active_model.update(&self.db_connection).await?;
+        Ok(reminder)
+    }
+
+    /// Records a failed attempt. The reminder is retried at `retry_at`, or
+    /// given up on if there is none.
+    pub async fn record_failure(
+        &self,
+        reminder: reminder::Model,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<reminder::Model, Error> {
+        debug!("reminder: record_failure({}, {:?})", reminder.id, retry_at);
+
+        let attempts = reminder.attempts + 1;
+        let mut active_model: reminder::ActiveModel = reminder.into();
+        active_model.attempts = sea_orm::Set(attempts);
+        active_model.next_attempt_at = sea_orm::Set(retry_at);
+
+        let reminder = active_model.update(&self.db_connection).await?;
+        Ok(reminder)
+    }
+}
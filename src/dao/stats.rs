@@ -0,0 +1,156 @@
+use crate::dao::utils;
+use crate::lib::errors::Error;
+use crate::lib::query;
+use entity::task;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use migration::{Expr, SimpleExpr};
+use sea_orm::entity::prelude::*;
+use sea_orm::{DatabaseConnection, FromQueryResult, QueryOrder, QuerySelect};
+
+/// Tasks by state, as of the time of the query.
+#[derive(Debug, Default, FromQueryResult)]
+pub struct StateCounts {
+    pub open: i64,
+    pub completed: i64,
+    pub overdue: i64,
+}
+
+/// Tasks whose date falls into the bucket starting at `bucket`.
+#[derive(Debug, FromQueryResult)]
+pub struct BucketCount {
+    pub bucket: DateTime<Utc>,
+    pub count: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct Average {
+    seconds: Option<f64>,
+}
+
+/// Aggregates over tasks, computed by the database rather than by loading
+/// the tasks.
+pub struct StatsDao {
+    pub db_connection: DatabaseConnection,
+}
+
+/// Start of the bucket `column` falls into, truncated in `tz`.
+fn truncate(column: task::Column, bucket: query::Bucket, tz: &Tz) -> SimpleExpr {
+    Expr::cust_with_values(
+        &format!(
+            r#"date_trunc($1, "{}" AT TIME ZONE $2) AT TIME ZONE $2"#,
+            column.to_string()
+        ),
+        vec![bucket.as_str(), tz.name()],
+    )
+}
+
+impl StatsDao {
+    pub fn init(db_connection: DatabaseConnection) -> Self {
+        StatsDao { db_connection }
+    }
+
+    pub async fn count_by_state(
+        &self,
+        filter: &query::TaskFilter,
+        now: DateTime<Utc>,
+    ) -> Result<StateCounts, Error> {
+        debug!("stats: count_by_state(filter: {}, now: {})", filter, now);
+
+        let counts = task::Entity::find()
+            .select_only()
+            .column_as(
+                Expr::cust(r#"count(*) FILTER (WHERE NOT "completed")"#),
+                "open",
+            )
+            .column_as(
+                Expr::cust(r#"count(*) FILTER (WHERE "completed")"#),
+                "completed",
+            )
+            .column_as(
+                Expr::cust_with_values(
+                    r#"count(*) FILTER (WHERE NOT "completed" AND "deadline" < $1)"#,
+                    vec![now],
+                ),
+                "overdue",
+            )
+            .filter(utils::construct_task_filter(filter))
+            .into_model::<StateCounts>()
+            .one(&self.db_connection)
+            .await?;
+
+        Ok(counts.unwrap_or_default())
+    }
+
+    /// Tasks per bucket whose `column` lies within `[from, to)`, leaving out
+    /// empty buckets.
+    pub async fn count_per_bucket(
+        &self,
+        column: task::Column,
+        bucket: query::Bucket,
+        tz: &Tz,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        filter: &query::TaskFilter,
+    ) -> Result<Vec<BucketCount>, Error> {
+        debug!(
+            "stats: count_per_bucket(column: {}, bucket: {:?}, tz: {}, from: {}, to: {}, filter: {})",
+            column.to_string(),
+            bucket,
+            tz,
+            from,
+            to,
+            filter
+        );
+
+        // Grouped by position, as the bound values of the bucket expression
+        // would make it differ from its copy in `GROUP BY`
+        let counts = task::Entity::find()
+            .select_only()
+            .column_as(truncate(column, bucket, tz), "bucket")
+            .column_as(Expr::cust("count(*)"), "count")
+            .filter(utils::construct_task_filter(filter))
+            .filter(column.gte(from))
+            .filter(column.lt(to))
+            .group_by(Expr::cust("1"))
+            .order_by_asc(Expr::cust("1"))
+            .into_model::<BucketCount>()
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(counts)
+    }
+
+    /// Mean time from creation to completion of the tasks completed within
+    /// `[from, to)`, `None` if there are none.
+    pub async fn average_completion_seconds(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        filter: &query::TaskFilter,
+    ) -> Result<Option<f64>, Error> {
+        debug!(
+            "stats: average_completion_seconds(from: {}, to: {}, filter: {})",
+            from, to, filter
+        );
+
+        let average = task::Entity::find()
+            .select_only()
+            .column_as(
+                Expr::cust(
+                    r#"CAST(avg(extract(epoch FROM "completed_at" - "created_at")) AS double precision)"#,
+                ),
+                "seconds",
+            )
+            .filter(utils::construct_task_filter(filter))
+            .filter(task::Column::Completed.eq(true))
+            .filter(task::Column::CompletedAt.gte(from))
+            .filter(task::Column::CompletedAt.lt(to))
+            .into_model::<Average>()
+            .one(&self.db_connection)
+            .await?;
+
+        Ok(average.and_then(|average| average.seconds))
+    }
+}
@@ -1,12 +1,19 @@
+use std::collections::{HashMap, HashSet};
+
 use crate::dao::utils;
 use crate::lib::errors::Error;
+use crate::lib::position;
 use crate::lib::query;
+use crate::lib::time::get_current_time;
 use entity::task;
 
 use chrono::{DateTime, Utc};
-use migration::Condition;
+use migration::{Condition, SimpleExpr};
 use sea_orm::entity::prelude::*;
-use sea_orm::{DatabaseConnection, QueryOrder};
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbBackend, QueryOrder, Statement,
+    TransactionTrait,
+};
 
 pub struct TaskDao {
     pub db_connection: DatabaseConnection,
@@ -17,15 +24,36 @@ impl TaskDao {
         TaskDao { db_connection }
     }
 
-    pub async fn create(&self, new_task: task::Model) -> Result<task::Model, Error> {
+    pub async fn create(&self, mut new_task: task::Model) -> Result<task::Model, Error> {
         debug!("todo: create({:?})", new_task);
 
+        place(&self.db_connection, &mut new_task).await?;
         let active_model: task::ActiveModel = new_task.into();
         let task = active_model.insert(&self.db_connection).await?;
 
         Ok(task)
     }
 
+    /// Inserts all of `new_tasks` or, if any fails, none of them.
+    pub async fn create_many(
+        &self,
+        new_tasks: Vec<task::Model>,
+    ) -> Result<Vec<task::Model>, Error> {
+        debug!("todo: create_many({} tasks)", new_tasks.len());
+
+        let txn = self.db_connection.begin().await?;
+
+        let mut tasks = Vec::with_capacity(new_tasks.len());
+        for mut new_task in new_tasks {
+            place(&txn, &mut new_task).await?;
+            let active_model: task::ActiveModel = new_task.into();
+            tasks.push(active_model.insert(&txn).await?);
+        }
+
+        txn.commit().await?;
+        Ok(tasks)
+    }
+
     pub async fn find_id(&self, id: Uuid) -> Result<task::Model, Error> {
         debug!("todo: find_id({:?})", id);
 
@@ -39,17 +67,31 @@ impl TaskDao {
         }
     }
 
+    /// Those of `ids` that belong to an existing task.
+    pub async fn find_existing_ids(&self, ids: Vec<Uuid>) -> Result<Vec<Uuid>, Error> {
+        debug!("todo: find_existing_ids({} ids)", ids.len());
+
+        let tasks = task::Entity::find()
+            .filter(task::Column::Id.is_in(ids))
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(tasks.into_iter().map(|task| task.id).collect())
+    }
+
     pub async fn find_all(
         &self,
         sort_attrib: String,
         sort_order: query::SortOrder,
+        filter: &query::TaskFilter,
     ) -> Result<Vec<task::Model>, Error> {
         debug!(
-            "to_do: find_all(sort_attrib: {}, sort_order: {})",
-            sort_attrib, sort_order
+            "to_do: find_all(sort_attrib: {}, sort_order: {}, filter: {})",
+            sort_attrib, sort_order, filter
         );
 
         let tasks = task::Entity::find()
+            .filter(utils::construct_task_filter(filter))
             .order_by(
                 utils::match_task_column(&sort_attrib)?,
                 utils::match_sort_order(&sort_order),
@@ -66,13 +108,15 @@ impl TaskDao {
         verb: query::FilterOps,
         date: DateTime<Utc>,
         sort_order: query::SortOrder,
+        filter: &query::TaskFilter,
     ) -> Result<Vec<task::Model>, Error> {
         debug!(
-            "to_do: find_with_params(attrib: {}, verb: {}, date: {}, sort_order: {})",
-            attrib, verb, date, sort_order
+            "to_do: find_with_params(attrib: {}, verb: {}, date: {}, sort_order: {}, filter: {})",
+            attrib, verb, date, sort_order, filter
         );
 
         let tasks = task::Entity::find()
+            .filter(utils::construct_task_filter(filter))
             .filter(utils::construct_filter(
                 utils::match_task_column(&attrib)?,
                 verb,
@@ -94,13 +138,15 @@ impl TaskDao {
         start: DateTime<Utc>,
         end: DateTime<Utc>,
         sort_order: query::SortOrder,
+        filter: &query::TaskFilter,
     ) -> Result<Vec<task::Model>, Error> {
         debug!(
-            "to_do: find_between(attrib: {}, start: {}, end: {}, sort_order: {})",
-            attrib, start, end, sort_order
+            "to_do: find_between(attrib: {}, start: {}, end: {}, sort_order: {}, filter: {})",
+            attrib, start, end, sort_order, filter
         );
 
         let tasks = task::Entity::find()
+            .filter(utils::construct_task_filter(filter))
             .filter(
                 Condition::all()
                     .add(utils::construct_filter(
@@ -127,40 +173,526 @@ impl TaskDao {
     pub async fn update_task(&self, id: Uuid, new_task: task::Model) -> Result<task::Model, Error> {
         debug!("todo: update_task({:?}, {:?})", id, new_task);
 
-        let task = task::Entity::find_by_id(id)
-            .one(&self.db_connection)
-            .await?;
-        match task {
-            Some(task) => {
-                let mut active_model: task::ActiveModel = task.into();
+        apply_update(&self.db_connection, id, new_task).await
+    }
+
+    /// Completes an occurrence of a recurring task and creates the next one
+    /// in the same transaction.
+    pub async fn update_with_next_occurrence(
+        &self,
+        id: Uuid,
+        new_task: task::Model,
+        mut next_task: task::Model,
+    ) -> Result<(task::Model, Option<task::Model>), Error> {
+        debug!(
+            "todo: update_with_next_occurrence({:?}, {:?}, {:?})",
+            id, new_task, next_task
+        );
+
+        let txn = self.db_connection.begin().await?;
 
-                active_model.title = sea_orm::Set(new_task.title);
-                active_model.completed = sea_orm::Set(new_task.completed);
-                active_model.deadline = sea_orm::Set(new_task.deadline);
+        let updated_task = apply_update(&txn, id, new_task).await?;
 
-                let updated_task = active_model.update(&self.db_connection).await?;
-                Ok(updated_task)
+        // Completing an occurrence again, after reopening it, keeps the
+        // next occurrence created the first time
+        if let Some(series_id) = next_task.series_id {
+            let existing = task::Entity::find()
+                .filter(task::Column::SeriesId.eq(series_id))
+                .filter(task::Column::Occurrence.eq(next_task.occurrence))
+                .one(&txn)
+                .await?;
+            if existing.is_some() {
+                txn.commit().await?;
+                return Ok((updated_task, None));
             }
-            None => Err(Error::NotFound(format!("Task <id: {}>", id))),
         }
+
+        // The next occurrence takes the place of the completed one
+        if next_task.list_id == updated_task.list_id {
+            let following = position_after(&txn, &updated_task, updated_task.id).await?;
+            next_task.position =
+                position::between(Some(&updated_task.position), following.as_deref())
+                    .unwrap_or_default();
+        }
+        place(&txn, &mut next_task).await?;
+        let active_model: task::ActiveModel = next_task.into();
+        let next_task = active_model.insert(&txn).await?;
+
+        txn.commit().await?;
+        Ok((updated_task, Some(next_task)))
     }
 
-    pub async fn delete_id(&self, id: Uuid) -> Result<sea_orm::DeleteResult, Error> {
-        debug!("to_do: delete_id({})", id);
+    /// Applies all updates or none of them.
+    pub async fn update_many(
+        &self,
+        new_tasks: Vec<(Uuid, task::Model)>,
+    ) -> Result<Vec<task::Model>, Error> {
+        debug!("todo: update_many({} tasks)", new_tasks.len());
 
-        let res: sea_orm::DeleteResult = task::Entity::delete_by_id(id)
-            .exec(&self.db_connection)
+        let txn = self.db_connection.begin().await?;
+
+        let mut updated_tasks = Vec::with_capacity(new_tasks.len());
+        for (id, new_task) in new_tasks {
+            updated_tasks.push(apply_update(&txn, id, new_task).await?);
+        }
+
+        txn.commit().await?;
+        Ok(updated_tasks)
+    }
+
+    pub async fn begin(&self) -> Result<DatabaseTransaction, Error> {
+        Ok(self.db_connection.begin().await?)
+    }
+
+    /// Brings tasks back to an earlier version, recreating deleted ones with
+    /// their original id, and deletes others. Left to the caller to commit
+    /// `txn`, along with the record of the changes. Returns the restored
+    /// tasks, and the subtasks of deleted ones as they were and as they are.
+    pub async fn restore(
+        &self,
+        txn: &DatabaseTransaction,
+        versions: Vec<task::Model>,
+        deletions: Vec<Uuid>,
+    ) -> Result<(Vec<task::Model>, Vec<(task::Model, task::Model)>), Error> {
+        debug!(
+            "todo: restore({} tasks, deleting {:?})",
+            versions.len(),
+            deletions
+        );
+
+        let mut restored_tasks = Vec::with_capacity(versions.len());
+        for version in versions {
+            let exists = task::Entity::find_by_id(version.id)
+                .one(txn)
+                .await?
+                .is_some();
+            let restored_task = if exists {
+                apply_update(txn, version.id, version).await?
+            } else {
+                let mut version = version;
+                place(txn, &mut version).await?;
+                let active_model: task::ActiveModel = version.into();
+                active_model.insert(txn).await?
+            };
+            restored_tasks.push(restored_task);
+        }
+
+        let mut detached = Vec::new();
+        if !deletions.is_empty() {
+            detached = detach_subtasks(txn, &deletions).await?;
+            task::Entity::delete_many()
+                .filter(task::Column::Id.is_in(deletions))
+                .exec(txn)
+                .await?;
+        }
+
+        Ok((restored_tasks, detached))
+    }
+
+    /// Moves a task right after `after` and/or right before `before`, tasks
+    /// of the same list. Returns every task whose position changed, as it
+    /// was and as it is, the moved one first.
+    pub async fn move_task(
+        &self,
+        id: Uuid,
+        after: Option<Uuid>,
+        before: Option<Uuid>,
+    ) -> Result<Vec<(task::Model, task::Model)>, Error> {
+        debug!(
+            "todo: move_task({}, after: {:?}, before: {:?})",
+            id, after, before
+        );
+
+        let txn = self.db_connection.begin().await?;
+
+        let task = find_in(&txn, id).await?;
+        let mut changes = Changes::default();
+        let new_position = match position_between(&txn, &task, after, before).await? {
+            Some(new_position) => new_position,
+            // Tasks sharing a position leave no room between them
+            None => {
+                changes.record(rebalance(&txn, task.list_id).await?);
+                position_between(&txn, &changes.current(task.clone()), after, before)
+                    .await?
+                    .ok_or_else(|| {
+                        Error::InvalidPosition("No room is left between the tasks".to_string())
+                    })?
+            }
+        };
+
+        let current = changes.current(task.clone());
+        let mut active_model: task::ActiveModel = current.clone().into();
+        active_model.position = sea_orm::Set(new_position);
+        let moved = active_model.update(&txn).await?;
+        let too_long = moved.position.len() > position::MAX_LENGTH;
+        changes.record(vec![(current, moved)]);
+        if too_long {
+            changes.record(rebalance(&txn, task.list_id).await?);
+        }
+
+        txn.commit().await?;
+        Ok(changes.into_vec(id))
+    }
+
+    /// Occurrences of the series from `occurrence` onwards that are not completed yet.
+    pub async fn find_future_occurrences(
+        &self,
+        series_id: Uuid,
+        occurrence: i32,
+    ) -> Result<Vec<task::Model>, Error> {
+        debug!(
+            "todo: find_future_occurrences({}, from: {})",
+            series_id, occurrence
+        );
+
+        let tasks = task::Entity::find()
+            .filter(task::Column::SeriesId.eq(series_id))
+            .filter(task::Column::Occurrence.gte(occurrence))
+            .filter(task::Column::Completed.eq(false))
+            .order_by_asc(task::Column::Occurrence)
+            .all(&self.db_connection)
             .await?;
-        Ok(res)
+
+        Ok(tasks)
     }
 
-    pub async fn delete_all(&self) -> Result<sea_orm::DeleteResult, Error> {
-        debug!("to_do: delete_all()");
+    /// Deletes a task, turning its subtasks into top-level tasks. Returns
+    /// them as they were and as they are.
+    pub async fn delete_id(
+        &self,
+        id: Uuid,
+    ) -> Result<(sea_orm::DeleteResult, Vec<(task::Model, task::Model)>), Error> {
+        debug!("to_do: delete_id({})", id);
+
+        let txn = self.db_connection.begin().await?;
+        let detached = detach_subtasks(&txn, &[id]).await?;
+        let res: sea_orm::DeleteResult = task::Entity::delete_by_id(id).exec(&txn).await?;
+        txn.commit().await?;
+
+        Ok((res, detached))
+    }
+
+    /// Moves the subtasks of a task, and theirs, to its list `list_id`.
+    /// Returns the moved ones as they were and as they are.
+    pub async fn move_subtasks(
+        &self,
+        parent_id: Uuid,
+        list_id: Option<Uuid>,
+    ) -> Result<Vec<(task::Model, task::Model)>, Error> {
+        debug!("to_do: move_subtasks({}, {:?})", parent_id, list_id);
+
+        let txn = self.db_connection.begin().await?;
+
+        let mut moved = Vec::new();
+        let mut visited = HashSet::from([parent_id]);
+        let mut parents = vec![parent_id];
+        while let Some(parent_id) = parents.pop() {
+            let subtasks = task::Entity::find()
+                .filter(task::Column::ParentId.eq(parent_id))
+                .order_by_asc(task::Column::Position)
+                .all(&txn)
+                .await?;
+            for subtask in subtasks {
+                if !visited.insert(subtask.id) {
+                    continue;
+                }
+                parents.push(subtask.id);
+                if subtask.list_id == list_id {
+                    continue;
+                }
+
+                let mut new_task = subtask.clone();
+                new_task.list_id = list_id;
+                let updated_task = apply_update(&txn, subtask.id, new_task).await?;
+                moved.push((subtask, updated_task));
+            }
+        }
+
+        txn.commit().await?;
+        Ok(moved)
+    }
+
+    pub async fn delete_all(
+        &self,
+        lists: &query::ListFilter,
+    ) -> Result<sea_orm::DeleteResult, Error> {
+        debug!("to_do: delete_all(lists: {})", lists);
 
         let res: sea_orm::DeleteResult = task::Entity::delete_many()
+            .filter(utils::construct_list_filter(lists))
             .exec(&self.db_connection)
             .await?;
 
         Ok(res)
     }
+
+    /// Sends `payload` to the instances listening on `channel`.
+    pub async fn notify(&self, channel: &str, payload: &str) -> Result<(), Error> {
+        debug!("to_do: notify({}, {} bytes)", channel, payload.len());
+
+        self.db_connection
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT pg_notify($1, $2)",
+                vec![channel.into(), payload.into()],
+            ))
+            .await?;
+
+        Ok(())
+    }
+}
+
+async fn apply_update<C: ConnectionTrait>(
+    db: &C,
+    id: Uuid,
+    new_task: task::Model,
+) -> Result<task::Model, Error> {
+    let task = task::Entity::find_by_id(id).one(db).await?;
+    match task {
+        Some(task) => {
+            // Only a change of `completed` moves the completion time
+            let completion_changed = task.completed != new_task.completed;
+            let list_changed = task.list_id != new_task.list_id;
+            let current_position = task.position.clone();
+            let mut active_model: task::ActiveModel = task.into();
+
+            active_model.title = sea_orm::Set(new_task.title);
+            active_model.completed = sea_orm::Set(new_task.completed);
+            active_model.deadline = sea_orm::Set(new_task.deadline);
+            active_model.list_id = sea_orm::Set(new_task.list_id);
+            active_model.priority = sea_orm::Set(new_task.priority);
+            active_model.rrule = sea_orm::Set(new_task.rrule);
+            active_model.timezone = sea_orm::Set(new_task.timezone);
+            active_model.series_id = sea_orm::Set(new_task.series_id);
+            active_model.parent_id = sea_orm::Set(new_task.parent_id);
+            active_model.notes = sea_orm::Set(new_task.notes);
+            // A task moved to another list goes to its end
+            let mut position = new_task.position;
+            if list_changed && position == current_position {
+                position = String::new();
+            }
+            if position.is_empty() {
+                position = new_position(db, new_task.list_id).await?;
+            }
+            active_model.position = sea_orm::Set(position);
+            if completion_changed {
+                active_model.completed_at = sea_orm::Set(new_task.completed.then(get_current_time));
+            }
+
+            let updated_task = active_model.update(db).await?;
+            Ok(updated_task)
+        }
+        None => Err(Error::NotFound(format!("Task <id: {}>", id))),
+    }
+}
+
+/// Turns the subtasks of `parent_ids` into top-level tasks, leaving out
+/// those among `parent_ids`. Returns them as they were and as they are.
+async fn detach_subtasks<C: ConnectionTrait>(
+    db: &C,
+    parent_ids: &[Uuid],
+) -> Result<Vec<(task::Model, task::Model)>, Error> {
+    let subtasks = task::Entity::find()
+        .filter(task::Column::ParentId.is_in(parent_ids.iter().copied()))
+        .filter(task::Column::Id.is_not_in(parent_ids.iter().copied()))
+        .all(db)
+        .await?;
+
+    let mut detached = Vec::with_capacity(subtasks.len());
+    for subtask in subtasks {
+        let mut active_model: task::ActiveModel = subtask.clone().into();
+        active_model.parent_id = sea_orm::Set(None);
+        let updated_task = active_model.update(db).await?;
+        detached.push((subtask, updated_task));
+    }
+    Ok(detached)
+}
+
+fn in_list(list_id: Option<Uuid>) -> SimpleExpr {
+    match list_id {
+        Some(list_id) => task::Column::ListId.eq(list_id),
+        None => task::Column::ListId.is_null(),
+    }
+}
+
+async fn find_in<C: ConnectionTrait>(db: &C, id: Uuid) -> Result<task::Model, Error> {
+    task::Entity::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Task <id: {}>", id)))
+}
+
+/// Position after the last task of the list.
+async fn new_position<C: ConnectionTrait>(db: &C, list_id: Option<Uuid>) -> Result<String, Error> {
+    let last = task::Entity::find()
+        .filter(in_list(list_id))
+        .order_by_desc(task::Column::Position)
+        .one(db)
+        .await?;
+
+    position::between(last.as_ref().map(|task| task.position.as_str()), None)
+        .map_err(Error::InvalidPosition)
+}
+
+/// Gives a task without a position the one after the last task of its list.
+async fn place<C: ConnectionTrait>(db: &C, task: &mut task::Model) -> Result<(), Error> {
+    if task.position.is_empty() {
+        task.position = new_position(db, task.list_id).await?;
+    }
+    Ok(())
+}
+
+/// Position of the task following `task` in its list, leaving out `except`.
+async fn position_after<C: ConnectionTrait>(
+    db: &C,
+    task: &task::Model,
+    except: Uuid,
+) -> Result<Option<String>, Error> {
+    let next = task::Entity::find()
+        .filter(in_list(task.list_id))
+        .filter(task::Column::Id.ne(except))
+        .filter(task::Column::Position.gt(task.position.clone()))
+        .order_by_asc(task::Column::Position)
+        .one(db)
+        .await?;
+
+    Ok(next.map(|task| task.position))
+}
+
+/// Position of the task preceding `task` in its list, leaving out `except`.
+async fn position_before<C: ConnectionTrait>(
+    db: &C,
+    task: &task::Model,
+    except: Uuid,
+) -> Result<Option<String>, Error> {
+    let previous = task::Entity::find()
+        .filter(in_list(task.list_id))
+        .filter(task::Column::Id.ne(except))
+        .filter(task::Column::Position.lt(task.position.clone()))
+        .order_by_desc(task::Column::Position)
+        .one(db)
+        .await?;
+
+    Ok(previous.map(|task| task.position))
+}
+
+/// A position for `task` right after `after` and right before `before`,
+/// `None` if they share their position.
+async fn position_between<C: ConnectionTrait>(
+    db: &C,
+    task: &task::Model,
+    after: Option<Uuid>,
+    before: Option<Uuid>,
+) -> Result<Option<String>, Error> {
+    let mut neighbours = Vec::with_capacity(2);
+    for id in after.iter().chain(before.iter()) {
+        let neighbour = find_in(db, *id).await?;
+        if neighbour.list_id != task.list_id {
+            return Err(Error::InvalidPosition(format!(
+                "Task <id: {}> is not in the list of Task <id: {}>",
+                id, task.id
+            )));
+        }
+        neighbours.push(neighbour);
+    }
+
+    let (lower, upper) = match (after, before) {
+        (Some(_), Some(_)) => {
+            let (after, before) = (&neighbours[0], &neighbours[1]);
+            if (&after.position, after.id) > (&before.position, before.id) {
+                return Err(Error::InvalidPosition(format!(
+                    "Task <id: {}> comes after Task <id: {}>",
+                    after.id, before.id
+                )));
+            }
+            (Some(after.position.clone()), Some(before.position.clone()))
+        }
+        (Some(_), None) => {
+            let after = &neighbours[0];
+            (
+                Some(after.position.clone()),
+                position_after(db, after, task.id).await?,
+            )
+        }
+        (None, Some(_)) => {
+            let before = &neighbours[0];
+            (
+                position_before(db, before, task.id).await?,
+                Some(before.position.clone()),
+            )
+        }
+        (None, None) => {
+            return Err(Error::InvalidPosition(
+                "'after' or 'before' is required".to_string(),
+            ))
+        }
+    };
+
+    if matches!((&lower, &upper), (Some(lower), Some(upper)) if lower >= upper) {
+        return Ok(None);
+    }
+    position::between(lower.as_deref(), upper.as_deref())
+        .map(Some)
+        .map_err(Error::InvalidPosition)
+}
+
+/// Spreads the positions of a list out again, keeping its order.
+async fn rebalance<C: ConnectionTrait>(
+    db: &C,
+    list_id: Option<Uuid>,
+) -> Result<Vec<(task::Model, task::Model)>, Error> {
+    debug!("todo: rebalance({:?})", list_id);
+
+    let tasks = task::Entity::find()
+        .filter(in_list(list_id))
+        .order_by_asc(task::Column::Position)
+        .order_by_asc(task::Column::Id)
+        .all(db)
+        .await?;
+    let positions = position::spread(tasks.len()).map_err(Error::InvalidPosition)?;
+
+    let mut changes = Vec::new();
+    for (task, position) in tasks.into_iter().zip(positions) {
+        if task.position == position {
+            continue;
+        }
+        let mut active_model: task::ActiveModel = task.clone().into();
+        active_model.position = sea_orm::Set(position);
+        let updated = active_model.update(db).await?;
+        changes.push((task, updated));
+    }
+    Ok(changes)
+}
+
+/// Tasks changed by several steps, as they were before the first one and
+/// after the last one.
+#[derive(Default)]
+struct Changes {
+    tasks: HashMap<Uuid, (task::Model, task::Model)>,
+}
+
+impl Changes {
+    fn record(&mut self, changes: Vec<(task::Model, task::Model)>) {
+        for (before, after) in changes {
+            self.tasks
+                .entry(after.id)
+                .and_modify(|(_, latest)| *latest = after.clone())
+                .or_insert((before, after));
+        }
+    }
+
+    /// The latest version of `task`.
+    fn current(&self, task: task::Model) -> task::Model {
+        match self.tasks.get(&task.id) {
+            Some((_, latest)) => latest.clone(),
+            None => task,
+        }
+    }
+
+    /// The changes, those of `first` first.
+    fn into_vec(self, first: Uuid) -> Vec<(task::Model, task::Model)> {
+        let mut changes: Vec<_> = self.tasks.into_values().collect();
+        changes.sort_by_key(|(before, _)| (before.id != first, before.position.clone()));
+        changes
+    }
 }
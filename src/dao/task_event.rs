@@ -0,0 +1,144 @@
+use crate::lib::errors::Error;
+use crate::lib::query::ListFilter;
+use entity::task_event;
+
+use chrono::{DateTime, Utc};
+use migration::{Condition, Query};
+use sea_orm::entity::prelude::*;
+use sea_orm::{DatabaseConnection, DatabaseTransaction, QueryOrder, QuerySelect};
+
+pub struct TaskEventDao {
+    pub db_connection: DatabaseConnection,
+}
+
+fn construct_list_filter(lists: &ListFilter) -> Condition {
+    match lists {
+        ListFilter::All => Condition::all(),
+        ListFilter::Accessible(ids) => Condition::any()
+            .add(task_event::Column::ListId.is_null())
+            .add(task_event::Column::ListId.is_in(ids.clone())),
+        ListFilter::Only(id) => Condition::all().add(task_event::Column::ListId.eq(*id)),
+    }
+}
+
+impl TaskEventDao {
+    pub fn init(db_connection: DatabaseConnection) -> Self {
+        TaskEventDao { db_connection }
+    }
+
+    pub async fn create(&self, new_event: task_event::Model) -> Result<task_event::Model, Error> {
+        debug!(
+            "task_event: create({}, {})",
+            new_event.task_id, new_event.event
+        );
+
+        let active_model: task_event::ActiveModel = new_event.into();
+        let event = active_model.insert(&self.db_connection).await?;
+
+        Ok(event)
+    }
+
+    /// Inserts the events as part of `txn`, e.g. along with the changes they record.
+    pub async fn create_many(
+        &self,
+        txn: &DatabaseTransaction,
+        new_events: Vec<task_event::Model>,
+    ) -> Result<(), Error> {
+        debug!("task_event: create_many({} events)", new_events.len());
+
+        if new_events.is_empty() {
+            return Ok(());
+        }
+
+        let active_models: Vec<task_event::ActiveModel> =
+            new_events.into_iter().map(|event| event.into()).collect();
+        task_event::Entity::insert_many(active_models)
+            .exec(txn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// History of a task, oldest first.
+    pub async fn find_for_task(&self, task_id: Uuid) -> Result<Vec<task_event::Model>, Error> {
+        debug!("task_event: find_for_task({})", task_id);
+
+        let events = task_event::Entity::find()
+            .filter(task_event::Column::TaskId.eq(task_id))
+            .order_by_asc(task_event::Column::CreatedAt)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(events)
+    }
+
+    /// Events of the most recent mutation of `actor` that was neither an undo
+    /// nor undone yet, oldest first.
+    pub async fn find_last_undoable(&self, actor: &str) -> Result<Vec<task_event::Model>, Error> {
+        debug!("task_event: find_last_undoable({})", actor);
+
+        let undone = Query::select()
+            .column(task_event::Column::Reverts)
+            .from(task_event::Entity)
+            .and_where(task_event::Column::Reverts.is_not_null())
+            .to_owned();
+
+        let last_event = task_event::Entity::find()
+            .filter(task_event::Column::Actor.eq(actor))
+            .filter(task_event::Column::Reverts.is_null())
+            .filter(task_event::Column::MutationId.not_in_subquery(undone))
+            .order_by_desc(task_event::Column::CreatedAt)
+            .one(&self.db_connection)
+            .await?;
+
+        match last_event {
+            Some(last_event) => {
+                let events = task_event::Entity::find()
+                    .filter(task_event::Column::MutationId.eq(last_event.mutation_id))
+                    .order_by_asc(task_event::Column::CreatedAt)
+                    .all(&self.db_connection)
+                    .await?;
+                Ok(events)
+            }
+            None => Err(Error::NotFound(format!(
+                "Mutation of <actor: {}> to undo",
+                actor
+            ))),
+        }
+    }
+
+    /// Events in `[from, to)`, oldest first.
+    pub async fn find_between(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        actor: Option<&str>,
+        lists: &ListFilter,
+        limit: u64,
+    ) -> Result<Vec<task_event::Model>, Error> {
+        debug!(
+            "task_event: find_between({:?}, {:?}, actor: {:?}, lists: {}, limit: {})",
+            from, to, actor, lists, limit
+        );
+
+        let mut condition = Condition::all().add(construct_list_filter(lists));
+        if let Some(from) = from {
+            condition = condition.add(task_event::Column::CreatedAt.gte(from));
+        }
+        if let Some(to) = to {
+            condition = condition.add(task_event::Column::CreatedAt.lt(to));
+        }
+        if let Some(actor) = actor {
+            condition = condition.add(task_event::Column::Actor.eq(actor));
+        }
+
+        let events = task_event::Entity::find()
+            .filter(condition)
+            .order_by_asc(task_event::Column::CreatedAt)
+            .limit(limit)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(events)
+    }
+}
@@ -0,0 +1,84 @@
+use crate::lib::errors::Error;
+use entity::list_member::{self, Role};
+use entity::task_list;
+
+use sea_orm::entity::prelude::*;
+use sea_orm::{DatabaseConnection, QueryOrder, TransactionTrait};
+
+pub struct TaskListDao {
+    pub db_connection: DatabaseConnection,
+}
+
+impl TaskListDao {
+    pub fn init(db_connection: DatabaseConnection) -> Self {
+        TaskListDao { db_connection }
+    }
+
+    /// Creates the list and makes `owner` its first, already accepted, owner.
+    pub async fn create(
+        &self,
+        new_list: task_list::Model,
+        owner: String,
+    ) -> Result<task_list::Model, Error> {
+        debug!("task_list: create({:?}, owner: {})", new_list, owner);
+
+        let txn = self.db_connection.begin().await?;
+
+        let active_model: task_list::ActiveModel = new_list.into();
+        let list = active_model.insert(&txn).await?;
+
+        let mut membership = list_member::Model::new(list.id, owner.clone(), Role::Owner, owner);
+        membership.accepted_at = Some(list.created_at);
+        let active_model: list_member::ActiveModel = membership.into();
+        active_model.insert(&txn).await?;
+
+        txn.commit().await?;
+        Ok(list)
+    }
+
+    pub async fn find_id(&self, id: Uuid) -> Result<task_list::Model, Error> {
+        debug!("task_list: find_id({})", id);
+
+        let list = task_list::Entity::find_by_id(id)
+            .one(&self.db_connection)
+            .await?;
+
+        match list {
+            Some(list) => Ok(list),
+            None => Err(Error::NotFound(format!("List <id: {}>", id))),
+        }
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<task_list::Model>, Error> {
+        debug!("task_list: find_all()");
+
+        let lists = task_list::Entity::find()
+            .order_by_asc(task_list::Column::CreatedAt)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(lists)
+    }
+
+    pub async fn find_by_ids(&self, ids: Vec<Uuid>) -> Result<Vec<task_list::Model>, Error> {
+        debug!("task_list: find_by_ids({:?})", ids);
+
+        let lists = task_list::Entity::find()
+            .filter(task_list::Column::Id.is_in(ids))
+            .order_by_asc(task_list::Column::CreatedAt)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(lists)
+    }
+
+    /// Deletes the list; its tasks and memberships go with it.
+    pub async fn delete_id(&self, id: Uuid) -> Result<sea_orm::DeleteResult, Error> {
+        debug!("task_list: delete_id({})", id);
+
+        let res: sea_orm::DeleteResult = task_list::Entity::delete_by_id(id)
+            .exec(&self.db_connection)
+            .await?;
+        Ok(res)
+    }
+}
@@ -1,6 +1,7 @@
 use crate::lib::{errors::Error, query};
 use entity::task;
 
+use migration::Condition;
 use sea_orm::ColumnTrait;
 
 pub fn match_sort_order(sort_order: &query::SortOrder) -> sea_orm::Order {
@@ -15,6 +16,9 @@ pub fn match_task_column(column_name: &str) -> Result<task::Column, Error> {
         "title" => Ok(task::Column::Title),
         "created_at" => Ok(task::Column::CreatedAt),
         "deadline" => Ok(task::Column::Deadline),
+        "priority" => Ok(task::Column::Priority),
+        "completed_at" => Ok(task::Column::CompletedAt),
+        "position" => Ok(task::Column::Position),
         &_ => Err(Error::NotFound(format!(
             "Column `{}` not found",
             column_name
@@ -31,3 +35,43 @@ where
         query::FilterOps::Lte => col.lte(val),
     }
 }
+
+pub fn construct_list_filter(lists: &query::ListFilter) -> Condition {
+    match lists {
+        query::ListFilter::All => Condition::all(),
+        query::ListFilter::Accessible(ids) => Condition::any()
+            .add(task::Column::ListId.is_null())
+            .add(task::Column::ListId.is_in(ids.clone())),
+        query::ListFilter::Only(id) => Condition::all().add(task::Column::ListId.eq(*id)),
+    }
+}
+
+pub fn construct_task_filter(filter: &query::TaskFilter) -> Condition {
+    let mut condition = Condition::all().add(construct_list_filter(&filter.lists));
+
+    // `is_in` does not cast to the Postgres enum, `eq` does
+    if !filter.priorities.is_empty() {
+        condition = condition.add(
+            filter
+                .priorities
+                .iter()
+                .fold(Condition::any(), |any, priority| {
+                    any.add(task::Column::Priority.eq(*priority))
+                }),
+        );
+    }
+
+    if let Some(due) = filter.due {
+        condition = condition.add(match due {
+            query::DueFilter::Before(now) => Condition::all()
+                .add(task::Column::Completed.eq(false))
+                .add(task::Column::Deadline.lt(now)),
+            query::DueFilter::Between(start, end) => Condition::all()
+                .add(task::Column::Deadline.gte(start))
+                .add(task::Column::Deadline.lt(end)),
+            query::DueFilter::NoDeadline => Condition::all().add(task::Column::Deadline.is_null()),
+        });
+    }
+
+    condition
+}
@@ -0,0 +1,180 @@
+use crate::lib::errors::Error;
+use crate::lib::time::get_current_time;
+use entity::webhook_delivery::{self, DeliveryStatus};
+
+use chrono::{DateTime, Duration, Utc};
+use migration::{Expr, LockBehavior, LockType};
+use sea_orm::entity::prelude::*;
+use sea_orm::{DatabaseConnection, QueryOrder, QuerySelect, QueryTrait, TransactionTrait};
+
+pub struct WebhookDeliveryDao {
+    pub db_connection: DatabaseConnection,
+}
+
+impl WebhookDeliveryDao {
+    pub fn init(db_connection: DatabaseConnection) -> Self {
+        WebhookDeliveryDao { db_connection }
+    }
+
+    pub async fn enqueue(&self, deliveries: Vec<webhook_delivery::Model>) -> Result<(), Error> {
+        debug!("webhook_delivery: enqueue({} deliveries)", deliveries.len());
+
+        if deliveries.is_empty() {
+            return Ok(());
+        }
+
+        let active_models: Vec<webhook_delivery::ActiveModel> = deliveries
+            .into_iter()
+            .map(|delivery| delivery.into())
+            .collect();
+        webhook_delivery::Entity::insert_many(active_models)
+            .exec(&self.db_connection)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_id(&self, id: Uuid) -> Result<webhook_delivery::Model, Error> {
+        debug!("webhook_delivery: find_id({})", id);
+
+        let delivery = webhook_delivery::Entity::find_by_id(id)
+            .one(&self.db_connection)
+            .await?;
+
+        match delivery {
+            Some(delivery) => Ok(delivery),
+            None => Err(Error::NotFound(format!("Webhook Delivery <id: {}>", id))),
+        }
+    }
+
+    /// Most recent deliveries first.
+    pub async fn find_for_subscription(
+        &self,
+        subscription_id: Uuid,
+        limit: u64,
+    ) -> Result<Vec<webhook_delivery::Model>, Error> {
+        debug!(
+            "webhook_delivery: find_for_subscription({}, limit: {})",
+            subscription_id, limit
+        );
+
+        let deliveries = webhook_delivery::Entity::find()
+            .filter(webhook_delivery::Column::SubscriptionId.eq(subscription_id))
+            .order_by_desc(webhook_delivery::Column::CreatedAt)
+            .limit(limit)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Dead deliveries of the given subscriptions, most recent first.
+    pub async fn find_dead(
+        &self,
+        subscription_ids: Vec<Uuid>,
+    ) -> Result<Vec<webhook_delivery::Model>, Error> {
+        debug!("webhook_delivery: find_dead({:?})", subscription_ids);
+
+        let deliveries = webhook_delivery::Entity::find()
+            .filter(webhook_delivery::Column::Status.eq(DeliveryStatus::Dead))
+            .filter(webhook_delivery::Column::SubscriptionId.is_in(subscription_ids))
+            .order_by_desc(webhook_delivery::Column::CreatedAt)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Queues a delivery again, with a fresh set of attempts.
+    pub async fn retry(
+        &self,
+        delivery: webhook_delivery::Model,
+    ) -> Result<webhook_delivery::Model, Error> {
+        debug!("webhook_delivery: retry({})", delivery.id);
+
+        let mut active_model: webhook_delivery::ActiveModel = delivery.into();
+        active_model.status = sea_orm::Set(DeliveryStatus::Pending);
+        active_model.attempts = sea_orm::Set(0);
+        active_model.next_attempt_at = sea_orm::Set(get_current_time());
+
+        let delivery = active_model.update(&self.db_connection).await?;
+        Ok(delivery)
+    }
+
+    /// Claims up to `limit` pending deliveries that are due, skipping those
+    /// locked by another instance. Claimed deliveries are not due again
+    /// before `lease` has passed, so that they can be sent without holding
+    /// a lock.
+    pub async fn claim_due(
+        &self,
+        limit: u64,
+        lease: Duration,
+    ) -> Result<Vec<webhook_delivery::Model>, Error> {
+        let txn = self.db_connection.begin().await?;
+
+        let now = get_current_time();
+        let mut select = webhook_delivery::Entity::find()
+            .filter(webhook_delivery::Column::Status.eq(DeliveryStatus::Pending))
+            .filter(webhook_delivery::Column::NextAttemptAt.lte(now))
+            .order_by_asc(webhook_delivery::Column::NextAttemptAt)
+            .limit(limit);
+        QueryTrait::query(&mut select)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked);
+        let deliveries = select.all(&txn).await?;
+
+        if !deliveries.is_empty() {
+            webhook_delivery::Entity::update_many()
+                .col_expr(
+                    webhook_delivery::Column::NextAttemptAt,
+                    Expr::value(now + lease),
+                )
+                .filter(
+                    webhook_delivery::Column::Id
+                        .is_in(deliveries.iter().map(|delivery| delivery.id)),
+                )
+                .exec(&txn)
+                .await?;
+        }
+
+        txn.commit().await?;
+        Ok(deliveries)
+    }
+
+    /// Records the outcome of an attempt. Failed deliveries are retried at
+    /// `retry_at`, or marked dead if there is none.
+    pub async fn record_attempt(
+        &self,
+        delivery: webhook_delivery::Model,
+        response_status: Option<i32>,
+        error: Option<String>,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<webhook_delivery::Model, Error> {
+        debug!(
+            "webhook_delivery: record_attempt({}, {:?})",
+            delivery.id, response_status
+        );
+
+        let attempts = delivery.attempts + 1;
+        let mut active_model: webhook_delivery::ActiveModel = delivery.into();
+
+        active_model.attempts = sea_orm::Set(attempts);
+        active_model.response_status = sea_orm::Set(response_status);
+        match error {
+            None => {
+                active_model.status = sea_orm::Set(DeliveryStatus::Delivered);
+                active_model.delivered_at = sea_orm::Set(Some(get_current_time()));
+                active_model.last_error = sea_orm::Set(None);
+            }
+            Some(error) => {
+                active_model.last_error = sea_orm::Set(Some(error));
+                match retry_at {
+                    Some(retry_at) => active_model.next_attempt_at = sea_orm::Set(retry_at),
+                    None => active_model.status = sea_orm::Set(DeliveryStatus::Dead),
+                }
+            }
+        }
+
+        let delivery = active_model.update(&self.db_connection).await?;
+        Ok(delivery)
+    }
+}
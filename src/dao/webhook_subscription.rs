@@ -0,0 +1,87 @@
+use crate::lib::errors::Error;
+use entity::webhook_subscription;
+
+use sea_orm::entity::prelude::*;
+use sea_orm::{DatabaseConnection, QueryOrder};
+
+pub struct WebhookSubscriptionDao {
+    pub db_connection: DatabaseConnection,
+}
+
+impl WebhookSubscriptionDao {
+    pub fn init(db_connection: DatabaseConnection) -> Self {
+        WebhookSubscriptionDao { db_connection }
+    }
+
+    pub async fn create(
+        &self,
+        new_subscription: webhook_subscription::Model,
+    ) -> Result<webhook_subscription::Model, Error> {
+        debug!("webhook_subscription: create({})", new_subscription.url);
+
+        let active_model: webhook_subscription::ActiveModel = new_subscription.into();
+        let subscription = active_model.insert(&self.db_connection).await?;
+
+        Ok(subscription)
+    }
+
+    pub async fn find_id(&self, id: Uuid) -> Result<webhook_subscription::Model, Error> {
+        debug!("webhook_subscription: find_id({})", id);
+
+        let subscription = webhook_subscription::Entity::find_by_id(id)
+            .one(&self.db_connection)
+            .await?;
+
+        match subscription {
+            Some(subscription) => Ok(subscription),
+            None => Err(Error::NotFound(format!(
+                "Webhook Subscription <id: {}>",
+                id
+            ))),
+        }
+    }
+
+    /// Subscriptions of `owner`, or all of them if `None`.
+    pub async fn find_all(
+        &self,
+        owner: Option<&str>,
+    ) -> Result<Vec<webhook_subscription::Model>, Error> {
+        debug!("webhook_subscription: find_all({:?})", owner);
+
+        let mut select = webhook_subscription::Entity::find();
+        if let Some(owner) = owner {
+            select = select.filter(webhook_subscription::Column::Owner.eq(owner));
+        }
+
+        let subscriptions = select
+            .order_by_asc(webhook_subscription::Column::CreatedAt)
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(subscriptions)
+    }
+
+    pub async fn find_for_event(
+        &self,
+        event: &str,
+    ) -> Result<Vec<webhook_subscription::Model>, Error> {
+        let subscriptions = webhook_subscription::Entity::find()
+            .filter(webhook_subscription::Column::Events.contains(event))
+            .all(&self.db_connection)
+            .await?;
+
+        Ok(subscriptions
+            .into_iter()
+            .filter(|subscription| subscription.is_subscribed_to(event))
+            .collect())
+    }
+
+    pub async fn delete_id(&self, id: Uuid) -> Result<sea_orm::DeleteResult, Error> {
+        debug!("webhook_subscription: delete_id({})", id);
+
+        let res: sea_orm::DeleteResult = webhook_subscription::Entity::delete_by_id(id)
+            .exec(&self.db_connection)
+            .await?;
+        Ok(res)
+    }
+}
@@ -1,6 +1,17 @@
 use std::time::Duration;
 
+use crate::dao::access_token::AccessTokenDao;
+use crate::dao::attachment::AttachmentDao;
+use crate::dao::backup::BackupDao;
+use crate::dao::comment::CommentDao;
+use crate::dao::list_member::ListMemberDao;
+use crate::dao::reminder::ReminderDao;
+use crate::dao::stats::StatsDao;
 use crate::dao::task::TaskDao;
+use crate::dao::task_event::TaskEventDao;
+use crate::dao::task_list::TaskListDao;
+use crate::dao::webhook_delivery::WebhookDeliveryDao;
+use crate::dao::webhook_subscription::WebhookSubscriptionDao;
 use crate::lib::env::get_env_var;
 use crate::lib::errors::Error;
 
@@ -8,9 +19,20 @@ use sea_orm::{ConnectOptions, Database, DatabaseConnection};
 
 pub struct DbClient {
     pub task_dao: TaskDao,
+    pub access_token_dao: AccessTokenDao,
+    pub task_list_dao: TaskListDao,
+    pub list_member_dao: ListMemberDao,
+    pub reminder_dao: ReminderDao,
+    pub webhook_subscription_dao: WebhookSubscriptionDao,
+    pub webhook_delivery_dao: WebhookDeliveryDao,
+    pub task_event_dao: TaskEventDao,
+    pub backup_dao: BackupDao,
+    pub stats_dao: StatsDao,
+    pub attachment_dao: AttachmentDao,
+    pub comment_dao: CommentDao,
 }
 
-fn construct_db_uri() -> Result<String, Error> {
+pub fn construct_db_uri() -> Result<String, Error> {
     let mut uri = String::new();
     info!("Constructing DB URI");
 
@@ -64,8 +86,32 @@ impl DbClient {
 
         let db_connection: DatabaseConnection = Database::connect(options).await?;
 
-        let task_dao = TaskDao::init(db_connection);
-
-        Ok(DbClient { task_dao })
+        let task_dao = TaskDao::init(db_connection.clone());
+        let access_token_dao = AccessTokenDao::init(db_connection.clone());
+        let task_list_dao = TaskListDao::init(db_connection.clone());
+        let list_member_dao = ListMemberDao::init(db_connection.clone());
+        let reminder_dao = ReminderDao::init(db_connection.clone());
+        let webhook_subscription_dao = WebhookSubscriptionDao::init(db_connection.clone());
+        let webhook_delivery_dao = WebhookDeliveryDao::init(db_connection.clone());
+        let task_event_dao = TaskEventDao::init(db_connection.clone());
+        let backup_dao = BackupDao::init(db_connection.clone());
+        let stats_dao = StatsDao::init(db_connection.clone());
+        let attachment_dao = AttachmentDao::init(db_connection.clone());
+        let comment_dao = CommentDao::init(db_connection);
+
+        Ok(DbClient {
+            task_dao,
+            access_token_dao,
+            task_list_dao,
+            list_member_dao,
+            reminder_dao,
+            webhook_subscription_dao,
+            webhook_delivery_dao,
+            task_event_dao,
+            backup_dao,
+            stats_dao,
+            attachment_dao,
+            comment_dao,
+        })
     }
 }
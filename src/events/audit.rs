@@ -0,0 +1,179 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::database::DbClient;
+use crate::events::{Mutation, TaskEvent};
+use crate::lib::errors::Error;
+use crate::model::task::PublicTask;
+use entity::task::Model as Task;
+use entity::task_event::Model as TaskEventRecord;
+
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+/// Fields of `PublicTask` derived from the others and the current time,
+/// which are not part of a task's state.
+const COMPUTED_FIELDS: [&str; 2] = ["overdue", "dueInSeconds"];
+
+fn to_fields(task: Option<&Task>) -> Map<String, Value> {
+    match task.map(|task| serde_json::to_value(PublicTask::from(task.clone()))) {
+        Some(Ok(Value::Object(mut fields))) => {
+            for field in COMPUTED_FIELDS {
+                fields.remove(field);
+            }
+            fields
+        }
+        _ => Map::new(),
+    }
+}
+
+/// Field-level differences between two versions of a task, keyed by the
+/// field names of the API. A missing version has every field `null`.
+pub fn diff(before: Option<&Task>, after: Option<&Task>) -> Value {
+    let before = to_fields(before);
+    let after = to_fields(after);
+
+    let fields: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut changes = Map::new();
+    for field in fields.into_iter().filter(|field| *field != "_id") {
+        let old = before.get(field).unwrap_or(&Value::Null);
+        let new = after.get(field).unwrap_or(&Value::Null);
+        if old != new {
+            changes.insert(field.clone(), json!({ "before": old, "after": new }));
+        }
+    }
+
+    Value::Object(changes)
+}
+
+/// The entry of the task's history for a change, unless it was an update
+/// that did not change anything.
+pub fn to_record(
+    mutation: &Mutation,
+    event: TaskEvent,
+    before: Option<&Task>,
+    task: &Task,
+) -> Option<TaskEventRecord> {
+    let changes = match event {
+        TaskEvent::Created => diff(None, Some(task)),
+        TaskEvent::Deleted => diff(Some(task), None),
+        TaskEvent::Updated | TaskEvent::Completed => diff(before, Some(task)),
+    };
+    if changes.as_object().is_some_and(Map::is_empty) {
+        return None;
+    }
+
+    Some(TaskEventRecord::new(
+        task.id,
+        task.list_id,
+        mutation.actor.clone(),
+        event.to_string(),
+        changes,
+        mutation.id,
+        mutation.reverts,
+    ))
+}
+
+/// Appends a change to the task's history. Updates that did not change
+/// anything are left out.
+pub async fn record(
+    db: &DbClient,
+    mutation: &Mutation,
+    event: TaskEvent,
+    before: Option<&Task>,
+    task: &Task,
+) -> Result<(), Error> {
+    if let Some(record) = to_record(mutation, event, before, task) {
+        db.task_event_dao.create(record).await?;
+    }
+
+    Ok(())
+}
+
+/// The entry of `mutation` without any changes, e.g. undoing changes that
+/// were overwritten in the meantime.
+pub fn unchanged_record(mutation: &Mutation, task_id: Uuid) -> TaskEventRecord {
+    TaskEventRecord::new(
+        task_id,
+        None,
+        mutation.actor.clone(),
+        TaskEvent::Updated.to_string(),
+        json!({}),
+        mutation.id,
+        mutation.reverts,
+    )
+}
+
+/// Sets every field named in `changes` to its `before` or `after` value.
+fn apply(fields: &mut Map<String, Value>, changes: &Value, side: &str) {
+    if let Some(changes) = changes.as_object() {
+        for (field, change) in changes {
+            match change.get(side) {
+                Some(Value::Null) | None => fields.remove(field),
+                Some(value) => fields.insert(field.clone(), value.clone()),
+            };
+        }
+    }
+}
+
+fn to_task(id: Uuid, mut fields: Map<String, Value>) -> Result<Task, String> {
+    fields.insert("_id".to_string(), json!(id));
+    serde_json::from_value::<PublicTask>(Value::Object(fields))
+        .map(Task::from)
+        .map_err(|err| format!("Cannot rebuild Task <id: {}>: {}", id, err))
+}
+
+/// The task as it was right after the event `target`, rebuilt by rolling
+/// back the later events of its `history` (oldest first) from its `current`
+/// version. `None` if `target` deleted it.
+pub fn state_after(
+    current: Option<&Task>,
+    history: &[TaskEventRecord],
+    target: Uuid,
+) -> Result<Option<Task>, String> {
+    let position = history
+        .iter()
+        .position(|event| event.id == target)
+        .ok_or_else(|| format!("Task Event <id: {}> is not part of the history", target))?;
+
+    let mut fields = to_fields(current);
+    for event in history[position + 1..].iter().rev() {
+        apply(&mut fields, &event.changes, "before");
+    }
+
+    if history[position].event == TaskEvent::Deleted.to_string() {
+        return Ok(None);
+    }
+    to_task(history[position].task_id, fields).map(Some)
+}
+
+/// The versions of the tasks touched by a mutation's `events` once it is
+/// undone, starting from their `current` versions. Fields changed since are
+/// kept, and `None` stands for a task that is deleted.
+pub fn undo(
+    events: &[TaskEventRecord],
+    mut current: HashMap<Uuid, Option<Task>>,
+) -> Result<HashMap<Uuid, Option<Task>>, String> {
+    for event in events.iter().rev() {
+        let task = current.remove(&event.task_id).flatten();
+        let reverted = match (event.event.parse::<TaskEvent>(), task) {
+            (Ok(TaskEvent::Created), _) => None,
+            (Ok(TaskEvent::Deleted), Some(task)) => Some(task),
+            (Ok(TaskEvent::Deleted), None) => {
+                let mut fields = Map::new();
+                apply(&mut fields, &event.changes, "before");
+                Some(to_task(event.task_id, fields)?)
+            }
+            // Deleted since, so there is nothing left to change back
+            (Ok(TaskEvent::Updated | TaskEvent::Completed), None) => None,
+            (Ok(TaskEvent::Updated | TaskEvent::Completed), Some(task)) => {
+                let mut fields = to_fields(Some(&task));
+                apply(&mut fields, &event.changes, "before");
+                Some(to_task(task.id, fields)?)
+            }
+            (Err(_), _) => return Err(format!("Unknown event `{}`", event.event)),
+        };
+        current.insert(event.task_id, reverted);
+    }
+
+    Ok(current)
+}
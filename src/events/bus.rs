@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::events::TaskEvent;
+use entity::task::Model as Task;
+
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// A change to a task, numbered in the order it happened on this instance.
+#[derive(Debug, Clone)]
+pub struct ChangeEvent {
+    pub id: u64,
+    pub event: TaskEvent,
+    pub task: Task,
+}
+
+/// Number of recent events kept for resuming streams, from `EVENT_REPLAY_SIZE`.
+pub fn replay_size() -> usize {
+    std::env::var("EVENT_REPLAY_SIZE")
+        .ok()
+        .and_then(|size| size.parse::<usize>().ok())
+        .unwrap_or(1024)
+        .max(1)
+}
+
+/// Fans task changes out to live subscribers, and keeps the most recent ones
+/// so that subscribers which lost their connection can catch up.
+pub struct EventBus {
+    /// Tells this instance's notifications apart from those of others
+    origin: Uuid,
+    sender: broadcast::Sender<Arc<ChangeEvent>>,
+    history: Mutex<VecDeque<Arc<ChangeEvent>>>,
+    capacity: usize,
+}
+
+/// What a subscriber missed since the last event it saw.
+pub enum Replay {
+    Events(Vec<Arc<ChangeEvent>>),
+    /// The event is no longer, or was never, in the history
+    Gone,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        EventBus {
+            origin: Uuid::new_v4(),
+            sender,
+            history: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    pub fn origin(&self) -> Uuid {
+        self.origin
+    }
+
+    pub fn publish(&self, event: TaskEvent, task: Task) -> Arc<ChangeEvent> {
+        // Sending while holding the lock keeps ids and delivery in the same order
+        let mut history = self.history.lock().expect("event history is not poisoned");
+        let id = history.back().map_or(1, |last| last.id + 1);
+        let change = Arc::new(ChangeEvent { id, event, task });
+
+        if history.len() == self.capacity {
+            history.pop_front();
+        }
+        history.push_back(change.clone());
+        // Fails only if nobody is listening
+        let _ = self.sender.send(change.clone());
+
+        change
+    }
+
+    /// Subscribes to future events, along with those after `last_event_id`.
+    /// Events in both are told apart by their id.
+    pub fn subscribe(
+        &self,
+        last_event_id: Option<u64>,
+    ) -> (Replay, broadcast::Receiver<Arc<ChangeEvent>>) {
+        let history = self.history.lock().expect("event history is not poisoned");
+        let receiver = self.sender.subscribe();
+
+        let replay = match last_event_id {
+            None => Replay::Events(Vec::new()),
+            Some(last_event_id) => {
+                let oldest = history.front().map_or(1, |first| first.id);
+                let latest = history.back().map_or(0, |last| last.id);
+                if last_event_id + 1 < oldest || last_event_id > latest {
+                    Replay::Gone
+                } else {
+                    Replay::Events(
+                        history
+                            .iter()
+                            .filter(|change| change.id > last_event_id)
+                            .cloned()
+                            .collect(),
+                    )
+                }
+            }
+        };
+
+        (replay, receiver)
+    }
+}
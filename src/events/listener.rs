@@ -0,0 +1,133 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::database::DbClient;
+use crate::events::bus::EventBus;
+use crate::events::TaskEvent;
+use crate::lib::errors::Error;
+use crate::model::task::PublicTask;
+use entity::task::Model as Task;
+
+use actix_web::web::Data;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use uuid::Uuid;
+
+pub const CHANNEL: &str = "task_changes";
+
+/// Postgres rejects payloads of 8000 bytes or more.
+const MAX_PAYLOAD_SIZE: usize = 7900;
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A change announced to the other instances.
+#[derive(Debug, Serialize, Deserialize)]
+struct Notification {
+    origin: Uuid,
+
+    event: String,
+
+    #[serde(rename = "taskId")]
+    task_id: Uuid,
+
+    #[serde(rename = "listId", default, skip_serializing_if = "Option::is_none")]
+    list_id: Option<Uuid>,
+
+    /// Left out if too large, in which case it is fetched again, or replaced
+    /// by a stub of its id and list for a deletion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    task: Option<PublicTask>,
+}
+
+/// Announces a change made on this instance to the others.
+pub async fn notify(
+    db: &DbClient,
+    bus: &EventBus,
+    event: TaskEvent,
+    task: &Task,
+) -> Result<(), Error> {
+    let mut notification = Notification {
+        origin: bus.origin(),
+        event: event.to_string(),
+        task_id: task.id,
+        list_id: task.list_id,
+        task: Some(PublicTask::from(task.clone())),
+    };
+
+    let mut payload = serde_json::to_string(&notification).expect("notification is serializable");
+    if payload.len() > MAX_PAYLOAD_SIZE {
+        notification.task = None;
+        payload = serde_json::to_string(&notification).expect("notification is serializable");
+    }
+
+    db.task_dao.notify(CHANNEL, &payload).await
+}
+
+/// Passes a change made on another instance on to the local subscribers.
+async fn republish(db: &DbClient, bus: &EventBus, payload: &str) -> Result<(), String> {
+    let notification: Notification =
+        serde_json::from_str(payload).map_err(|err| format!("Malformed payload: {}", err))?;
+    if notification.origin == bus.origin() {
+        return Ok(());
+    }
+
+    let event = TaskEvent::from_str(&notification.event)
+        .map_err(|_| format!("Unknown event `{}`", notification.event))?;
+    let task = match notification.task {
+        Some(task) => Task::from(task),
+        None if event == TaskEvent::Deleted => Task {
+            id: notification.task_id,
+            list_id: notification.list_id,
+            ..Task::new(String::new(), false, None)
+        },
+        None => match db.task_dao.find_id(notification.task_id).await {
+            Ok(task) => task,
+            // Deleted since, which is announced on its own
+            Err(Error::NotFound(_)) => return Ok(()),
+            Err(err) => return Err(err.to_string()),
+        },
+    };
+
+    bus.publish(event, task);
+    Ok(())
+}
+
+/// Republishes notifications until the connection fails for good.
+async fn listen(
+    db: &DbClient,
+    bus: &EventBus,
+    uri: &str,
+    backoff: &mut Duration,
+) -> Result<(), Error> {
+    let mut listener = PgListener::connect(uri).await?;
+    listener.listen(CHANNEL).await?;
+    info!("Listening for changes made on other instances");
+    *backoff = MIN_BACKOFF;
+
+    loop {
+        // `None` if the connection was lost, it is re-established on the next call
+        match listener.try_recv().await? {
+            Some(notification) => {
+                if let Err(err) = republish(db, bus, notification.payload()).await {
+                    warn!("Failed to republish a change: {}", err);
+                }
+            }
+            None => warn!("Lost the listener connection, changes of other instances were missed"),
+        }
+    }
+}
+
+/// Holds a dedicated connection listening for changes made on other
+/// instances, reconnecting with exponential backoff whenever it drops.
+pub async fn run(db: Data<DbClient>, bus: Data<EventBus>, uri: String) {
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        if let Err(err) = listen(&db, &bus, &uri, &mut backoff).await {
+            error!("Listener connection failed: {}", err);
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
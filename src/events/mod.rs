@@ -0,0 +1,87 @@
+pub mod audit;
+pub mod bus;
+pub mod listener;
+pub mod webhook;
+
+use crate::database::DbClient;
+use crate::events::bus::EventBus;
+use crate::lib::auth::Principal;
+use entity::task::Model as Task;
+
+use strum_macros::{Display, EnumIter, EnumString};
+use uuid::Uuid;
+
+/// Lifecycle events of a task, as announced to subscribers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, EnumIter)]
+pub enum TaskEvent {
+    #[strum(serialize = "task.created")]
+    Created,
+    #[strum(serialize = "task.updated")]
+    Updated,
+    #[strum(serialize = "task.completed")]
+    Completed,
+    #[strum(serialize = "task.deleted")]
+    Deleted,
+}
+
+/// The changes made by one request, which are undone together.
+pub struct Mutation {
+    pub id: Uuid,
+    pub actor: String,
+    /// Mutation undone by this one
+    pub reverts: Option<Uuid>,
+}
+
+impl Mutation {
+    pub fn new(principal: &Principal) -> Self {
+        Mutation {
+            id: Uuid::new_v4(),
+            actor: principal.subject.clone(),
+            reverts: None,
+        }
+    }
+
+    pub fn undo(principal: &Principal, reverted: Uuid) -> Self {
+        Mutation {
+            reverts: Some(reverted),
+            ..Mutation::new(principal)
+        }
+    }
+}
+
+/// Records and announces a change made to a task as part of `mutation`,
+/// given the task as it was `before` an update. Failing to do so never fails
+/// the change itself, so errors are only logged.
+pub async fn publish(
+    db: &DbClient,
+    bus: &EventBus,
+    mutation: &Mutation,
+    event: TaskEvent,
+    before: Option<&Task>,
+    task: &Task,
+) {
+    if let Err(err) = audit::record(db, mutation, event, before, task).await {
+        error!(
+            "Failed to record `{}` for Task <id: {}>: {}",
+            event, task.id, err
+        );
+    }
+    announce(db, bus, event, task).await;
+}
+
+/// Announces a change to a task that was recorded already, like `publish`.
+pub async fn announce(db: &DbClient, bus: &EventBus, event: TaskEvent, task: &Task) {
+    bus.publish(event, task.clone());
+    if let Err(err) = listener::notify(db, bus, event, task).await {
+        error!(
+            "Failed to notify other instances of `{}` for Task <id: {}>: {}",
+            event, task.id, err
+        );
+    }
+    if let Err(err) = webhook::enqueue(db, event, task).await {
+        error!(
+            "Failed to enqueue `{}` for Task <id: {}>: {}",
+            event, task.id, err
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use crate::database::DbClient;
+use crate::events::TaskEvent;
+use crate::lib::errors::Error;
+use crate::model::webhook::WebhookPayload;
+use entity::task::Model as Task;
+use entity::webhook_delivery::Model as WebhookDelivery;
+use entity::webhook_subscription::Model as WebhookSubscription;
+
+use uuid::Uuid;
+
+/// Whether the owner of `subscription` may see `task`. Tasks outside of a
+/// list are visible to everyone, as they are through the API.
+async fn can_view(
+    db: &DbClient,
+    subscription: &WebhookSubscription,
+    task: &Task,
+) -> Result<bool, Error> {
+    match task.list_id {
+        None => Ok(true),
+        Some(_) if subscription.owner == "admin" => Ok(true),
+        Some(list_id) => Ok(db
+            .list_member_dao
+            .find_role(list_id, &subscription.owner)
+            .await?
+            .is_some()),
+    }
+}
+
+/// Queues one delivery of `event` per interested subscription. The payload
+/// is rendered now, so retries send exactly what happened at the time.
+pub async fn enqueue(db: &DbClient, event: TaskEvent, task: &Task) -> Result<(), Error> {
+    let subscriptions = db
+        .webhook_subscription_dao
+        .find_for_event(&event.to_string())
+        .await?;
+
+    let mut deliveries = Vec::new();
+    for subscription in subscriptions {
+        if !can_view(db, &subscription, task).await? {
+            continue;
+        }
+
+        let id = Uuid::new_v4();
+        let payload = WebhookPayload::new(id, event, task.clone());
+        let payload = serde_json::to_string(&payload).expect("payload is serializable");
+        deliveries.push(WebhookDelivery::new(
+            id,
+            subscription.id,
+            event.to_string(),
+            payload,
+        ));
+    }
+
+    db.webhook_delivery_dao.enqueue(deliveries).await
+}
@@ -0,0 +1,227 @@
+//! Files attached to tasks, stored on local disk under the name of their
+//! SHA-256 so that identical uploads share one file.
+
+use std::fmt::Display;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use actix_web::web::Bytes;
+use futures::{Stream, StreamExt};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+/// Files younger than this are never removed, as an upload of the same
+/// contents may be about to reference them.
+const GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// Uploads still unfinished after this were abandoned.
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Directory beneath which the files are stored.
+pub fn root_dir() -> PathBuf {
+    std::env::var("ATTACHMENTS_DIR")
+        .unwrap_or_else(|_| "attachments".to_string())
+        .into()
+}
+
+/// Combined size of the attachments a task may have, in bytes.
+pub fn quota_bytes() -> u64 {
+    std::env::var("ATTACHMENT_QUOTA_BYTES")
+        .ok()
+        .and_then(|size| size.parse::<u64>().ok())
+        .unwrap_or(25 * 1024 * 1024)
+}
+
+fn uploads_dir() -> PathBuf {
+    root_dir().join("uploads")
+}
+
+/// e.g. `<root>/ab/ab12…`, spread over subdirectories by the first byte.
+pub fn blob_path(checksum: &str) -> PathBuf {
+    root_dir()
+        .join(checksum.get(..2).unwrap_or_default())
+        .join(checksum)
+}
+
+fn is_checksum(name: &str) -> bool {
+    name.len() == 64
+        && name
+            .bytes()
+            .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum StoreError {
+    #[error("The file exceeds the remaining quota of {0} bytes")]
+    TooLarge(u64),
+
+    #[error("Failed to read the upload: {0}")]
+    Upload(String),
+
+    #[error("Failed to store the upload: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug)]
+pub struct StoredFile {
+    pub checksum: String,
+    pub size: u64,
+}
+
+/// Streams an upload to disk while hashing it, giving up once it grows
+/// beyond `limit` bytes.
+pub async fn store<S, E>(mut stream: S, limit: u64) -> Result<StoredFile, StoreError>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Display,
+{
+    tokio::fs::create_dir_all(uploads_dir()).await?;
+    let upload_path = uploads_dir().join(Uuid::new_v4().to_string());
+
+    let res = write_upload(&mut stream, &upload_path, limit).await;
+    let stored = match res {
+        Ok(stored) => stored,
+        Err(err) => {
+            let _ = tokio::fs::remove_file(&upload_path).await;
+            return Err(err);
+        }
+    };
+
+    let path = blob_path(&stored.checksum);
+    if tokio::fs::metadata(&path).await.is_ok() {
+        // Already stored, restart its grace period so that it outlives
+        // the insertion of the attachment referencing it
+        tokio::fs::remove_file(&upload_path).await?;
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)?
+            .set_modified(SystemTime::now())?;
+    } else {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(&upload_path, &path).await?;
+    }
+    Ok(stored)
+}
+
+async fn write_upload<S, E>(
+    stream: &mut S,
+    upload_path: &Path,
+    limit: u64,
+) -> Result<StoredFile, StoreError>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Display,
+{
+    let mut file = tokio::fs::File::create(upload_path).await?;
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|err| StoreError::Upload(err.to_string()))?;
+        size += chunk.len() as u64;
+        if size > limit {
+            return Err(StoreError::TooLarge(limit));
+        }
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+    file.sync_all().await?;
+
+    Ok(StoredFile {
+        checksum: hex::encode(hasher.finalize()),
+        size,
+    })
+}
+
+fn is_stale(modified: io::Result<SystemTime>, age: Duration) -> bool {
+    modified
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|elapsed| elapsed >= age)
+}
+
+/// Removes the file of `checksum` unless it was stored or reused within the
+/// grace period. Returns whether it was removed.
+pub async fn remove(checksum: &str) -> io::Result<bool> {
+    if !is_checksum(checksum) {
+        return Ok(false);
+    }
+    let path = blob_path(checksum);
+    match tokio::fs::metadata(&path).await {
+        Ok(metadata) if is_stale(metadata.modified(), GRACE_PERIOD) => {
+            tokio::fs::remove_file(&path).await?;
+            Ok(true)
+        }
+        Ok(_) => Ok(false),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Checksums of the stored files older than the grace period.
+pub async fn stale_checksums() -> io::Result<Vec<String>> {
+    let mut checksums = Vec::new();
+    let mut dirs = match tokio::fs::read_dir(root_dir()).await {
+        Ok(dirs) => dirs,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(checksums),
+        Err(err) => return Err(err),
+    };
+
+    while let Some(dir) = dirs.next_entry().await? {
+        let prefix = dir.file_name().to_string_lossy().to_string();
+        if prefix.len() != 2 || !dir.file_type().await?.is_dir() {
+            continue;
+        }
+        let mut files = tokio::fs::read_dir(dir.path()).await?;
+        while let Some(file) = files.next_entry().await? {
+            let name = file.file_name().to_string_lossy().to_string();
+            if is_checksum(&name)
+                && name.starts_with(&prefix)
+                && is_stale(file.metadata().await?.modified(), GRACE_PERIOD)
+            {
+                checksums.push(name);
+            }
+        }
+    }
+    Ok(checksums)
+}
+
+/// Removes what is left of uploads that were interrupted, e.g. by the
+/// client going away. Returns how many were removed.
+pub async fn remove_abandoned_uploads() -> io::Result<usize> {
+    let mut uploads = match tokio::fs::read_dir(uploads_dir()).await {
+        Ok(uploads) => uploads,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+
+    let mut removed = 0;
+    while let Some(upload) = uploads.next_entry().await? {
+        if is_stale(upload.metadata().await?.modified(), UPLOAD_TIMEOUT) {
+            tokio::fs::remove_file(upload.path()).await?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// A filename safe to send back in a `Content-Disposition`, without the
+/// directories some clients include.
+pub fn sanitize_filename(filename: &str) -> String {
+    let name = filename
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|char| !char.is_control())
+        .take(255)
+        .collect::<String>();
+    match name.trim() {
+        "" | "." | ".." => "attachment".to_string(),
+        name => name.to_string(),
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+
+pub const TOKEN_PREFIX: &str = "tds_";
+pub const WEBHOOK_SECRET_PREFIX: &str = "whsec_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, EnumString, EnumIter)]
+pub enum Scope {
+    #[strum(serialize = "tasks:read")]
+    TasksRead,
+    #[strum(serialize = "tasks:write")]
+    TasksWrite,
+    #[strum(serialize = "tasks:delete")]
+    TasksDelete,
+    #[strum(serialize = "tokens:manage")]
+    TokensManage,
+    #[strum(serialize = "webhooks:manage")]
+    WebhooksManage,
+    /// Only grants the calendar feed, for tokens embedded in its URL
+    #[strum(serialize = "calendar:read")]
+    CalendarRead,
+}
+
+/// The caller of a request, as established from its `Authorization` header.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub subject: String,
+    pub scopes: HashSet<Scope>,
+    /// Bypasses list membership checks.
+    pub admin: bool,
+}
+
+impl Principal {
+    /// Holder of the `ADMIN_TOKEN`, allowed to do everything.
+    pub fn admin() -> Self {
+        Self {
+            subject: "admin".to_string(),
+            scopes: Scope::iter().collect(),
+            admin: true,
+        }
+    }
+
+    /// Caller without credentials while `AUTH_REQUIRED` is not set.
+    pub fn anonymous() -> Self {
+        Self {
+            subject: "anonymous".to_string(),
+            scopes: HashSet::from([Scope::TasksRead, Scope::TasksWrite, Scope::TasksDelete]),
+            admin: false,
+        }
+    }
+
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+pub fn parse_scopes(scopes: &str) -> Result<HashSet<Scope>, String> {
+    scopes
+        .split_whitespace()
+        .map(|scope| Scope::from_str(scope).map_err(|_| scope.to_string()))
+        .collect()
+}
+
+pub fn join_scopes(scopes: &HashSet<Scope>) -> String {
+    let mut scopes: Vec<String> = scopes.iter().map(Scope::to_string).collect();
+    scopes.sort();
+    scopes.join(" ")
+}
+
+fn generate_secret(prefix: &str) -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", prefix, hex::encode(bytes))
+}
+
+pub fn generate_token() -> String {
+    generate_secret(TOKEN_PREFIX)
+}
+
+pub fn generate_webhook_secret() -> String {
+    generate_secret(WEBHOOK_SECRET_PREFIX)
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub fn auth_required() -> bool {
+    matches!(
+        std::env::var("AUTH_REQUIRED").as_deref(),
+        Ok("1") | Ok("true")
+    )
+}
@@ -0,0 +1,439 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::lib::recurrence::{parse_timezone, to_utc};
+use crate::model::import::{ImportedRow, RowError};
+use entity::sea_orm_active_enums::Priority;
+use entity::task::Model as Task;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use sea_orm::ActiveEnum;
+use uuid::Uuid;
+
+/// Columns of an export, named like the fields of a task in JSON.
+const EXPORT_COLUMNS: [&str; 9] = [
+    "id",
+    "title",
+    "completed",
+    "createdAt",
+    "deadline",
+    "listId",
+    "priority",
+    "rrule",
+    "timezone",
+];
+
+/// Fields read by an import. Everything else, e.g. `id`, is ignored.
+const IMPORT_FIELDS: [&str; 7] = [
+    "title",
+    "completed",
+    "deadline",
+    "listId",
+    "priority",
+    "rrule",
+    "timezone",
+];
+
+const LOCAL_DATE_TIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// Writes a single RFC 4180 record, quoting fields only where needed.
+fn write_record<I, T>(record: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    let mut writer = ::csv::WriterBuilder::new()
+        .terminator(::csv::Terminator::CRLF)
+        .from_writer(Vec::new());
+    // Writing into a Vec<u8> cannot fail
+    writer
+        .write_record(record)
+        .expect("Failed to write CSV record");
+    writer.into_inner().expect("Failed to flush CSV record")
+}
+
+fn format_date(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+pub fn header() -> Vec<u8> {
+    write_record(EXPORT_COLUMNS)
+}
+
+pub fn row(task: &Task) -> Vec<u8> {
+    write_record([
+        task.id.to_string(),
+        task.title.clone(),
+        task.completed.to_string(),
+        format_date(task.created_at),
+        task.deadline.map(format_date).unwrap_or_default(),
+        task.list_id.map(|id| id.to_string()).unwrap_or_default(),
+        task.priority.to_value(),
+        task.rrule.clone().unwrap_or_default(),
+        task.timezone.clone().unwrap_or_default(),
+    ])
+}
+
+/// Which column of an import holds a field, given as `field:Column` pairs
+/// separated by commas, e.g. `title:Name,deadline:Due Date`.
+/// Fields that are not mapped are read from the column of the same name.
+#[derive(Debug, Default)]
+pub struct ColumnMapping(HashMap<&'static str, String>);
+
+impl FromStr for ColumnMapping {
+    type Err = String;
+
+    fn from_str(mapping: &str) -> Result<Self, Self::Err> {
+        let mut columns = HashMap::new();
+        for pair in mapping.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let (field, column) = pair
+                .split_once(':')
+                .ok_or_else(|| format!("Malformed column mapping `{}`", pair))?;
+            let field = IMPORT_FIELDS
+                .into_iter()
+                .find(|known| known.eq_ignore_ascii_case(field.trim()))
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown field `{}`. Valid fields are: {}",
+                        field.trim(),
+                        IMPORT_FIELDS.join(", ")
+                    )
+                })?;
+            columns.insert(field, column.trim().to_string());
+        }
+        Ok(ColumnMapping(columns))
+    }
+}
+
+/// Compares column names ignoring case, spaces and underscores, so that
+/// e.g. `List ID` matches `listId`.
+fn same_column(a: &str, b: &str) -> bool {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|c| !c.is_whitespace() && *c != '_')
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    normalize(a) == normalize(b)
+}
+
+impl ColumnMapping {
+    /// Resolves the index of every field's column in `headers`.
+    fn resolve(
+        &self,
+        headers: &::csv::StringRecord,
+    ) -> Result<HashMap<&'static str, usize>, String> {
+        let mut indices = HashMap::new();
+        for field in IMPORT_FIELDS {
+            let index = match self.0.get(field) {
+                Some(column) => Some(
+                    headers
+                        .iter()
+                        .position(|header| header == column)
+                        .ok_or_else(|| {
+                            format!("Column `{}` mapped to `{}` not found", column, field)
+                        })?,
+                ),
+                None => headers.iter().position(|header| same_column(header, field)),
+            };
+            if let Some(index) = index {
+                indices.insert(field, index);
+            }
+        }
+        if !indices.contains_key("title") {
+            return Err("No 'title' column. Map one with `columns=title:<column>`".to_string());
+        }
+        Ok(indices)
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "" | "false" | "no" | "0" => Ok(false),
+        "true" | "yes" | "1" | "x" => Ok(true),
+        _ => Err(format!("Invalid boolean `{}`", value)),
+    }
+}
+
+/// Accepts RFC 3339, local date-times and dates in `timezone`, and
+/// milliseconds since the epoch.
+fn parse_date(value: &str, timezone: &Tz) -> Result<DateTime<Utc>, String> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date_time.with_timezone(&Utc));
+    }
+    for format in LOCAL_DATE_TIME_FORMATS {
+        if let Ok(local) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(to_utc(timezone, local));
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(to_utc(timezone, date.and_hms(0, 0, 0)));
+    }
+    value
+        .parse()
+        .ok()
+        .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+        .ok_or_else(|| format!("Invalid date `{}`", value))
+}
+
+fn parse_record(
+    row: u64,
+    record: &::csv::StringRecord,
+    columns: &HashMap<&'static str, usize>,
+) -> Result<Task, Vec<RowError>> {
+    let field = |name: &str| {
+        columns
+            .get(name)
+            .and_then(|index| record.get(*index))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let mut errors = Vec::new();
+    let mut error = |field: &str, message: String| {
+        errors.push(RowError::new(row, Some(field), message));
+    };
+
+    let title = field("title").map(str::to_string);
+    if title.is_none() {
+        error("title", "Missing title".to_string());
+    }
+
+    let completed = parse_bool(field("completed").unwrap_or_default()).unwrap_or_else(|err| {
+        error("completed", err);
+        false
+    });
+
+    let timezone = field("timezone").map(str::to_string);
+    let tz = match timezone.as_deref().map(parse_timezone) {
+        Some(Ok(tz)) => tz,
+        Some(Err(err)) => {
+            error("timezone", err);
+            Tz::UTC
+        }
+        None => Tz::UTC,
+    };
+
+    let deadline = field("deadline").and_then(|value| match parse_date(value, &tz) {
+        Ok(deadline) => Some(deadline),
+        Err(err) => {
+            error("deadline", err);
+            None
+        }
+    });
+
+    let list_id = field("listId").and_then(|value| match Uuid::parse_str(value) {
+        Ok(list_id) => Some(list_id),
+        Err(_) => {
+            error("listId", format!("Invalid list id `{}`", value));
+            None
+        }
+    });
+
+    let priority = match field("priority") {
+        Some(value) => Priority::try_from_value(&value.to_lowercase()).unwrap_or_else(|_| {
+            error("priority", format!("Invalid priority `{}`", value));
+            Priority::None
+        }),
+        None => Priority::None,
+    };
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut task = Task::new(title.unwrap_or_default(), completed, deadline);
+    task.list_id = list_id;
+    task.priority = priority;
+    task.rrule = field("rrule").map(str::to_string);
+    task.timezone = timezone;
+    Ok(task)
+}
+
+/// The line a record starts on. A record after a CRLF terminator starts at
+/// its `\n`, which the reader has not counted yet.
+fn line(data: &[u8], position: Option<&::csv::Position>) -> u64 {
+    match position {
+        Some(position) => match data.get(position.byte() as usize) {
+            Some(b'\n') => position.line() + 1,
+            _ => position.line(),
+        },
+        None => 0,
+    }
+}
+
+/// Reads the tasks of a CSV import with a header row. Rows that cannot be
+/// read are reported instead of failing the whole import.
+pub fn parse(
+    data: &[u8],
+    mapping: &ColumnMapping,
+) -> Result<(Vec<ImportedRow>, Vec<RowError>), String> {
+    let mut reader = ::csv::ReaderBuilder::new().flexible(true).from_reader(data);
+    let headers = reader
+        .headers()
+        .map_err(|err| format!("Invalid header row: {}", err))?
+        .clone();
+    let columns = mapping.resolve(&headers)?;
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                let row = line(data, err.position());
+                errors.push(RowError::new(row, None, err.to_string()));
+                continue;
+            }
+        };
+        let row = line(data, record.position());
+        if record.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        match parse_record(row, &record, &columns) {
+            Ok(task) => rows.push(ImportedRow { row, task }),
+            Err(row_errors) => errors.extend(row_errors),
+        }
+    }
+
+    Ok((rows, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(date_time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(date_time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn read(data: &str) -> (Vec<ImportedRow>, Vec<RowError>) {
+        parse(data.as_bytes(), &ColumnMapping::default()).unwrap()
+    }
+
+    #[test]
+    fn quotes_fields_and_ends_records_with_crlf() {
+        let mut task = Task::new(
+            "Buy \"milk\", eggs\nand bread".to_string(),
+            false,
+            Some(utc("2024-05-06T09:30:00Z")),
+        );
+        task.priority = Priority::High;
+
+        let row = String::from_utf8(row(&task)).unwrap();
+        assert!(row.ends_with("\r\n"));
+        assert!(row.contains(",\"Buy \"\"milk\"\", eggs\nand bread\",false,"));
+        assert!(row.contains(",2024-05-06T09:30:00.000Z,,high,,\r\n"));
+        assert_eq!(
+            String::from_utf8(header()).unwrap(),
+            "id,title,completed,createdAt,deadline,listId,priority,rrule,timezone\r\n"
+        );
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let mut task = Task::new(
+            "Buy \"milk\", eggs\r\nand bread".to_string(),
+            true,
+            Some(utc("2024-05-06T09:30:00Z")),
+        );
+        task.list_id = Some(Uuid::new_v4());
+        task.priority = Priority::Urgent;
+        task.rrule = Some("FREQ=WEEKLY;BYDAY=MO,TH".to_string());
+        task.timezone = Some("Europe/Berlin".to_string());
+
+        let mut data = header();
+        data.extend(row(&task));
+        data.extend(row(&Task::new("Second".to_string(), false, None)));
+        let (rows, errors) = parse(&data, &ColumnMapping::default()).unwrap();
+
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(rows.len(), 2);
+        let imported = &rows[0].task;
+        assert_eq!(rows[0].row, 2);
+        assert_eq!(imported.title, task.title);
+        assert_eq!(imported.completed, task.completed);
+        assert_eq!(imported.deadline, task.deadline);
+        assert_eq!(imported.list_id, task.list_id);
+        assert_eq!(imported.priority, task.priority);
+        assert_eq!(imported.rrule, task.rrule);
+        assert_eq!(imported.timezone, task.timezone);
+        assert_ne!(imported.id, task.id);
+        assert_eq!(rows[1].row, 4);
+        assert_eq!(rows[1].task.title, "Second");
+        assert_eq!(rows[1].task.deadline, None);
+    }
+
+    #[test]
+    fn reads_mapped_columns_and_local_dates() {
+        let mapping = "title:Name, deadline:Due Date"
+            .parse::<ColumnMapping>()
+            .unwrap();
+        let data = "Name,Due Date,Time Zone,Completed\r\n\
+                    Pay rent,2024-03-31 02:30,Europe/Berlin,yes\r\n\
+                    Call mom,2024-05-06,,\r\n\
+                    ,,,\r\n";
+        let (rows, errors) = parse(data.as_bytes(), &mapping).unwrap();
+
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].task.title, "Pay rent");
+        // 02:30 does not exist in Berlin on that day and is read as 03:30
+        assert_eq!(rows[0].task.deadline, Some(utc("2024-03-31T01:30:00Z")));
+        assert!(rows[0].task.completed);
+        assert_eq!(rows[1].row, 3);
+        assert_eq!(rows[1].task.deadline, Some(utc("2024-05-06T00:00:00Z")));
+    }
+
+    #[test]
+    fn reports_malformed_rows_and_keeps_the_others() {
+        let (rows, errors) = read(
+            "title,completed,deadline,priority,listId\n\
+             Fine,,1714988400000,low,\n\
+             ,maybe,tomorrow,highest,not-a-uuid\n\
+             \"Unterminated,\n",
+        );
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].task.deadline, Some(utc("2024-05-06T09:40:00Z")));
+        assert_eq!(rows[0].task.priority, Priority::Low);
+        assert_eq!(rows[1].row, 4);
+        assert_eq!(rows[1].task.title, "Unterminated,");
+
+        let fields: Vec<_> = errors
+            .iter()
+            .map(|error| (error.row, error.field.as_deref()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                (3, Some("title")),
+                (3, Some("completed")),
+                (3, Some("deadline")),
+                (3, Some("listId")),
+                (3, Some("priority")),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unusable_headers_and_mappings() {
+        let mapping = ColumnMapping::default();
+        assert!(parse(b"name,deadline\nPay rent,\n", &mapping).is_err());
+        assert!(parse(b"", &mapping).is_err());
+
+        let mapping = "title:Name".parse::<ColumnMapping>().unwrap();
+        assert!(parse(b"title\nPay rent\n", &mapping).is_err());
+
+        assert!("title".parse::<ColumnMapping>().is_err());
+        assert!("owner:Name".parse::<ColumnMapping>().is_err());
+        assert!("".parse::<ColumnMapping>().is_ok());
+    }
+}
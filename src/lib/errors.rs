@@ -4,6 +4,9 @@ pub enum Error {
     #[error("sea_orm::DbErr: {0}")]
     Db(#[from] sea_orm::DbErr),
 
+    #[error("sqlx::Error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+
     #[error("ENV VARIABLE for `{0}` is not set")]
     EnvironmentVariableNotSet(String),
 
@@ -15,4 +18,22 @@ pub enum Error {
 
     #[error("InvalidUuid: UUID {0} is not valid")]
     InvalidUuid(String),
+
+    #[error("reqwest::Error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Schema mismatch: {0}")]
+    SchemaMismatch(String),
+
+    #[error("Invalid position: {0}")]
+    InvalidPosition(String),
+
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
 }
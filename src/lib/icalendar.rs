@@ -0,0 +1,636 @@
+use std::collections::{HashMap, HashSet};
+use std::io::BufReader;
+use std::str::FromStr;
+
+use crate::lib::recurrence::{parse_timezone, to_utc, RRule};
+use entity::sea_orm_active_enums::Priority;
+use entity::task::Model as Task;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use ical::parser::ical::component::IcalTimeZone;
+use ical::property::Property;
+use serde::Serialize;
+use uuid::Uuid;
+
+const PRODID: &str = "-//ToDo-SeaOrm//Tasks//EN";
+
+/// Namespace of the task ids derived from imported UIDs.
+const UID_NAMESPACE: Uuid = uuid::uuid!("84e777a2-05f0-41fd-81a5-a08c9a1ea8c4");
+
+/// Windows timezone names used by Outlook and Exchange exports, mapped to
+/// their IANA counterparts.
+const WINDOWS_TIMEZONES: [(&str, &str); 16] = [
+    ("UTC", "UTC"),
+    ("GMT Standard Time", "Europe/London"),
+    ("W. Europe Standard Time", "Europe/Berlin"),
+    ("Romance Standard Time", "Europe/Paris"),
+    ("Central Europe Standard Time", "Europe/Budapest"),
+    ("E. Europe Standard Time", "Europe/Chisinau"),
+    ("FLE Standard Time", "Europe/Kiev"),
+    ("Russian Standard Time", "Europe/Moscow"),
+    ("Eastern Standard Time", "America/New_York"),
+    ("Central Standard Time", "America/Chicago"),
+    ("Mountain Standard Time", "America/Denver"),
+    ("Pacific Standard Time", "America/Los_Angeles"),
+    ("India Standard Time", "Asia/Kolkata"),
+    ("China Standard Time", "Asia/Shanghai"),
+    ("Tokyo Standard Time", "Asia/Tokyo"),
+    ("AUS Eastern Standard Time", "Australia/Sydney"),
+];
+
+/// Content lines are folded after this many octets (RFC 5545, section 3.1).
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Components rendered for every task with a deadline, given as a comma
+/// separated list of `vtodo` and `vevent`.
+#[derive(Debug, Clone, Copy)]
+pub struct Components {
+    pub todo: bool,
+    pub event: bool,
+}
+
+impl Default for Components {
+    fn default() -> Self {
+        Components {
+            todo: true,
+            event: false,
+        }
+    }
+}
+
+impl FromStr for Components {
+    type Err = String;
+
+    fn from_str(components: &str) -> Result<Self, Self::Err> {
+        let mut parsed = Components {
+            todo: false,
+            event: false,
+        };
+        for component in components.split(',') {
+            match component.trim().to_lowercase().as_str() {
+                "vtodo" => parsed.todo = true,
+                "vevent" => parsed.event = true,
+                _ => {
+                    return Err(format!(
+                        "Invalid component: {}. Valid components are: vtodo, vevent",
+                        component
+                    ))
+                }
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Maps priorities onto the 1 (highest) to 9 (lowest) scale of `PRIORITY`,
+/// `None` being undefined.
+pub fn ical_priority(priority: Priority) -> Option<u8> {
+    match priority {
+        Priority::None => None,
+        Priority::Low => Some(7),
+        Priority::Medium => Some(5),
+        Priority::High => Some(3),
+        Priority::Urgent => Some(1),
+    }
+}
+
+fn format_date_time(date: DateTime<Utc>) -> String {
+    date.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\n', '\r'], "\\n")
+}
+
+struct Writer(String);
+
+impl Writer {
+    /// Appends a content line, folding it without splitting characters.
+    fn line(&mut self, line: &str) {
+        let mut octets = 0;
+        for c in line.chars() {
+            if octets + c.len_utf8() > MAX_LINE_OCTETS {
+                self.0.push_str("\r\n ");
+                octets = 1;
+            }
+            self.0.push(c);
+            octets += c.len_utf8();
+        }
+        self.0.push_str("\r\n");
+    }
+
+    fn property(&mut self, name: &str, value: &str) {
+        self.line(&format!("{}:{}", name, value));
+    }
+}
+
+/// Renders the tasks that have a deadline as an iCalendar feed. Deadlines are
+/// given in UTC and recurring tasks without their `RRULE`, as every occurrence
+/// is a task of its own.
+pub fn calendar(tasks: &[Task], components: Components, now: DateTime<Utc>) -> String {
+    let mut writer = Writer(String::new());
+    let dtstamp = format_date_time(now);
+
+    writer.property("BEGIN", "VCALENDAR");
+    writer.property("VERSION", "2.0");
+    writer.property("PRODID", PRODID);
+    writer.property("CALSCALE", "GREGORIAN");
+    writer.property("METHOD", "PUBLISH");
+    writer.property("X-WR-CALNAME", "Tasks");
+
+    for task in tasks {
+        let deadline = match task.deadline {
+            Some(deadline) => format_date_time(deadline),
+            None => continue,
+        };
+        let summary = escape_text(&task.title);
+
+        if components.todo {
+            writer.property("BEGIN", "VTODO");
+            writer.property("UID", &task.id.to_string());
+            writer.property("DTSTAMP", &dtstamp);
+            writer.property("CREATED", &format_date_time(task.created_at));
+            writer.property("SUMMARY", &summary);
+            writer.property("DUE", &deadline);
+            if task.completed {
+                writer.property("STATUS", "COMPLETED");
+                writer.property("PERCENT-COMPLETE", "100");
+                if let Some(completed_at) = task.completed_at {
+                    writer.property("COMPLETED", &format_date_time(completed_at));
+                }
+            } else {
+                writer.property("STATUS", "NEEDS-ACTION");
+            }
+            if let Some(priority) = ical_priority(task.priority) {
+                writer.property("PRIORITY", &priority.to_string());
+            }
+            writer.property("END", "VTODO");
+        }
+
+        // Events are placed at the deadline, without blocking any time
+        if components.event {
+            writer.property("BEGIN", "VEVENT");
+            writer.property("UID", &format!("{}-deadline", task.id));
+            writer.property("DTSTAMP", &dtstamp);
+            writer.property("CREATED", &format_date_time(task.created_at));
+            writer.property("SUMMARY", &summary);
+            writer.property("DTSTART", &deadline);
+            writer.property("DTEND", &deadline);
+            writer.property("TRANSP", "TRANSPARENT");
+            if let Some(priority) = ical_priority(task.priority) {
+                writer.property("PRIORITY", &priority.to_string());
+            }
+            writer.property("END", "VEVENT");
+        }
+    }
+
+    writer.property("END", "VCALENDAR");
+    writer.0
+}
+
+/// A component of an import that did not become a task.
+#[derive(Serialize, Debug, Clone)]
+pub struct SkippedComponent {
+    /// e.g. `VTODO` or `VEVENT`
+    pub component: String,
+
+    /// Position among the components of its kind, starting at 1
+    pub index: usize,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+
+    pub reason: String,
+}
+
+/// A `VTODO` read from an import. Its task id is derived from the `UID`, so
+/// importing the same component again yields the same id.
+pub struct ImportedTodo {
+    pub index: usize,
+    pub uid: Option<String>,
+    pub task: Task,
+}
+
+impl ImportedTodo {
+    pub fn skip(&self, reason: String) -> SkippedComponent {
+        SkippedComponent {
+            component: "VTODO".to_string(),
+            index: self.index,
+            uid: self.uid.clone(),
+            summary: Some(self.task.title.clone()),
+            reason,
+        }
+    }
+}
+
+/// Task id of a `UID`: the UID itself if it is a UUID, as in our own feed,
+/// otherwise one derived from it.
+pub fn task_id(uid: &str) -> Uuid {
+    Uuid::parse_str(uid).unwrap_or_else(|_| Uuid::new_v5(&UID_NAMESPACE, uid.as_bytes()))
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+fn property<'a>(properties: &'a [Property], name: &str) -> Option<&'a Property> {
+    properties
+        .iter()
+        .find(|property| property.name.eq_ignore_ascii_case(name))
+}
+
+fn value<'a>(properties: &'a [Property], name: &str) -> Option<&'a str> {
+    property(properties, name)
+        .and_then(|property| property.value.as_deref())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn param<'a>(property: &'a Property, name: &str) -> Option<&'a str> {
+    property
+        .params
+        .iter()
+        .flatten()
+        .find(|(key, _)| key == name)
+        .and_then(|(_, values)| values.first())
+        .map(|value| value.trim_matches('"'))
+}
+
+/// Resolves a `TZID` to an IANA timezone, also when it is a Windows name or
+/// prefixed like `/mozilla.org/20050126_1/Europe/Berlin`.
+fn resolve_tzid(tzid: &str, location: Option<&str>) -> Option<Tz> {
+    let candidates = std::iter::once(tzid)
+        .chain(location)
+        .chain(tzid.match_indices('/').map(|(index, _)| &tzid[index + 1..]));
+    for candidate in candidates {
+        if let Ok(tz) = candidate.parse() {
+            return Some(tz);
+        }
+    }
+    WINDOWS_TIMEZONES
+        .iter()
+        .find(|(windows, _)| windows.eq_ignore_ascii_case(tzid))
+        .and_then(|(_, iana)| iana.parse().ok())
+}
+
+/// Timezones of the `VTIMEZONE`s of a calendar, by `TZID`.
+fn timezones(definitions: &[IcalTimeZone]) -> HashMap<String, Tz> {
+    definitions
+        .iter()
+        .filter_map(|definition| {
+            let tzid = value(&definition.properties, "TZID")?;
+            let location = value(&definition.properties, "X-LIC-LOCATION");
+            resolve_tzid(tzid, location).map(|tz| (tzid.to_string(), tz))
+        })
+        .collect()
+}
+
+/// Reads a `DATE` or `DATE-TIME` property along with the timezone it names,
+/// UTC for times with `Z`. Times without `Z` or `TZID` and dates are taken to
+/// be in `floating`, without naming a timezone.
+fn parse_date(
+    property: &Property,
+    timezones: &HashMap<String, Tz>,
+    floating: Tz,
+) -> Result<(DateTime<Utc>, Option<Tz>), String> {
+    let value = property
+        .value
+        .as_deref()
+        .map(str::trim)
+        .ok_or_else(|| format!("Empty {}", property.name))?;
+    let tz = match param(property, "TZID") {
+        Some(tzid) => Some(
+            timezones
+                .get(tzid)
+                .copied()
+                .or_else(|| resolve_tzid(tzid, None))
+                .ok_or_else(|| format!("Unknown timezone `{}`", tzid))?,
+        ),
+        None => None,
+    };
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .map(|date_time| (Utc.from_utc_datetime(&date_time), Some(Tz::UTC)))
+            .map_err(|_| format!("Invalid {} `{}`", property.name, value));
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y%m%d").map(|date| date.and_hms(0, 0, 0)))
+        .map_err(|_| format!("Invalid {} `{}`", property.name, value))?;
+    Ok((to_utc(&tz.unwrap_or(floating), local), tz))
+}
+
+/// Inverse of [`ical_priority`].
+fn parse_priority(priority: &str) -> Priority {
+    match priority.parse::<u8>() {
+        Ok(1..=2) => Priority::Urgent,
+        Ok(3..=4) => Priority::High,
+        Ok(5) => Priority::Medium,
+        Ok(6..=9) => Priority::Low,
+        _ => Priority::None,
+    }
+}
+
+fn parse_todo(
+    properties: &[Property],
+    timezones: &HashMap<String, Tz>,
+    floating: Tz,
+) -> Result<Task, String> {
+    let title = value(properties, "SUMMARY")
+        .map(unescape_text)
+        .ok_or_else(|| "Missing SUMMARY".to_string())?;
+    let status = value(properties, "STATUS").map(str::to_uppercase);
+    if status.as_deref() == Some("CANCELLED") {
+        return Err("Cancelled".to_string());
+    }
+    let completed =
+        status.as_deref() == Some("COMPLETED") || property(properties, "COMPLETED").is_some();
+
+    let (deadline, deadline_tz) = match property(properties, "DUE") {
+        Some(due) => {
+            let (deadline, tz) = parse_date(due, timezones, floating)?;
+            (Some(deadline), tz)
+        }
+        None => (None, None),
+    };
+
+    let mut task = Task::new(title, completed, deadline);
+    if let Some(uid) = value(properties, "UID") {
+        task.id = task_id(uid);
+    }
+    if let Some(created) = property(properties, "CREATED") {
+        task.created_at = parse_date(created, timezones, Tz::UTC)?.0;
+    }
+    if let Some(completed_at) = property(properties, "COMPLETED") {
+        task.completed_at = Some(parse_date(completed_at, timezones, Tz::UTC)?.0);
+    }
+    if let Some(priority) = value(properties, "PRIORITY") {
+        task.priority = parse_priority(priority);
+    }
+    if let Some(rrule) = value(properties, "RRULE") {
+        rrule
+            .parse::<RRule>()
+            .map_err(|err| format!("Unsupported RRULE: {}", err))?;
+        task.rrule = Some(rrule.to_string());
+        task.timezone = deadline_tz
+            .or(Some(floating))
+            .filter(|tz| *tz != Tz::UTC)
+            .map(|tz| tz.name().to_string());
+    }
+    Ok(task)
+}
+
+fn skip_all(kind: &str, components: &[Vec<Property>], skipped: &mut Vec<SkippedComponent>) {
+    for (index, properties) in components.iter().enumerate() {
+        skipped.push(SkippedComponent {
+            component: kind.to_string(),
+            index: index + 1,
+            uid: value(properties, "UID").map(str::to_string),
+            summary: value(properties, "SUMMARY").map(unescape_text),
+            reason: "Only VTODO components are imported".to_string(),
+        });
+    }
+}
+
+/// Reads the `VTODO`s of an iCalendar file, reporting the components that
+/// cannot be imported as well as repeated UIDs. Floating times are taken to
+/// be in `floating`.
+pub fn parse(
+    data: &[u8],
+    floating: &str,
+) -> Result<(Vec<ImportedTodo>, Vec<SkippedComponent>), String> {
+    let floating = parse_timezone(floating)?;
+    let mut todos = Vec::new();
+    let mut skipped = Vec::new();
+    let mut seen = HashSet::new();
+
+    let mut index = 0;
+    let mut events = Vec::new();
+    let mut journals = Vec::new();
+    for calendar in ical::IcalParser::new(BufReader::new(data)) {
+        let calendar = calendar.map_err(|err| format!("Invalid iCalendar file: {}", err))?;
+        let timezones = timezones(&calendar.timezones);
+
+        for todo in calendar.todos {
+            index += 1;
+            let uid = value(&todo.properties, "UID").map(str::to_string);
+            match parse_todo(&todo.properties, &timezones, floating) {
+                Ok(task) if !seen.insert(task.id) => skipped.push(SkippedComponent {
+                    component: "VTODO".to_string(),
+                    index,
+                    uid,
+                    summary: Some(task.title),
+                    reason: "Duplicate UID".to_string(),
+                }),
+                Ok(task) => todos.push(ImportedTodo { index, uid, task }),
+                Err(reason) => skipped.push(SkippedComponent {
+                    component: "VTODO".to_string(),
+                    index,
+                    uid,
+                    summary: value(&todo.properties, "SUMMARY").map(unescape_text),
+                    reason,
+                }),
+            }
+        }
+        events.extend(calendar.events.into_iter().map(|event| event.properties));
+        journals.extend(
+            calendar
+                .journals
+                .into_iter()
+                .map(|journal| journal.properties),
+        );
+    }
+    skip_all("VEVENT", &events, &mut skipped);
+    skip_all("VJOURNAL", &journals, &mut skipped);
+
+    Ok((todos, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(date_time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(date_time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn vcalendar(components: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\n{}END:VCALENDAR\r\n",
+            components
+        )
+    }
+
+    fn reasons(skipped: &[SkippedComponent]) -> Vec<(&str, usize, &str)> {
+        skipped
+            .iter()
+            .map(|skipped| {
+                (
+                    skipped.component.as_str(),
+                    skipped.index,
+                    skipped.reason.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_back_its_own_feed() {
+        let mut task = Task::new(
+            "Call Jörg; bring the \"blue\" folder, the keys\nand a very long tail that has to be folded".to_string(),
+            true,
+            Some(utc("2024-05-06T09:30:00Z")),
+        );
+        task.created_at = utc("2024-05-01T08:00:00Z");
+        task.completed_at = Some(utc("2024-05-05T18:15:00Z"));
+        task.priority = Priority::High;
+        let undated = Task::new("No deadline".to_string(), false, None);
+
+        let feed = calendar(
+            &[task.clone(), undated],
+            Components::default(),
+            utc("2024-05-06T12:00:00Z"),
+        );
+        assert!(feed.lines().all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert!(feed.contains("SUMMARY:Call Jörg\\; bring the \"blue\" folder\\, the keys\\n"));
+
+        let (todos, skipped) = parse(feed.as_bytes(), "UTC").unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(todos.len(), 1);
+        let imported = &todos[0].task;
+        assert_eq!(imported.id, task.id);
+        assert_eq!(imported.title, task.title);
+        assert_eq!(imported.completed, task.completed);
+        assert_eq!(imported.deadline, task.deadline);
+        assert_eq!(imported.created_at, task.created_at);
+        assert_eq!(imported.completed_at, task.completed_at);
+        assert_eq!(imported.priority, task.priority);
+        assert_eq!(imported.rrule, None);
+    }
+
+    #[test]
+    fn reads_due_dates_in_their_timezone() {
+        let data = vcalendar(
+            "BEGIN:VTIMEZONE\r\nTZID:W. Europe Standard Time\r\nEND:VTIMEZONE\r\n\
+             BEGIN:VTODO\r\nUID:a\r\nSUMMARY:Windows\r\n\
+             DUE;TZID=W. Europe Standard Time:20240331T023000\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:b\r\nSUMMARY:Prefixed\r\n\
+             DUE;TZID=/mozilla.org/20050126_1/America/New_York:20241103T013000\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:c\r\nSUMMARY:Floating\r\nDUE:20240506T090000\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:d\r\nSUMMARY:Date\r\nDUE;VALUE=DATE:20240506\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:e\r\nSUMMARY:UTC\r\nDUE:20240506T090000Z\r\nEND:VTODO\r\n",
+        );
+        let (todos, skipped) = parse(data.as_bytes(), "Asia/Tokyo").unwrap();
+
+        assert!(skipped.is_empty(), "{:?}", reasons(&skipped));
+        let deadlines: Vec<_> = todos.iter().map(|todo| todo.task.deadline).collect();
+        assert_eq!(
+            deadlines,
+            [
+                // 02:30 does not exist in Berlin on that day and is read as 03:30
+                Some(utc("2024-03-31T01:30:00Z")),
+                // Of the repeated 01:30 in New York, the earlier one is taken
+                Some(utc("2024-11-03T05:30:00Z")),
+                Some(utc("2024-05-06T00:00:00Z")),
+                Some(utc("2024-05-05T15:00:00Z")),
+                Some(utc("2024-05-06T09:00:00Z")),
+            ]
+        );
+        assert_eq!(todos[0].task.id, task_id("a"));
+        assert_eq!(todos[0].uid.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn keeps_recurrence_rules_with_the_timezone_of_their_due_date() {
+        let data = vcalendar(
+            "BEGIN:VTODO\r\nUID:zoned\r\nSUMMARY:Standup\r\n\
+             DUE;TZID=Europe/Berlin:20240506T090000\r\n\
+             RRULE:FREQ=WEEKLY;BYDAY=MO,TH\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:floating\r\nSUMMARY:Water plants\r\n\
+             DUE:20240506T090000\r\nRRULE:FREQ=DAILY;INTERVAL=2\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:utc\r\nSUMMARY:Backup\r\n\
+             DUE:20240506T090000Z\r\nRRULE:FREQ=MONTHLY\r\nEND:VTODO\r\n",
+        );
+        let (todos, skipped) = parse(data.as_bytes(), "America/Chicago").unwrap();
+
+        assert!(skipped.is_empty(), "{:?}", reasons(&skipped));
+        let rules: Vec<_> = todos
+            .iter()
+            .map(|todo| (todo.task.rrule.as_deref(), todo.task.timezone.as_deref()))
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                (Some("FREQ=WEEKLY;BYDAY=MO,TH"), Some("Europe/Berlin")),
+                (Some("FREQ=DAILY;INTERVAL=2"), Some("America/Chicago")),
+                (Some("FREQ=MONTHLY"), None),
+            ]
+        );
+        assert_eq!(todos[0].task.deadline, Some(utc("2024-05-06T07:00:00Z")));
+    }
+
+    #[test]
+    fn skips_components_that_cannot_be_imported() {
+        let data = vcalendar(
+            "BEGIN:VTODO\r\nUID:ok\r\nSUMMARY:Fine\r\nPRIORITY:9\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:no-summary\r\nDUE:20240506T090000Z\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:bad-due\r\nSUMMARY:Bad due\r\nDUE:tomorrow\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:bad-tz\r\nSUMMARY:Bad zone\r\nDUE;TZID=Mars/Olympus:20240506T090000\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:bad-rule\r\nSUMMARY:Bad rule\r\nDUE:20240506T090000Z\r\nRRULE:FREQ=SECONDLY\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:huge-rule\r\nSUMMARY:Huge rule\r\nDUE:20240506T090000Z\r\nRRULE:FREQ=DAILY;INTERVAL=4000000000\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:cancelled\r\nSUMMARY:Cancelled\r\nSTATUS:CANCELLED\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nUID:ok\r\nSUMMARY:Again\r\nEND:VTODO\r\n\
+             BEGIN:VEVENT\r\nUID:event\r\nSUMMARY:Meeting\r\nDTSTART:20240506T090000Z\r\nEND:VEVENT\r\n",
+        );
+        let (todos, skipped) = parse(data.as_bytes(), "UTC").unwrap();
+
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].task.title, "Fine");
+        assert_eq!(todos[0].task.priority, Priority::Low);
+
+        let reasons = reasons(&skipped);
+        assert_eq!(reasons.len(), 8, "{:?}", reasons);
+        assert_eq!(reasons[0], ("VTODO", 2, "Missing SUMMARY"));
+        assert_eq!(reasons[1], ("VTODO", 3, "Invalid DUE `tomorrow`"));
+        assert_eq!(reasons[2], ("VTODO", 4, "Unknown timezone `Mars/Olympus`"));
+        assert!(reasons[3].2.starts_with("Unsupported RRULE"));
+        assert!(reasons[4].2.starts_with("Unsupported RRULE"));
+        assert_eq!(reasons[5], ("VTODO", 7, "Cancelled"));
+        assert_eq!(reasons[6], ("VTODO", 8, "Duplicate UID"));
+        assert_eq!(
+            reasons[7],
+            ("VEVENT", 1, "Only VTODO components are imported")
+        );
+    }
+
+    #[test]
+    fn rejects_unreadable_files() {
+        assert!(parse(b"BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nSUMMARY:Open\r\n", "UTC").is_err());
+        assert!(parse(b"BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n", "Mars/Olympus").is_err());
+        assert!("vtodo,vevent".parse::<Components>().is_ok());
+        assert!("vjournal".parse::<Components>().is_err());
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use crate::lib::recurrence::to_utc;
+use crate::lib::todotxt::project;
+use crate::model::import::{ImportedRow, RowError};
+use entity::task::Model as Task;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Timelike, Utc};
+use chrono_tz::Tz;
+use uuid::Uuid;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+const NO_LIST: &str = "No list";
+const NO_DEADLINE: &str = "No deadline";
+
+/// How the checklist of an export is split into sections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    List,
+    Day,
+}
+
+impl FromStr for Grouping {
+    type Err = String;
+
+    fn from_str(grouping: &str) -> Result<Self, Self::Err> {
+        match grouping.to_lowercase().as_str() {
+            "list" => Ok(Grouping::List),
+            "day" => Ok(Grouping::Day),
+            _ => Err(format!(
+                "Invalid group: {}. Valid groups are: list, day",
+                grouping
+            )),
+        }
+    }
+}
+
+fn format_due(deadline: DateTime<Utc>, tz: &Tz) -> String {
+    let local = deadline.with_timezone(tz);
+    if (local.hour(), local.minute()) == (0, 0) {
+        local.format(DATE_FORMAT).to_string()
+    } else {
+        local.format(DATE_TIME_FORMAT).to_string()
+    }
+}
+
+fn write_item(
+    out: &mut String,
+    task: &Task,
+    depth: usize,
+    subtasks: &HashMap<Uuid, Vec<&Task>>,
+    tz: &Tz,
+) {
+    out.push_str(&"  ".repeat(depth));
+    out.push_str(if task.completed { "- [x] " } else { "- [ ] " });
+    out.push_str(&task.title.split_whitespace().collect::<Vec<_>>().join(" "));
+    if let Some(deadline) = task.deadline {
+        out.push_str(&format!(" (due {})", format_due(deadline, tz)));
+    }
+    out.push('\n');
+
+    for subtask in subtasks.get(&task.id).into_iter().flatten() {
+        write_item(out, subtask, depth + 1, subtasks, tz);
+    }
+}
+
+/// Renders tasks as GitHub-flavoured checklists, one section per list or
+/// deadline day in `tz`. Subtasks are nested below their parent if it is
+/// part of the export, otherwise they are listed in their own section.
+pub fn checklist(
+    tasks: &[Task],
+    grouping: Grouping,
+    list_names: &HashMap<Uuid, String>,
+    tz: &Tz,
+) -> String {
+    let ids: HashSet<Uuid> = tasks.iter().map(|task| task.id).collect();
+    let mut subtasks: HashMap<Uuid, Vec<&Task>> = HashMap::new();
+    let mut top_level = Vec::new();
+    for task in tasks {
+        match task.parent_id.filter(|parent_id| ids.contains(parent_id)) {
+            Some(parent_id) => subtasks.entry(parent_id).or_default().push(task),
+            None => top_level.push(task),
+        }
+    }
+
+    // Sections in order of appearance, except days which are chronological,
+    // and the tasks without a list or deadline last
+    let mut sections: Vec<(Option<String>, Vec<&Task>)> = Vec::new();
+    for task in top_level {
+        let key = match grouping {
+            Grouping::List => task
+                .list_id
+                .map(|list_id| list_names.get(&list_id).cloned().unwrap_or_default()),
+            Grouping::Day => task
+                .deadline
+                .map(|deadline| deadline.with_timezone(tz).format(DATE_FORMAT).to_string()),
+        };
+        match sections.iter_mut().find(|(section, _)| *section == key) {
+            Some((_, section_tasks)) => section_tasks.push(task),
+            None => sections.push((key, vec![task])),
+        }
+    }
+    if grouping == Grouping::Day {
+        sections.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+    sections.sort_by_key(|(key, _)| key.is_none());
+
+    let mut out = String::new();
+    for (key, section_tasks) in sections {
+        let heading = key.unwrap_or_else(|| match grouping {
+            Grouping::List => NO_LIST.to_string(),
+            Grouping::Day => NO_DEADLINE.to_string(),
+        });
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("## {}\n\n", heading));
+        for task in section_tasks {
+            write_item(&mut out, task, 0, &subtasks, tz);
+        }
+    }
+    out
+}
+
+/// Width of the indentation of a line, tabs counting as four spaces.
+fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// The completion state and text of a checklist item, e.g. `- [x] Done`.
+fn parse_item(line: &str) -> Option<(bool, &str)> {
+    let item = line
+        .trim_start()
+        .strip_prefix(['-', '*', '+'])?
+        .strip_prefix(' ')?
+        .trim_start();
+    let (completed, text) = if let Some(text) = item.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = item
+        .strip_prefix("[x]")
+        .or_else(|| item.strip_prefix("[X]"))
+    {
+        (true, text)
+    } else {
+        return None;
+    };
+    Some((completed, text.trim()))
+}
+
+/// Splits a trailing `(due 2024-03-05)` or `(due 2024-03-05 14:30)` off the
+/// text of an item.
+fn split_due(text: &str) -> (&str, Option<&str>) {
+    match text
+        .strip_suffix(')')
+        .and_then(|text| text.rsplit_once("(due "))
+    {
+        Some((title, due)) => (title.trim_end(), Some(due.trim())),
+        None => (text, None),
+    }
+}
+
+fn parse_due(due: &str, tz: &Tz) -> Result<DateTime<Utc>, String> {
+    NaiveDateTime::parse_from_str(due, DATE_TIME_FORMAT)
+        .or_else(|_| NaiveDate::parse_from_str(due, DATE_FORMAT).map(|date| date.and_hms(0, 0, 0)))
+        .map(|local| to_utc(tz, local))
+        .map_err(|_| format!("Invalid date `{}`", due))
+}
+
+/// Reads the checklist items of a Markdown document, ignoring other text.
+///
+/// Headings naming one of `projects` put the following items in that list,
+/// other headings take them out of it again, except for dates which give
+/// them a deadline on that day in `tz`.
+/// Items indented below another one become its subtasks.
+pub fn parse(
+    text: &str,
+    projects: &HashMap<String, Uuid>,
+    tz: &Tz,
+) -> (Vec<ImportedRow>, Vec<RowError>) {
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    let mut list_id = None;
+    let mut day = None;
+    // Indentation of the enclosing items and the task their subtasks belong to
+    let mut parents: Vec<(usize, Option<Uuid>)> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let row = index as u64 + 1;
+
+        let trimmed = line.trim_start();
+        let heading = trimmed.trim_start_matches('#');
+        if heading.len() < trimmed.len() && (heading.is_empty() || heading.starts_with(' ')) {
+            let heading = heading.trim();
+            if let Ok(date) = NaiveDate::parse_from_str(heading, DATE_FORMAT) {
+                day = Some(date);
+            } else if heading.eq_ignore_ascii_case(NO_DEADLINE) {
+                day = None;
+            } else {
+                // e.g. `No list`, or a list the caller cannot add tasks to
+                list_id = projects.get(&project(heading)).copied();
+            }
+            parents.clear();
+            continue;
+        }
+
+        let (completed, text) = match parse_item(line) {
+            Some(item) => item,
+            None => continue,
+        };
+        let indent = indentation(line);
+        while parents.last().is_some_and(|(depth, _)| *depth >= indent) {
+            parents.pop();
+        }
+        let parent_id = parents.last().and_then(|(_, parent_id)| *parent_id);
+
+        let (title, due) = split_due(text);
+        let deadline = match due {
+            Some(due) => parse_due(due, tz).map(Some),
+            None => Ok(day.map(|day| to_utc(tz, day.and_hms(0, 0, 0)))),
+        };
+        let result = match (title.is_empty(), deadline) {
+            (true, _) => Err(RowError::new(
+                row,
+                Some("title"),
+                "Missing title".to_string(),
+            )),
+            (false, Err(err)) => Err(RowError::new(row, Some("due"), err)),
+            (false, Ok(deadline)) => {
+                let mut task = Task::new(title.to_string(), completed, deadline);
+                task.list_id = list_id;
+                task.parent_id = parent_id;
+                Ok(task)
+            }
+        };
+        match result {
+            Ok(task) => {
+                parents.push((indent, Some(task.id)));
+                rows.push(ImportedRow { row, task });
+            }
+            // Subtasks of an item that was not imported move up a level
+            Err(error) => {
+                parents.push((indent, parent_id));
+                errors.push(error);
+            }
+        }
+    }
+
+    (rows, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(date_time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(date_time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn task(title: &str, deadline: Option<&str>) -> Task {
+        Task::new(title.to_string(), false, deadline.map(utc))
+    }
+
+    #[test]
+    fn groups_by_list_and_nests_subtasks() {
+        let list_id = Uuid::new_v4();
+        let list_names = HashMap::from([(list_id, "Family Budget".to_string())]);
+
+        let mut rent = task("Pay   rent", Some("2024-05-31T07:30:00Z"));
+        rent.list_id = Some(list_id);
+        let mut receipt = task("Keep receipt", None);
+        receipt.list_id = Some(list_id);
+        receipt.parent_id = Some(rent.id);
+        receipt.completed = true;
+        let mut orphan = task("Orphan", None);
+        orphan.parent_id = Some(Uuid::new_v4());
+
+        let out = checklist(
+            &[orphan, receipt, rent],
+            Grouping::List,
+            &list_names,
+            &"Europe/Berlin".parse().unwrap(),
+        );
+        assert_eq!(
+            out,
+            "## Family Budget\n\n\
+             - [ ] Pay rent (due 2024-05-31 09:30)\n  - [x] Keep receipt\n\n\
+             ## No list\n\n\
+             - [ ] Orphan\n"
+        );
+    }
+
+    #[test]
+    fn groups_by_day_in_order() {
+        let tasks = [
+            task("Later", Some("2024-05-07T22:30:00Z")),
+            task("Someday", None),
+            task("Sooner", Some("2024-05-06T00:00:00Z")),
+        ];
+
+        let out = checklist(&tasks, Grouping::Day, &HashMap::new(), &Tz::UTC);
+        assert_eq!(
+            out,
+            "## 2024-05-06\n\n- [ ] Sooner (due 2024-05-06)\n\n\
+             ## 2024-05-07\n\n- [ ] Later (due 2024-05-07 22:30)\n\n\
+             ## No deadline\n\n- [ ] Someday\n"
+        );
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let list_id = Uuid::new_v4();
+        let list_names = HashMap::from([(list_id, "Family Budget".to_string())]);
+        let projects = HashMap::from([(project("Family Budget"), list_id)]);
+        let tz: Tz = "America/New_York".parse().unwrap();
+
+        let mut rent = task("Pay rent", Some("2024-03-10T07:30:00Z"));
+        rent.list_id = Some(list_id);
+        let mut receipt = task("Keep receipt", Some("2024-03-10T05:00:00Z"));
+        receipt.list_id = Some(list_id);
+        receipt.parent_id = Some(rent.id);
+        receipt.completed = true;
+        let tasks = [rent, receipt, task("Call mom", None)];
+
+        let (rows, errors) = parse(
+            &checklist(&tasks, Grouping::List, &list_names, &tz),
+            &projects,
+            &tz,
+        );
+
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(rows.len(), 3);
+        for (imported, task) in rows.iter().map(|row| &row.task).zip(&tasks) {
+            assert_eq!(imported.title, task.title);
+            assert_eq!(imported.completed, task.completed);
+            assert_eq!(imported.deadline, task.deadline);
+            assert_eq!(imported.list_id, task.list_id);
+        }
+        assert_eq!(rows[1].task.parent_id, Some(rows[0].task.id));
+        assert_eq!(rows[2].task.parent_id, None);
+    }
+
+    #[test]
+    fn reads_items_under_day_headings_and_ignores_other_text() {
+        let text = "# Plan\n\
+                    Some notes, and a [link](https://example.com).\n\
+                    ## 2024-05-06\n\
+                    * [X] Shop\n\
+                    \t+ [ ] Milk (due 2024-05-07)\n\
+                    - [] Not an item\n\
+                    ## No deadline\n\
+                    - [ ] Someday\n";
+        let (rows, errors) = parse(text, &HashMap::new(), &Tz::UTC);
+
+        assert!(errors.is_empty(), "{:?}", errors);
+        let read: Vec<_> = rows
+            .iter()
+            .map(|row| (row.row, row.task.title.as_str(), row.task.deadline))
+            .collect();
+        assert_eq!(
+            read,
+            [
+                (4, "Shop", Some(utc("2024-05-06T00:00:00Z"))),
+                (5, "Milk", Some(utc("2024-05-07T00:00:00Z"))),
+                (8, "Someday", None),
+            ]
+        );
+        assert!(rows[0].task.completed);
+        assert_eq!(rows[1].task.parent_id, Some(rows[0].task.id));
+    }
+
+    #[test]
+    fn reports_malformed_items_and_moves_their_subtasks_up() {
+        let text = "- [ ] Parent\n\
+                    \x20 - [ ] Broken (due tomorrow)\n\
+                    \x20   - [ ] Child\n\
+                    - [ ] \n";
+        let (rows, errors) = parse(text, &HashMap::new(), &Tz::UTC);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].task.title, "Child");
+        assert_eq!(rows[1].task.parent_id, Some(rows[0].task.id));
+
+        let fields: Vec<_> = errors
+            .iter()
+            .map(|error| (error.row, error.field.as_deref()))
+            .collect();
+        assert_eq!(fields, [(2, Some("due")), (4, Some("title"))]);
+        assert!("week".parse::<Grouping>().is_err());
+    }
+}
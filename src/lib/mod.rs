@@ -1,5 +1,15 @@
+pub mod attachments;
+pub mod auth;
+pub mod csv;
 pub mod env;
 pub mod errors;
+pub mod icalendar;
+pub mod markdown;
+pub mod notes;
+pub mod position;
 pub mod query;
+pub mod quick;
+pub mod recurrence;
 pub mod time;
+pub mod todotxt;
 pub mod uuid;
@@ -0,0 +1,175 @@
+use std::str::FromStr;
+
+use ammonia::Builder;
+use pulldown_cmark::{html, Options, Parser};
+
+/// Representation of the notes added to tasks next to their Markdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rendering {
+    Html,
+}
+
+impl FromStr for Rendering {
+    type Err = String;
+
+    fn from_str(rendering: &str) -> Result<Self, Self::Err> {
+        match rendering.to_lowercase().as_str() {
+            "html" => Ok(Rendering::Html),
+            _ => Err(format!(
+                "Invalid render: {}. Valid renderings are: html",
+                rendering
+            )),
+        }
+    }
+}
+
+/// Parses the `render` query parameter, if given.
+pub fn parse_rendering(render: &Option<String>) -> Result<Option<Rendering>, String> {
+    render.as_deref().map(str::parse).transpose()
+}
+
+/// Largest notes a task may have, in bytes.
+pub fn max_bytes() -> usize {
+    std::env::var("NOTES_MAX_BYTES")
+        .ok()
+        .and_then(|size| size.parse::<usize>().ok())
+        .unwrap_or(64 * 1024)
+}
+
+pub fn validate(notes: Option<&str>) -> Result<(), String> {
+    let max_bytes = max_bytes();
+    match notes {
+        Some(notes) if notes.len() > max_bytes => {
+            Err(format!("Notes are limited to {} bytes", max_bytes))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether `class` names the language of a code block, e.g. `language-c++`.
+fn is_language_class(class: &str) -> bool {
+    class.strip_prefix("language-").is_some_and(|language| {
+        !language.is_empty()
+            && language
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_+#.".contains(c))
+    })
+}
+
+/// Renders Markdown notes, with tables, strikethrough and task lists, as
+/// HTML that is safe to embed in a page.
+///
+/// Raw HTML is cleaned like the rest: anything that could run scripts or
+/// style the page is stripped, links are kept to `http`, `https` and
+/// `mailto` and opened without a referrer.
+/// Task list items keep their checkboxes, which cannot be ticked.
+pub fn render_html(notes: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, Parser::new_ext(notes, options));
+
+    Builder::default()
+        .add_tags(&["input"])
+        .add_tag_attributes("input", &["checked"])
+        .set_tag_attribute_value("input", "type", "checkbox")
+        .set_tag_attribute_value("input", "disabled", "")
+        // e.g. `language-rust` of fenced code blocks, for syntax highlighting
+        .add_tag_attributes("code", &["class"])
+        .attribute_filter(|element, attribute, value| match (element, attribute) {
+            ("code", "class") if !is_language_class(value) => None,
+            _ => Some(value.into()),
+        })
+        .url_schemes(["http", "https", "mailto"].into_iter().collect())
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_markdown() {
+        let html = render_html(
+            "# Plan\n\n\
+             See [the docs](https://example.com/docs) and ~~not this~~.\n\n\
+             ```rust\nfn main() {}\n```\n\n\
+             | a | b |\n|---|---|\n| 1 | 2 |\n\n\
+             - [x] Done\n- [ ] Open\n",
+        );
+
+        assert!(html.contains("<h1>Plan</h1>"), "{}", html);
+        assert!(
+            html.contains(
+                "<a href=\"https://example.com/docs\" rel=\"noopener noreferrer nofollow\">the docs</a>"
+            ),
+            "{}",
+            html
+        );
+        assert!(html.contains("<del>not this</del>"), "{}", html);
+        assert!(
+            html.contains("<code class=\"language-rust\">fn main() {}"),
+            "{}",
+            html
+        );
+        assert!(html.contains("<td>2</td>"), "{}", html);
+        assert_eq!(html.matches("<input").count(), 2, "{}", html);
+        assert_eq!(html.matches("disabled=\"\"").count(), 2, "{}", html);
+        assert_eq!(html.matches("checked").count(), 1, "{}", html);
+    }
+
+    #[test]
+    fn strips_anything_unsafe() {
+        let html = render_html(
+            "<script>alert(1)</script>\n\n\
+             <img src=x onerror=\"alert(2)\"> <b style=\"color: red\" onclick=\"alert(3)\">bold</b>\n\n\
+             [click](javascript:alert(4)) <a href=\"data:text/html,alert(5)\">data</a>\n\n\
+             <input type=\"text\" value=\"secret\" name=\"password\">\n\n\
+             <iframe src=\"https://example.com\"></iframe><style>body {}</style>\n\n\
+             ```\"><script>alert(6)</script>\nx\n```\n\n\
+             <code class=\"evil\">code</code>",
+        );
+
+        for unsafe_html in [
+            "<script",
+            "alert(1)",
+            "onerror",
+            "onclick",
+            "style",
+            "javascript:",
+            "data:",
+            "<iframe",
+            "value=",
+            "name=",
+            "type=\"text\"",
+            "class=\"evil\"",
+        ] {
+            assert!(!html.contains(unsafe_html), "{} in {}", unsafe_html, html);
+        }
+        assert!(html.contains("<b>bold</b>"), "{}", html);
+        assert!(
+            html.contains("<input type=\"checkbox\" disabled=\"\">"),
+            "{}",
+            html
+        );
+    }
+
+    #[test]
+    fn checks_renderings_and_sizes() {
+        assert_eq!(
+            parse_rendering(&Some("HTML".to_string())),
+            Ok(Some(Rendering::Html))
+        );
+        assert_eq!(parse_rendering(&None), Ok(None));
+        assert!(parse_rendering(&Some("pdf".to_string())).is_err());
+
+        assert!(validate(None).is_ok());
+        assert!(validate(Some(&"x".repeat(max_bytes()))).is_ok());
+        assert!(validate(Some(&"x".repeat(max_bytes() + 1))).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+//! Fractional indexing of the manual order of tasks.
+//!
+//! Positions are strings that sort byte by byte, an integer part whose first
+//! character encodes its length followed by a fraction, so that a position
+//! can always be found between two others and moving a task rewrites only
+//! that task. Appending uses the integer part and grows it logarithmically,
+//! inserting between neighbours lengthens the fraction.
+
+/// Digits of the positions, in ascending byte order.
+const DIGITS: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Positions longer than this are spread out again.
+pub const MAX_LENGTH: usize = 32;
+
+/// The smallest integer part, below which nothing can be appended.
+const SMALLEST_INTEGER: &str = "A00000000000000000000000000";
+
+fn digit(byte: u8) -> Result<usize, String> {
+    DIGITS
+        .iter()
+        .position(|digit| *digit == byte)
+        .ok_or_else(|| format!("Invalid digit `{}` in position", byte as char))
+}
+
+/// Number of characters of the integer part, from its first character.
+fn integer_length(head: u8) -> Option<usize> {
+    match head {
+        b'a'..=b'z' => Some((head - b'a') as usize + 2),
+        b'A'..=b'Z' => Some((b'Z' - head) as usize + 2),
+        _ => None,
+    }
+}
+
+/// Splits a position into its integer part and fraction.
+fn split(position: &str) -> Result<(&str, &str), String> {
+    let invalid = || format!("Invalid position `{}`", position);
+
+    let head = *position.as_bytes().first().ok_or_else(invalid)?;
+    let length = integer_length(head).ok_or_else(invalid)?;
+    if position.len() < length || position == SMALLEST_INTEGER {
+        return Err(invalid());
+    }
+    for byte in position.bytes().skip(1) {
+        digit(byte)?;
+    }
+
+    let (integer, fraction) = position.split_at(length);
+    if fraction.ends_with('0') {
+        return Err(invalid());
+    }
+    Ok((integer, fraction))
+}
+
+/// A fraction between `a` and `b`, `None` standing for 1.
+fn midpoint(a: &str, b: Option<&str>) -> Result<String, String> {
+    if let Some(b) = b {
+        // Keep the common prefix, reading missing digits of `a` as zeros
+        let common = b
+            .bytes()
+            .enumerate()
+            .take_while(|(index, byte)| a.as_bytes().get(*index).copied().unwrap_or(b'0') == *byte)
+            .count();
+        if common == b.len() {
+            return Err(format!("`{}` does not come before `{}`", a, b));
+        }
+        if common > 0 {
+            let rest = midpoint(a.get(common..).unwrap_or(""), Some(&b[common..]))?;
+            return Ok(format!("{}{}", &b[..common], rest));
+        }
+    }
+
+    let digit_a = match a.as_bytes().first() {
+        Some(byte) => digit(*byte)?,
+        None => 0,
+    };
+    let digit_b = match b {
+        Some(b) => digit(b.as_bytes()[0])?,
+        None => DIGITS.len(),
+    };
+    if digit_b > digit_a + 1 {
+        return Ok((DIGITS[(digit_a + digit_b).div_ceil(2)] as char).to_string());
+    }
+    match b {
+        Some(b) if b.len() > 1 => Ok(b[..1].to_string()),
+        _ => Ok(format!(
+            "{}{}",
+            DIGITS[digit_a] as char,
+            midpoint(a.get(1..).unwrap_or(""), None)?
+        )),
+    }
+}
+
+fn increment_integer(integer: &str) -> Result<Option<String>, String> {
+    let head = integer.as_bytes()[0];
+    let mut digits = integer.as_bytes()[1..].to_vec();
+
+    let mut carry = true;
+    for byte in digits.iter_mut().rev() {
+        let next = digit(*byte)? + 1;
+        if next == DIGITS.len() {
+            *byte = DIGITS[0];
+        } else {
+            *byte = DIGITS[next];
+            carry = false;
+            break;
+        }
+    }
+
+    let head = if carry {
+        match head {
+            b'Z' => return Ok(Some("a0".to_string())),
+            b'z' => return Ok(None),
+            head => {
+                let head = head + 1;
+                if head > b'a' {
+                    digits.push(DIGITS[0]);
+                } else {
+                    digits.pop();
+                }
+                head
+            }
+        }
+    } else {
+        head
+    };
+    digits.insert(0, head);
+    Ok(String::from_utf8(digits).ok())
+}
+
+fn decrement_integer(integer: &str) -> Result<Option<String>, String> {
+    let head = integer.as_bytes()[0];
+    let mut digits = integer.as_bytes()[1..].to_vec();
+
+    let mut borrow = true;
+    for byte in digits.iter_mut().rev() {
+        match digit(*byte)? {
+            0 => *byte = DIGITS[DIGITS.len() - 1],
+            value => {
+                *byte = DIGITS[value - 1];
+                borrow = false;
+                break;
+            }
+        }
+    }
+
+    let head = if borrow {
+        match head {
+            b'a' => return Ok(Some("Zz".to_string())),
+            b'A' => return Ok(None),
+            head => {
+                let head = head - 1;
+                if head < b'Z' {
+                    digits.push(DIGITS[DIGITS.len() - 1]);
+                } else {
+                    digits.pop();
+                }
+                head
+            }
+        }
+    } else {
+        head
+    };
+    digits.insert(0, head);
+    Ok(String::from_utf8(digits).ok())
+}
+
+/// A position after `a` and before `b`, either of which may be missing at
+/// the ends of a list.
+pub fn between(a: Option<&str>, b: Option<&str>) -> Result<String, String> {
+    let exhausted = || "No position is left in that direction".to_string();
+
+    match (a, b) {
+        (None, None) => Ok("a0".to_string()),
+        (None, Some(b)) => {
+            let (integer, fraction) = split(b)?;
+            if integer == SMALLEST_INTEGER {
+                Ok(format!("{}{}", integer, midpoint("", Some(fraction))?))
+            } else if !fraction.is_empty() {
+                Ok(integer.to_string())
+            } else {
+                match decrement_integer(integer)? {
+                    // Not a position on its own, only with a fraction
+                    Some(previous) if previous == SMALLEST_INTEGER => {
+                        Ok(format!("{}{}", previous, midpoint("", None)?))
+                    }
+                    previous => previous.ok_or_else(exhausted),
+                }
+            }
+        }
+        (Some(a), None) => {
+            let (integer, fraction) = split(a)?;
+            match increment_integer(integer)? {
+                Some(next) => Ok(next),
+                None => Ok(format!("{}{}", integer, midpoint(fraction, None)?)),
+            }
+        }
+        (Some(a), Some(b)) => {
+            if a >= b {
+                return Err(format!("`{}` does not come before `{}`", a, b));
+            }
+            let (integer_a, fraction_a) = split(a)?;
+            let (integer_b, fraction_b) = split(b)?;
+            if integer_a == integer_b {
+                return Ok(format!(
+                    "{}{}",
+                    integer_a,
+                    midpoint(fraction_a, Some(fraction_b))?
+                ));
+            }
+            match increment_integer(integer_a)? {
+                Some(next) if next.as_str() < b => Ok(next),
+                Some(_) => Ok(format!("{}{}", integer_a, midpoint(fraction_a, None)?)),
+                None => Err(exhausted()),
+            }
+        }
+    }
+}
+
+/// `count` ascending positions as short as possible, for spreading out a
+/// list whose positions grew too long.
+pub fn spread(count: usize) -> Result<Vec<String>, String> {
+    let mut positions: Vec<String> = Vec::with_capacity(count);
+    for _ in 0..count {
+        let next = between(positions.last().map(String::as_str), None)?;
+        positions.push(next);
+    }
+    Ok(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_between(a: Option<&str>, b: Option<&str>) -> String {
+        let position = between(a, b).unwrap();
+        assert!(split(&position).is_ok(), "{} is invalid", position);
+        if let Some(a) = a {
+            assert!(a < position.as_str(), "{} is not after {}", position, a);
+        }
+        if let Some(b) = b {
+            assert!(position.as_str() < b, "{} is not before {}", position, b);
+        }
+        position
+    }
+
+    #[test]
+    fn starts_in_the_middle() {
+        assert_eq!(between(None, None).unwrap(), "a0");
+    }
+
+    #[test]
+    fn appends_and_prepends() {
+        assert_eq!(assert_between(Some("a0"), None), "a1");
+        assert_eq!(assert_between(Some("az"), None), "b00");
+        assert_eq!(assert_between(None, Some("a0")), "Zz");
+        assert_eq!(assert_between(None, Some("a0V")), "a0");
+    }
+
+    #[test]
+    fn inserts_between_neighbours() {
+        assert_between(Some("a0"), Some("a2"));
+        assert_between(Some("a0"), Some("b00"));
+        assert_between(Some("a0V"), Some("a1"));
+    }
+
+    #[test]
+    fn inserts_between_positions_one_digit_apart() {
+        assert_eq!(assert_between(Some("a0"), Some("a1")), "a0V");
+        assert_eq!(assert_between(Some("a0V"), Some("a0W")), "a0VV");
+        assert_eq!(assert_between(Some("a0Vz"), Some("a0W")), "a0VzV");
+    }
+
+    #[test]
+    fn keeps_inserting_at_the_same_place() {
+        let mut a = "a0".to_string();
+        let b = "a1".to_string();
+        for _ in 0..100 {
+            a = assert_between(Some(&a), Some(&b));
+        }
+    }
+
+    #[test]
+    fn stays_above_the_smallest_integer() {
+        let smallest_plus_one = format!("{}1", &SMALLEST_INTEGER[..SMALLEST_INTEGER.len() - 1]);
+        assert_between(None, Some(&smallest_plus_one));
+
+        let first = format!("{}V", SMALLEST_INTEGER);
+        let position = assert_between(None, Some(&first));
+        assert!(position.starts_with(SMALLEST_INTEGER));
+        assert_between(None, Some(&position));
+
+        assert!(split(SMALLEST_INTEGER).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_positions() {
+        for position in ["", "a", "0a", "a0!", "a0V0"] {
+            assert!(between(Some(position), None).is_err(), "{}", position);
+        }
+        assert!(between(Some("a1"), Some("a0")).is_err());
+        assert!(between(Some("a1"), Some("a1")).is_err());
+    }
+
+    #[test]
+    fn spreads_ascending_positions() {
+        assert!(spread(0).unwrap().is_empty());
+
+        let positions = spread(5000).unwrap();
+        assert_eq!(positions.len(), 5000);
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(positions.iter().all(|position| position.len() <= 4));
+        assert!(positions.iter().all(|position| split(position).is_ok()));
+    }
+}
@@ -1,4 +1,13 @@
 use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::lib::recurrence::to_utc;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use entity::sea_orm_active_enums::Priority;
+use entity::task::Model as Task;
+use uuid::Uuid;
 
 pub enum SortOrder {
     Asc,
@@ -11,6 +20,206 @@ pub enum FilterOps {
     Lte,
 }
 
+/// Restricts which lists' tasks a query may touch.
+/// Tasks that do not belong to a list are part of `Accessible`.
+pub enum ListFilter {
+    All,
+    Accessible(Vec<Uuid>),
+    Only(Uuid),
+}
+
+/// Shortcuts of `due=`, relative to the current day in the caller's timezone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueWindow {
+    Overdue,
+    Today,
+    Tomorrow,
+    ThisWeek,
+    NoDeadline,
+}
+
+impl FromStr for DueWindow {
+    type Err = String;
+
+    fn from_str(window: &str) -> Result<Self, Self::Err> {
+        match window.to_lowercase().as_str() {
+            "overdue" => Ok(DueWindow::Overdue),
+            "today" => Ok(DueWindow::Today),
+            "tomorrow" => Ok(DueWindow::Tomorrow),
+            "this_week" => Ok(DueWindow::ThisWeek),
+            "no_deadline" => Ok(DueWindow::NoDeadline),
+            _ => Err(format!(
+                "Invalid due: {}. Valid values are: overdue, today, tomorrow, this_week, no_deadline",
+                window
+            )),
+        }
+    }
+}
+
+/// A [`DueWindow`] compiled to instants, for `TaskDao` to query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueFilter {
+    /// Not completed and due before the given instant
+    Before(DateTime<Utc>),
+    /// Due at or after the start and before the end
+    Between(DateTime<Utc>, DateTime<Utc>),
+    NoDeadline,
+}
+
+impl DueWindow {
+    /// Resolves the window at `now`, days starting at midnight and weeks on
+    /// Monday in `tz`.
+    pub fn compile(self, now: DateTime<Utc>, tz: &Tz) -> DueFilter {
+        let today = now.with_timezone(tz).date_naive();
+        let midnight = |date: NaiveDate| to_utc(tz, date.and_hms(0, 0, 0));
+        match self {
+            DueWindow::Overdue => DueFilter::Before(now),
+            DueWindow::Today => {
+                DueFilter::Between(midnight(today), midnight(today + Duration::days(1)))
+            }
+            DueWindow::Tomorrow => DueFilter::Between(
+                midnight(today + Duration::days(1)),
+                midnight(today + Duration::days(2)),
+            ),
+            DueWindow::ThisWeek => {
+                let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+                DueFilter::Between(midnight(monday), midnight(monday + Duration::days(7)))
+            }
+            DueWindow::NoDeadline => DueFilter::NoDeadline,
+        }
+    }
+}
+
+impl DueFilter {
+    pub fn matches(&self, task: &Task) -> bool {
+        match (self, task.deadline) {
+            (DueFilter::Before(now), Some(deadline)) => !task.completed && deadline < *now,
+            (DueFilter::Between(start, end), Some(deadline)) => {
+                *start <= deadline && deadline < *end
+            }
+            (DueFilter::NoDeadline, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Width of the periods of `GET /stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bucket {
+    Day,
+    Week,
+}
+
+impl FromStr for Bucket {
+    type Err = String;
+
+    fn from_str(bucket: &str) -> Result<Self, Self::Err> {
+        match bucket.to_lowercase().as_str() {
+            "day" => Ok(Bucket::Day),
+            "week" => Ok(Bucket::Week),
+            _ => Err(format!(
+                "Invalid bucket: {}. Valid buckets are: day, week",
+                bucket
+            )),
+        }
+    }
+}
+
+impl Bucket {
+    /// Field of `date_trunc` truncating to this bucket.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Bucket::Day => "day",
+            Bucket::Week => "week",
+        }
+    }
+
+    /// Starts of the buckets overlapping `[from, to)`, the first one being
+    /// the bucket `from` falls into. Weeks start on Monday, like those of
+    /// `date_trunc`.
+    pub fn starts(&self, from: DateTime<Utc>, to: DateTime<Utc>, tz: &Tz) -> Vec<DateTime<Utc>> {
+        let mut date = from.with_timezone(tz).date_naive();
+        let step = match self {
+            Bucket::Day => Duration::days(1),
+            Bucket::Week => {
+                date -= Duration::days(date.weekday().num_days_from_monday() as i64);
+                Duration::days(7)
+            }
+        };
+
+        let mut starts = Vec::new();
+        loop {
+            let start = to_utc(tz, date.and_hms(0, 0, 0));
+            if start >= to {
+                return starts;
+            }
+            starts.push(start);
+            date += step;
+        }
+    }
+}
+
+/// Narrows down the tasks returned by `TaskDao` queries.
+pub struct TaskFilter {
+    pub lists: ListFilter,
+    /// Empty matches every priority.
+    pub priorities: Vec<Priority>,
+    pub due: Option<DueFilter>,
+}
+
+impl TaskFilter {
+    pub fn new(lists: ListFilter) -> Self {
+        TaskFilter {
+            lists,
+            priorities: Vec::new(),
+            due: None,
+        }
+    }
+
+    /// In-memory counterpart of `dao::utils::construct_task_filter`.
+    pub fn matches(&self, task: &Task) -> bool {
+        self.lists.matches(task.list_id)
+            && (self.priorities.is_empty() || self.priorities.contains(&task.priority))
+            && self.due.is_none_or(|due| due.matches(task))
+    }
+}
+
+impl ListFilter {
+    pub fn matches(&self, list_id: Option<Uuid>) -> bool {
+        match (self, list_id) {
+            (ListFilter::All, _) => true,
+            (ListFilter::Accessible(_), None) => true,
+            (ListFilter::Accessible(ids), Some(list_id)) => ids.contains(&list_id),
+            (ListFilter::Only(id), list_id) => list_id == Some(*id),
+        }
+    }
+}
+
+/// Inclusive bounds on `created_at`, `deadline` or `completed_at`, as given
+/// by `before`, `after`, `start` and `end`.
+pub struct DateRange {
+    pub attribute: String,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl DateRange {
+    pub fn contains(&self, task: &Task) -> bool {
+        let date = match self.attribute.as_str() {
+            "created_at" => Some(task.created_at),
+            "completed_at" => task.completed_at,
+            _ => task.deadline,
+        };
+        match date {
+            Some(date) => {
+                self.start.is_none_or(|start| start <= date)
+                    && self.end.is_none_or(|end| date <= end)
+            }
+            None => false,
+        }
+    }
+}
+
 impl SortOrder {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -40,3 +249,154 @@ impl Display for FilterOps {
         write!(f, "{}", self.as_str())
     }
 }
+
+impl Display for ListFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListFilter::All => write!(f, "ListFilter::All"),
+            ListFilter::Accessible(ids) => write!(f, "ListFilter::Accessible({} lists)", ids.len()),
+            ListFilter::Only(id) => write!(f, "ListFilter::Only({})", id),
+        }
+    }
+}
+
+impl Display for TaskFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "TaskFilter {{ lists: {}, priorities: {:?}, due: {:?} }}",
+            self.lists, self.priorities, self.due
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(date_time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(date_time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn between(start: &str, end: &str) -> DueFilter {
+        DueFilter::Between(utc(start), utc(end))
+    }
+
+    #[test]
+    fn compiles_windows_in_utc() {
+        let now = utc("2024-05-08T23:30:00Z");
+        let windows: Vec<_> = ["overdue", "today", "tomorrow", "this_week", "no_deadline"]
+            .into_iter()
+            .map(|window| window.parse::<DueWindow>().unwrap().compile(now, &Tz::UTC))
+            .collect();
+        assert_eq!(
+            windows,
+            [
+                DueFilter::Before(now),
+                between("2024-05-08T00:00:00Z", "2024-05-09T00:00:00Z"),
+                between("2024-05-09T00:00:00Z", "2024-05-10T00:00:00Z"),
+                between("2024-05-06T00:00:00Z", "2024-05-13T00:00:00Z"),
+                DueFilter::NoDeadline,
+            ]
+        );
+        assert!("next_week".parse::<DueWindow>().is_err());
+    }
+
+    #[test]
+    fn takes_the_day_and_week_of_the_callers_timezone() {
+        // Still Sunday in New York, already Monday in Tokyo
+        let now = utc("2024-05-06T02:00:00Z");
+
+        let new_york = "America/New_York".parse().unwrap();
+        assert_eq!(
+            DueWindow::Today.compile(now, &new_york),
+            between("2024-05-05T04:00:00Z", "2024-05-06T04:00:00Z")
+        );
+        assert_eq!(
+            DueWindow::ThisWeek.compile(now, &new_york),
+            between("2024-04-29T04:00:00Z", "2024-05-06T04:00:00Z")
+        );
+
+        let tokyo = "Asia/Tokyo".parse().unwrap();
+        assert_eq!(
+            DueWindow::Today.compile(now, &tokyo),
+            between("2024-05-05T15:00:00Z", "2024-05-06T15:00:00Z")
+        );
+        assert_eq!(
+            DueWindow::ThisWeek.compile(now, &tokyo),
+            between("2024-05-05T15:00:00Z", "2024-05-12T15:00:00Z")
+        );
+    }
+
+    #[test]
+    fn spans_the_local_day_across_dst_changes() {
+        // Berlin springs forward, the day lasting 23 hours
+        let berlin = "Europe/Berlin".parse().unwrap();
+        let now = utc("2024-03-31T10:00:00Z");
+        assert_eq!(
+            DueWindow::Today.compile(now, &berlin),
+            between("2024-03-30T23:00:00Z", "2024-03-31T22:00:00Z")
+        );
+        assert_eq!(
+            DueWindow::Tomorrow.compile(now, &berlin),
+            between("2024-03-31T22:00:00Z", "2024-04-01T22:00:00Z")
+        );
+        assert_eq!(
+            DueWindow::ThisWeek.compile(now, &berlin),
+            between("2024-03-24T23:00:00Z", "2024-03-31T22:00:00Z")
+        );
+
+        // New York falls back, the day lasting 25 hours
+        let new_york = "America/New_York".parse().unwrap();
+        let now = utc("2024-11-03T12:00:00Z");
+        assert_eq!(
+            DueWindow::Today.compile(now, &new_york),
+            between("2024-11-03T04:00:00Z", "2024-11-04T05:00:00Z")
+        );
+        assert_eq!(
+            DueWindow::Tomorrow.compile(now, &new_york),
+            between("2024-11-04T05:00:00Z", "2024-11-05T05:00:00Z")
+        );
+
+        // Santiago skips midnight, the day starting at 01:00
+        let santiago = "America/Santiago".parse().unwrap();
+        assert_eq!(
+            DueWindow::Today.compile(utc("2024-09-08T15:00:00Z"), &santiago),
+            between("2024-09-08T04:00:00Z", "2024-09-09T03:00:00Z")
+        );
+        assert_eq!(
+            DueWindow::Tomorrow.compile(utc("2024-09-07T15:00:00Z"), &santiago),
+            between("2024-09-08T04:00:00Z", "2024-09-09T03:00:00Z")
+        );
+    }
+
+    #[test]
+    fn matches_deadlines_inside_the_window_only() {
+        let mut task = Task::new("Pay rent".to_string(), false, None);
+        let today = DueWindow::Today.compile(
+            utc("2024-03-31T10:00:00Z"),
+            &"Europe/Berlin".parse().unwrap(),
+        );
+        assert!(!today.matches(&task));
+        assert!(DueFilter::NoDeadline.matches(&task));
+
+        for (deadline, matches) in [
+            ("2024-03-30T22:59:59Z", false),
+            ("2024-03-30T23:00:00Z", true),
+            ("2024-03-31T21:59:59Z", true),
+            ("2024-03-31T22:00:00Z", false),
+        ] {
+            task.deadline = Some(utc(deadline));
+            assert_eq!(today.matches(&task), matches, "{}", deadline);
+        }
+
+        let overdue = DueWindow::Overdue.compile(utc("2024-03-31T22:00:00Z"), &Tz::UTC);
+        assert!(!overdue.matches(&task));
+        task.deadline = Some(utc("2024-03-31T21:59:59Z"));
+        assert!(overdue.matches(&task));
+        task.completed = true;
+        assert!(!overdue.matches(&task));
+    }
+}
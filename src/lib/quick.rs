@@ -0,0 +1,587 @@
+use std::collections::HashMap;
+
+use crate::lib::recurrence::to_utc;
+use crate::model::quick::{QuickToken, QuickTokenKind};
+use entity::sea_orm_active_enums::Priority;
+use entity::task::Model as Task;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use sea_orm::ActiveEnum;
+use uuid::Uuid;
+
+/// Largest count, as in `in 10000 days`, so that dates stay representable.
+const MAX_COUNT: i64 = 10_000;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M";
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// A task read from a line of text, and the words it was read from.
+pub struct QuickParse {
+    pub task: Task,
+    pub tokens: Vec<QuickToken>,
+}
+
+/// A date, or an exact point in time such as `in 2 hours`.
+enum When {
+    Day(NaiveDate),
+    Instant(DateTime<Utc>),
+}
+
+struct Recurrence {
+    rrule: String,
+    by_day: Vec<Weekday>,
+}
+
+/// Lowercases a word and drops the punctuation it may be followed by.
+fn normalize(word: &str) -> String {
+    word.trim_end_matches([',', '.', ';']).to_lowercase()
+}
+
+/// Weekday of a full name, or of a three-letter one if `abbreviated`. Bare
+/// abbreviations are common words too, as in `buy sun cream`.
+fn parse_weekday(word: &str, abbreviated: bool) -> Option<Weekday> {
+    // Two letters would match words such as `we`
+    if word.len() < 3 || (word.len() == 3 && !abbreviated) {
+        return None;
+    }
+    word.parse().ok()
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// Month of a full or three-letter name, 1-based.
+fn parse_month(word: &str) -> Option<u32> {
+    if word.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|month| month.starts_with(word) && (word.len() == 3 || *month == word))
+        .map(|index| index as u32 + 1)
+}
+
+/// Day of the month, optionally with an ordinal suffix as in `5th`.
+fn parse_day(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &word[digits.len()..];
+    if !matches!(suffix, "" | "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+/// A count as in `in 3 days`, spelled out for one.
+fn parse_count(word: &str) -> Option<i64> {
+    match word {
+        "a" | "an" | "one" => Some(1),
+        word => word
+            .parse()
+            .ok()
+            .filter(|count| (1..=MAX_COUNT).contains(count)),
+    }
+}
+
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    today + Duration::days(if days == 0 { 7 } else { days as i64 })
+}
+
+/// Adds months, keeping the day unless the month is too short for it.
+fn add_months(date: NaiveDate, months: i64) -> Option<NaiveDate> {
+    let total = (date.year() as i64 * 12 + date.month0() as i64).checked_add(months)?;
+    let (year, month) = (
+        i32::try_from(total.div_euclid(12)).ok()?,
+        total.rem_euclid(12) as u32 + 1,
+    );
+    (1..=date.day())
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+}
+
+/// The next date with the given month and day, `today` included.
+fn next_date(today: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    (today.year()..=today.year() + 4)
+        .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+        .find(|date| *date >= today)
+}
+
+/// Reads a date at the start of `words`, returning it and the number of
+/// words it took. Weekdays may be abbreviated `after_preposition` such as
+/// `on` or `due`.
+fn parse_when(
+    words: &[String],
+    today: NaiveDate,
+    now: DateTime<Utc>,
+    after_preposition: bool,
+) -> Option<(usize, When)> {
+    let first = words.first()?.as_str();
+    let second = words.get(1).map(String::as_str);
+
+    match first {
+        "today" => return Some((1, When::Day(today))),
+        "tomorrow" => return Some((1, When::Day(today + Duration::days(1)))),
+        "next" => {
+            return match second? {
+                "week" => Some((2, When::Day(next_weekday(today, Weekday::Mon)))),
+                "month" => Some((2, When::Day(add_months(today.with_day(1)?, 1)?))),
+                "year" => Some((
+                    2,
+                    When::Day(NaiveDate::from_ymd_opt(today.year() + 1, 1, 1)?),
+                )),
+                word => parse_weekday(word, true)
+                    .map(|weekday| (2, When::Day(next_weekday(today, weekday)))),
+            }
+        }
+        "in" => {
+            let count = parse_count(second?)?;
+            let unit = words.get(2)?;
+            let unit = unit.strip_suffix('s').unwrap_or(unit);
+            let when = match unit {
+                "minute" | "min" => {
+                    When::Instant(now.checked_add_signed(Duration::minutes(count))?)
+                }
+                "hour" => When::Instant(now.checked_add_signed(Duration::hours(count))?),
+                "day" => When::Day(today.checked_add_signed(Duration::days(count))?),
+                "week" => When::Day(today.checked_add_signed(Duration::weeks(count))?),
+                "month" => When::Day(add_months(today, count)?),
+                "year" => When::Day(add_months(today, count.checked_mul(12)?)?),
+                _ => return None,
+            };
+            return Some((3, when));
+        }
+        _ => {}
+    }
+
+    if let Some(weekday) = parse_weekday(first, after_preposition) {
+        return Some((1, When::Day(next_weekday(today, weekday))));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(first, DATE_FORMAT) {
+        return Some((1, When::Day(date)));
+    }
+    // `march 5` or `5 march`
+    let (month, day) = match (parse_month(first), second.and_then(parse_day)) {
+        (Some(month), Some(day)) => (month, day),
+        _ => (second.and_then(parse_month)?, parse_day(first)?),
+    };
+    next_date(today, month, day).map(|date| (2, When::Day(date)))
+}
+
+/// Reads a time of day at the start of `words`. A bare hour, as in `at 5`,
+/// is only taken for a time after `at`.
+fn parse_time(words: &[String], bare_hour: bool) -> Option<(usize, NaiveTime)> {
+    let first = words.first()?.as_str();
+    match first {
+        "noon" => return Some((1, NaiveTime::from_hms(12, 0, 0))),
+        "midnight" => return Some((1, NaiveTime::from_hms(0, 0, 0))),
+        _ => {}
+    }
+
+    let (clock, mut meridiem) = match first
+        .strip_suffix("am")
+        .or_else(|| first.strip_suffix("pm"))
+    {
+        Some(clock) => (clock, Some(&first[clock.len()..])),
+        None => (first, None),
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour, Some(minute)),
+        Some(_) => return None,
+        None => (clock, None),
+    };
+    if hour.is_empty() || hour.len() > 2 || !hour.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut hour: u32 = hour.parse().ok()?;
+    let minute_given = minute.is_some();
+    let minute: u32 = match minute {
+        Some(minute) => minute.parse().ok()?,
+        None => 0,
+    };
+
+    let mut taken = 1;
+    if meridiem.is_none() {
+        if let Some(word @ ("am" | "pm")) = words.get(1).map(String::as_str) {
+            meridiem = Some(word);
+            taken = 2;
+        }
+    }
+    match meridiem {
+        Some(meridiem) => {
+            if !(1..=12).contains(&hour) {
+                return None;
+            }
+            hour = hour % 12 + if meridiem == "pm" { 12 } else { 0 };
+        }
+        None if minute_given || bare_hour => {}
+        None => return None,
+    }
+
+    NaiveTime::from_hms_opt(hour, minute, 0).map(|time| (taken, time))
+}
+
+/// Reads what follows `every`, e.g. `day`, `other week`, `3 months`,
+/// `weekday` or `mon and thu`.
+fn parse_recurrence(words: &[String]) -> Option<(usize, Recurrence)> {
+    let first = words.first()?.as_str();
+
+    let mut by_day = Vec::new();
+    let mut taken = 0;
+    while let Some(word) = words.get(taken) {
+        match parse_weekday(word, true) {
+            Some(weekday) => {
+                by_day.push(weekday);
+                taken += 1;
+            }
+            // `mon and thu`, but not `every monday and tuesday's leftovers`
+            None if word == "and"
+                && !by_day.is_empty()
+                && words
+                    .get(taken + 1)
+                    .and_then(|w| parse_weekday(w, true))
+                    .is_some() =>
+            {
+                taken += 1
+            }
+            None => break,
+        }
+    }
+    if first == "weekday" {
+        by_day = vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ];
+        taken = 1;
+    }
+    if !by_day.is_empty() {
+        by_day.sort_by_key(|weekday| weekday.num_days_from_monday());
+        by_day.dedup();
+        let days: Vec<&str> = by_day.iter().copied().map(weekday_code).collect();
+        let rrule = format!("FREQ=WEEKLY;BYDAY={}", days.join(","));
+        return Some((taken, Recurrence { rrule, by_day }));
+    }
+
+    let (interval, unit, taken) = match (first, words.get(1)) {
+        ("other", Some(unit)) => (2, unit.as_str(), 2),
+        (count, Some(unit)) if count.bytes().all(|b| b.is_ascii_digit()) => {
+            (parse_count(count)?, unit.as_str(), 2)
+        }
+        (unit, _) => (1, unit, 1),
+    };
+    let unit = unit.strip_suffix('s').unwrap_or(unit);
+    let freq = match unit {
+        "day" => "DAILY",
+        "week" => "WEEKLY",
+        "month" => "MONTHLY",
+        "year" => "YEARLY",
+        _ => return None,
+    };
+    let rrule = match interval {
+        1 => format!("FREQ={}", freq),
+        interval => format!("FREQ={};INTERVAL={}", freq, interval),
+    };
+    Some((
+        taken,
+        Recurrence {
+            rrule,
+            by_day: Vec::new(),
+        },
+    ))
+}
+
+/// Finds the list a `#tag` refers to, ignoring case.
+fn find_list(projects: &HashMap<String, Uuid>, tag: &str) -> Option<Uuid> {
+    projects.get(tag).copied().or_else(|| {
+        projects
+            .iter()
+            .find(|(project, _)| project.eq_ignore_ascii_case(tag))
+            .map(|(_, list_id)| *list_id)
+    })
+}
+
+/// Reads a task from a line such as `Pay rent tomorrow 9am #home !high
+/// every month`, relative to `now` in `tz`.
+///
+/// Dates, times, `#lists` from `projects`, `!priorities` and `every ...`
+/// recurrences are taken out of the text, the first of each kind only, and
+/// the rest becomes the title. A time without a date is the next one to
+/// come, a date without a time is its midnight.
+pub fn parse(
+    text: &str,
+    now: DateTime<Utc>,
+    tz: &Tz,
+    projects: &HashMap<String, Uuid>,
+) -> Result<QuickParse, String> {
+    let original: Vec<&str> = text.split_whitespace().collect();
+    let words: Vec<String> = original.iter().map(|word| normalize(word)).collect();
+    let today = now.with_timezone(tz).date_naive();
+
+    let mut title = Vec::new();
+    let mut tokens = Vec::new();
+    let mut when = None;
+    let mut time = None;
+    let mut list_id = None;
+    let mut priority = None;
+    let mut recurrence = None;
+
+    let mut index = 0;
+    while index < words.len() {
+        let word = words[index].as_str();
+        let rest = &words[index + 1..];
+        let preposition = matches!(word, "on" | "by" | "due" | "at") as usize;
+
+        let found = if let Some(tag) = word.strip_prefix('#').filter(|_| list_id.is_none()) {
+            find_list(projects, tag).map(|id| {
+                list_id = Some(id);
+                (1, QuickTokenKind::List, id.to_string())
+            })
+        } else if let Some(name) = word.strip_prefix('!').filter(|_| priority.is_none()) {
+            Priority::try_from_value(&name.to_string())
+                .ok()
+                .map(|value| {
+                    priority = Some(value);
+                    (1, QuickTokenKind::Priority, name.to_string())
+                })
+        } else if word == "every" && recurrence.is_none() {
+            parse_recurrence(rest).map(|(taken, value)| {
+                let rrule = value.rrule.clone();
+                recurrence = Some(value);
+                (1 + taken, QuickTokenKind::Recurrence, rrule)
+            })
+        } else {
+            let words = &words[index + preposition..];
+            let date = match word {
+                "at" => None,
+                _ if when.is_some() => None,
+                _ => parse_when(words, today, now, preposition > 0),
+            };
+            match date {
+                Some((taken, value)) => {
+                    let text = match &value {
+                        When::Day(date) => date.format(DATE_FORMAT).to_string(),
+                        When::Instant(at) => {
+                            time = time.or(Some(at.with_timezone(tz).time()));
+                            at.with_timezone(tz).format("%Y-%m-%dT%H:%M").to_string()
+                        }
+                    };
+                    when = Some(value);
+                    Some((preposition + taken, QuickTokenKind::Date, text))
+                }
+                None if time.is_none() => parse_time(words, word == "at").map(|(taken, value)| {
+                    time = Some(value);
+                    (
+                        preposition + taken,
+                        QuickTokenKind::Time,
+                        value.format(TIME_FORMAT).to_string(),
+                    )
+                }),
+                None => None,
+            }
+        };
+
+        match found {
+            Some((taken, kind, value)) => {
+                tokens.push(QuickToken {
+                    text: original[index..index + taken].join(" "),
+                    kind,
+                    value,
+                });
+                index += taken;
+            }
+            None => {
+                title.push(original[index]);
+                index += 1;
+            }
+        }
+    }
+
+    let title = title.join(" ");
+    if title.is_empty() {
+        return Err("Nothing is left of the text for a title".to_string());
+    }
+
+    let deadline = match when {
+        Some(When::Instant(at)) => Some(at),
+        Some(When::Day(date)) => Some(to_utc(
+            tz,
+            date.and_time(time.unwrap_or_else(|| NaiveTime::from_hms(0, 0, 0))),
+        )),
+        // The first matching day to come, today at a time still ahead
+        None if time.is_some() || recurrence.is_some() => {
+            let by_day = recurrence
+                .as_ref()
+                .map(|recurrence| recurrence.by_day.as_slice())
+                .unwrap_or_default();
+            (0..=7)
+                .map(|days| today + Duration::days(days))
+                .filter(|date| by_day.is_empty() || by_day.contains(&date.weekday()))
+                .map(|date| match time {
+                    Some(time) => to_utc(tz, date.and_time(time)),
+                    None => to_utc(tz, date.and_hms(0, 0, 0)),
+                })
+                .find(|deadline| time.is_none() || *deadline > now)
+        }
+        None => None,
+    };
+
+    let mut task = Task::new(title, false, deadline);
+    task.list_id = list_id;
+    task.priority = priority.unwrap_or_default();
+    if let Some(recurrence) = recurrence {
+        task.rrule = Some(recurrence.rrule);
+        task.timezone = Some(tz.name().to_string());
+    }
+    Ok(QuickParse { task, tokens })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Monday, 2024-05-06 12:00 UTC
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-05-06T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn utc(date_time: &str) -> Option<DateTime<Utc>> {
+        Some(
+            DateTime::parse_from_rfc3339(date_time)
+                .unwrap()
+                .with_timezone(&Utc),
+        )
+    }
+
+    #[test]
+    fn parses_the_example() {
+        let home = Uuid::new_v4();
+        let projects = HashMap::from([("Home".to_string(), home)]);
+        let parsed = parse(
+            "Pay rent tomorrow 9am #home !high every month",
+            now(),
+            &Tz::UTC,
+            &projects,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.task.title, "Pay rent");
+        assert_eq!(parsed.task.deadline, utc("2024-05-07T09:00:00Z"));
+        assert_eq!(parsed.task.list_id, Some(home));
+        assert_eq!(parsed.task.priority, Priority::High);
+        assert_eq!(parsed.task.rrule.as_deref(), Some("FREQ=MONTHLY"));
+        let kinds: Vec<QuickTokenKind> = parsed.tokens.iter().map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                QuickTokenKind::Date,
+                QuickTokenKind::Time,
+                QuickTokenKind::List,
+                QuickTokenKind::Priority,
+                QuickTokenKind::Recurrence,
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_dates_and_times() {
+        let cases = [
+            ("Call mom on sat", "Call mom", utc("2024-05-11T00:00:00Z")),
+            ("Call mom due fri.", "Call mom", utc("2024-05-10T00:00:00Z")),
+            ("Call mom saturday", "Call mom", utc("2024-05-11T00:00:00Z")),
+            ("Call mom next mon", "Call mom", utc("2024-05-13T00:00:00Z")),
+            (
+                "Dentist march 5 at 3",
+                "Dentist",
+                utc("2025-03-05T03:00:00Z"),
+            ),
+            ("Standup 9:30 pm", "Standup", utc("2024-05-06T21:30:00Z")),
+            ("Stretch in 2 hours", "Stretch", utc("2024-05-06T14:00:00Z")),
+            (
+                "Water plants every mon and thu",
+                "Water plants",
+                utc("2024-05-06T00:00:00Z"),
+            ),
+        ];
+        for (text, title, deadline) in cases {
+            let parsed = parse(text, now(), &Tz::UTC, &HashMap::new()).unwrap();
+            assert_eq!(parsed.task.title, title, "{}", text);
+            assert_eq!(parsed.task.deadline, deadline, "{}", text);
+        }
+    }
+
+    #[test]
+    fn keeps_words_that_only_look_like_dates() {
+        for text in [
+            "Buy sun cream",
+            "Sat prep",
+            "Wed anniversary gift",
+            "Fix the mar on the table",
+            "Read chapter 5",
+            "Tidy up #nowhere !someday",
+        ] {
+            let parsed = parse(text, now(), &Tz::UTC, &HashMap::new()).unwrap();
+            assert_eq!(parsed.task.title, text);
+            assert_eq!(parsed.task.deadline, None, "{}", text);
+            assert!(parsed.tokens.is_empty(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn keeps_counts_out_of_range_in_the_title() {
+        for text in [
+            "Call mom in 99999999999999 days",
+            "Call mom in 10001 years",
+            "Call mom in 0 hours",
+            "Call mom every 99999999999999 days",
+        ] {
+            let parsed = parse(text, now(), &Tz::UTC, &HashMap::new()).unwrap();
+            assert_eq!(parsed.task.title, text);
+            assert_eq!(parsed.task.deadline, None, "{}", text);
+        }
+
+        let parsed = parse("Renew in 10000 days", now(), &Tz::UTC, &HashMap::new()).unwrap();
+        assert_eq!(parsed.task.title, "Renew");
+        assert_eq!(parsed.task.deadline, utc("2051-09-22T00:00:00Z"));
+    }
+
+    #[test]
+    fn reads_the_first_of_each_kind_only() {
+        let parsed = parse("Ship today tomorrow", now(), &Tz::UTC, &HashMap::new()).unwrap();
+        assert_eq!(parsed.task.title, "Ship tomorrow");
+        assert_eq!(parsed.task.deadline, utc("2024-05-06T00:00:00Z"));
+    }
+
+    #[test]
+    fn requires_a_title() {
+        assert!(parse("tomorrow 9am", now(), &Tz::UTC, &HashMap::new()).is_err());
+    }
+}
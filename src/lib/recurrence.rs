@@ -0,0 +1,350 @@
+use std::str::FromStr;
+
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+/// Largest `INTERVAL`, far beyond any useful one, which keeps occurrences
+/// within the dates that can be represented.
+const MAX_INTERVAL: u32 = 1000;
+
+/// The subset of RFC 5545 `RRULE`s supported for recurring tasks:
+/// `FREQ`, `INTERVAL`, `COUNT`, `UNTIL` and, for weekly rules, `BYDAY`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<DateTime<Utc>>,
+    pub by_day: Vec<Weekday>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+fn parse_weekday(day: &str) -> Result<Weekday, String> {
+    match day {
+        "MO" => Ok(Weekday::Mon),
+        "TU" => Ok(Weekday::Tue),
+        "WE" => Ok(Weekday::Wed),
+        "TH" => Ok(Weekday::Thu),
+        "FR" => Ok(Weekday::Fri),
+        "SA" => Ok(Weekday::Sat),
+        "SU" => Ok(Weekday::Sun),
+        _ => Err(format!("Unsupported BYDAY value `{}`", day)),
+    }
+}
+
+fn parse_until(until: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date_time) = NaiveDateTime::parse_from_str(until, "%Y%m%dT%H%M%SZ") {
+        return Ok(Utc.from_utc_datetime(&date_time));
+    }
+    match NaiveDate::parse_from_str(until, "%Y%m%d") {
+        Ok(date) => Ok(Utc.from_utc_datetime(&date.and_hms(23, 59, 59))),
+        Err(_) => Err(format!("Invalid UNTIL value `{}`", until)),
+    }
+}
+
+impl FromStr for RRule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+
+        let mut freq = None;
+        let mut interval = 1;
+        let mut count = None;
+        let mut until = None;
+        let mut by_day = Vec::new();
+
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Malformed RRULE part `{}`", part))?;
+
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.to_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(format!("Unsupported FREQ `{}`", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| (1..=MAX_INTERVAL).contains(interval))
+                        .ok_or_else(|| format!("Invalid INTERVAL `{}`", value))?
+                }
+                "COUNT" => {
+                    count = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|count| *count > 0)
+                            .ok_or_else(|| format!("Invalid COUNT `{}`", value))?,
+                    )
+                }
+                "UNTIL" => until = Some(parse_until(value)?),
+                "BYDAY" => {
+                    by_day = value
+                        .to_uppercase()
+                        .split(',')
+                        .map(parse_weekday)
+                        .collect::<Result<_, _>>()?
+                }
+                "WKST" if value.eq_ignore_ascii_case("MO") => {}
+                _ => return Err(format!("Unsupported RRULE part `{}`", part)),
+            }
+        }
+
+        let freq = freq.ok_or_else(|| "RRULE requires FREQ".to_string())?;
+        if count.is_some() && until.is_some() {
+            return Err("COUNT and UNTIL cannot be used together".to_string());
+        }
+        if !by_day.is_empty() && freq != Frequency::Weekly {
+            return Err("BYDAY is only supported with FREQ=WEEKLY".to_string());
+        }
+        by_day.sort_by_key(Weekday::num_days_from_monday);
+        by_day.dedup();
+
+        Ok(RRule {
+            freq,
+            interval,
+            count,
+            until,
+            by_day,
+        })
+    }
+}
+
+fn add_months(date: NaiveDate, months: u32) -> Option<NaiveDate> {
+    let total = date.year() as i64 * 12 + date.month0() as i64 + months as i64;
+    NaiveDate::from_ymd_opt(
+        i32::try_from(total.div_euclid(12)).ok()?,
+        total.rem_euclid(12) as u32 + 1,
+        date.day(),
+    )
+}
+
+/// Resolves a local wall-clock time, moving past DST gaps.
+pub fn to_utc(tz: &Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(date_time) | LocalResult::Ambiguous(date_time, _) => {
+            date_time.with_timezone(&Utc)
+        }
+        LocalResult::None => to_utc(tz, local + Duration::hours(1)),
+    }
+}
+
+impl RRule {
+    /// Computes the occurrence following `current`, the `occurrence`-th one
+    /// (1-based) of the series, keeping its wall-clock time in `tz`.
+    pub fn next_after(
+        &self,
+        current: DateTime<Utc>,
+        occurrence: u32,
+        tz: &Tz,
+    ) -> Option<DateTime<Utc>> {
+        if let Some(count) = self.count {
+            if occurrence >= count {
+                return None;
+            }
+        }
+
+        let local = current.with_timezone(tz).naive_local();
+        let (date, time) = (local.date(), local.time());
+
+        let next_date = match self.freq {
+            Frequency::Daily => date.checked_add_signed(Duration::days(self.interval as i64)),
+            Frequency::Weekly if self.by_day.is_empty() => {
+                date.checked_add_signed(Duration::weeks(self.interval as i64))
+            }
+            Frequency::Weekly => {
+                let weekday = date.weekday().num_days_from_monday();
+                match self
+                    .by_day
+                    .iter()
+                    .find(|day| day.num_days_from_monday() > weekday)
+                {
+                    Some(day) => date.checked_add_signed(Duration::days(
+                        (day.num_days_from_monday() - weekday) as i64,
+                    )),
+                    None => date
+                        .checked_sub_signed(Duration::days(weekday as i64))
+                        .and_then(|week_start| {
+                            week_start.checked_add_signed(Duration::weeks(self.interval as i64))
+                        })
+                        .and_then(|week_start| {
+                            week_start.checked_add_signed(Duration::days(
+                                self.by_day[0].num_days_from_monday() as i64,
+                            ))
+                        }),
+                }
+            }
+            // Months and years lacking the day (e.g. the 31st) are skipped
+            Frequency::Monthly => (1..=48).find_map(|step| {
+                self.interval
+                    .checked_mul(step)
+                    .and_then(|months| add_months(date, months))
+            }),
+            Frequency::Yearly => (1..=8).find_map(|step| {
+                self.interval
+                    .checked_mul(12 * step)
+                    .and_then(|months| add_months(date, months))
+            }),
+        }?;
+
+        let next = to_utc(tz, next_date.and_time(time));
+        match self.until {
+            Some(until) if next > until => None,
+            _ => Some(next),
+        }
+    }
+}
+
+pub fn parse_timezone(timezone: &str) -> Result<Tz, String> {
+    timezone
+        .parse()
+        .map_err(|_| format!("Unknown timezone `{}`", timezone))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(date_time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(date_time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rule: RRule = "RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=TH,MO,TH;COUNT=5"
+            .parse()
+            .unwrap();
+        assert_eq!(rule.freq, Frequency::Weekly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.count, Some(5));
+        assert_eq!(rule.by_day, vec![Weekday::Mon, Weekday::Thu]);
+
+        let rule: RRule = "FREQ=DAILY;UNTIL=20240301".parse().unwrap();
+        assert_eq!(rule.until, Some(utc("2024-03-01T23:59:59Z")));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for rule in [
+            "",
+            "INTERVAL=2",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;COUNT=2;UNTIL=20240301",
+            "FREQ=DAILY;BYDAY=MO",
+            "FREQ=WEEKLY;BYDAY=XX",
+            "FREQ=DAILY;BYMONTH=1",
+            "FREQ=DAILY;INTERVAL=1001",
+            "FREQ=DAILY;INTERVAL=4000000000",
+        ] {
+            assert!(rule.parse::<RRule>().is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn daily() {
+        let rule: RRule = "FREQ=DAILY;INTERVAL=3".parse().unwrap();
+        assert_eq!(
+            rule.next_after(utc("2024-01-30T09:00:00Z"), 1, &Tz::UTC),
+            Some(utc("2024-02-02T09:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn daily_keeps_wall_clock_time_across_dst() {
+        let rule: RRule = "FREQ=DAILY".parse().unwrap();
+        // 09:00 in Berlin, before and after the switch to summer time
+        assert_eq!(
+            rule.next_after(utc("2024-03-30T08:00:00Z"), 1, &Tz::Europe__Berlin),
+            Some(utc("2024-03-31T07:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn weekly_by_day() {
+        let rule: RRule = "FREQ=WEEKLY;BYDAY=MO,TH".parse().unwrap();
+        // Monday to Thursday, Thursday to the next Monday
+        assert_eq!(
+            rule.next_after(utc("2024-05-06T09:00:00Z"), 1, &Tz::UTC),
+            Some(utc("2024-05-09T09:00:00Z"))
+        );
+        assert_eq!(
+            rule.next_after(utc("2024-05-09T09:00:00Z"), 2, &Tz::UTC),
+            Some(utc("2024-05-13T09:00:00Z"))
+        );
+
+        let rule: RRule = "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH".parse().unwrap();
+        assert_eq!(
+            rule.next_after(utc("2024-05-09T09:00:00Z"), 2, &Tz::UTC),
+            Some(utc("2024-05-20T09:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn monthly_skips_months_without_the_day() {
+        let rule: RRule = "FREQ=MONTHLY".parse().unwrap();
+        assert_eq!(
+            rule.next_after(utc("2024-01-31T09:00:00Z"), 1, &Tz::UTC),
+            Some(utc("2024-03-31T09:00:00Z"))
+        );
+        assert_eq!(
+            rule.next_after(utc("2024-03-31T09:00:00Z"), 2, &Tz::UTC),
+            Some(utc("2024-05-31T09:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn yearly_on_february_29() {
+        let rule: RRule = "FREQ=YEARLY".parse().unwrap();
+        assert_eq!(
+            rule.next_after(utc("2024-02-29T09:00:00Z"), 1, &Tz::UTC),
+            Some(utc("2028-02-29T09:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn stops_after_count_and_until() {
+        let rule: RRule = "FREQ=DAILY;COUNT=2".parse().unwrap();
+        let current = utc("2024-01-01T09:00:00Z");
+        assert!(rule.next_after(current, 1, &Tz::UTC).is_some());
+        assert_eq!(rule.next_after(current, 2, &Tz::UTC), None);
+
+        let rule: RRule = "FREQ=DAILY;UNTIL=20240102T080000Z".parse().unwrap();
+        assert_eq!(rule.next_after(current, 1, &Tz::UTC), None);
+    }
+
+    #[test]
+    fn ends_at_the_last_representable_year() {
+        let current = Utc.from_utc_datetime(&NaiveDate::from_ymd(262_143, 6, 15).and_hms(9, 0, 0));
+        for rule in [
+            "FREQ=DAILY;INTERVAL=1000",
+            "FREQ=WEEKLY;INTERVAL=1000",
+            "FREQ=WEEKLY;INTERVAL=1000;BYDAY=MO",
+            "FREQ=MONTHLY;INTERVAL=1000",
+            "FREQ=YEARLY;INTERVAL=1000",
+        ] {
+            let rule: RRule = rule.parse().unwrap();
+            assert_eq!(rule.next_after(current, 1, &Tz::UTC), None, "{:?}", rule);
+        }
+    }
+}
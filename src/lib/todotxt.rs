@@ -0,0 +1,418 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::lib::recurrence::{parse_timezone, to_utc, Frequency, RRule};
+use crate::model::import::{ImportedRow, RowError};
+use entity::sea_orm_active_enums::Priority;
+use entity::task::Model as Task;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Timelike, Utc};
+use chrono_tz::Tz;
+use uuid::Uuid;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Name of a list as a `+project`, its words joined by dashes.
+pub fn project(list_name: &str) -> String {
+    list_name.split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+fn priority_letter(priority: Priority) -> Option<char> {
+    match priority {
+        Priority::None => None,
+        Priority::Low => Some('D'),
+        Priority::Medium => Some('C'),
+        Priority::High => Some('B'),
+        Priority::Urgent => Some('A'),
+    }
+}
+
+/// Inverse of [`priority_letter`], everything below `(C)` being low.
+fn parse_priority_letter(letter: &str) -> Option<Priority> {
+    match letter {
+        "A" => Some(Priority::Urgent),
+        "B" => Some(Priority::High),
+        "C" => Some(Priority::Medium),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => {
+            Some(Priority::Low)
+        }
+        _ => None,
+    }
+}
+
+/// `rec:` of the rules it can express, e.g. `2w` for every other week.
+fn rec(rrule: &str) -> Option<String> {
+    let rrule = rrule.parse::<RRule>().ok()?;
+    if rrule.count.is_some() || rrule.until.is_some() || !rrule.by_day.is_empty() {
+        return None;
+    }
+    let unit = match rrule.freq {
+        Frequency::Daily => 'd',
+        Frequency::Weekly => 'w',
+        Frequency::Monthly => 'm',
+        Frequency::Yearly => 'y',
+    };
+    Some(format!("{}{}", rrule.interval, unit))
+}
+
+fn parse_rec(rec: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid rec `{}`", rec);
+    // Strict recurrence (`+`) is what recurring tasks do anyway
+    let rec = rec.strip_prefix('+').unwrap_or(rec);
+    let unit = rec.chars().last().ok_or_else(invalid)?;
+    let interval = match &rec[..rec.len() - unit.len_utf8()] {
+        "" => 1,
+        interval => interval
+            .parse::<u32>()
+            .ok()
+            .filter(|interval| *interval > 0)
+            .ok_or_else(invalid)?,
+    };
+    let freq = match unit {
+        'd' => "DAILY",
+        'w' => "WEEKLY",
+        'm' => "MONTHLY",
+        'y' => "YEARLY",
+        _ => return Err(invalid()),
+    };
+    Ok(format!("FREQ={};INTERVAL={}", freq, interval))
+}
+
+fn timezone(task: &Task) -> Tz {
+    task.timezone
+        .as_deref()
+        .and_then(|timezone| parse_timezone(timezone).ok())
+        .unwrap_or(Tz::UTC)
+}
+
+/// Renders a task as a todo.txt line. Dates are given in the task's timezone,
+/// with the time of the deadline as `at:` unless it is midnight. Completed
+/// tasks keep their priority as `pri:`.
+pub fn line(task: &Task, project: Option<&str>) -> String {
+    let tz = timezone(task);
+    let date = |date: DateTime<Utc>| date.with_timezone(&tz).format(DATE_FORMAT).to_string();
+    let priority = priority_letter(task.priority);
+
+    let mut parts = Vec::new();
+    if task.completed {
+        parts.push("x".to_string());
+        parts.push(date(task.completed_at.unwrap_or(task.created_at)));
+    } else if let Some(priority) = priority {
+        parts.push(format!("({})", priority));
+    }
+    parts.push(date(task.created_at));
+    parts.extend(task.title.split_whitespace().map(str::to_string));
+
+    if let (true, Some(priority)) = (task.completed, priority) {
+        parts.push(format!("pri:{}", priority));
+    }
+    if let Some(project) = project {
+        parts.push(format!("+{}", project));
+    }
+    if let Some(deadline) = task.deadline {
+        let local = deadline.with_timezone(&tz);
+        parts.push(format!("due:{}", local.format(DATE_FORMAT)));
+        match (local.hour(), local.minute(), local.second()) {
+            (0, 0, 0) => {}
+            (_, _, 0) => parts.push(format!("at:{}", local.format("%H:%M"))),
+            _ => parts.push(format!("at:{}", local.format("%H:%M:%S"))),
+        }
+    }
+    if let Some(rrule) = &task.rrule {
+        match rec(rrule) {
+            Some(rec) => parts.push(format!("rec:{}", rec)),
+            None => parts.push(format!("rrule:{}", rrule)),
+        }
+    }
+    if let Some(timezone) = &task.timezone {
+        parts.push(format!("tz:{}", timezone));
+    }
+
+    parts.join(" ")
+}
+
+fn parse_day(token: Option<&&str>) -> Option<NaiveDate> {
+    token.and_then(|token| NaiveDate::parse_from_str(token, DATE_FORMAT).ok())
+}
+
+fn parse_line(
+    row: u64,
+    line: &str,
+    projects: &HashMap<String, Uuid>,
+) -> Result<Task, Vec<RowError>> {
+    let mut tokens: VecDeque<&str> = line.split_whitespace().collect();
+    let mut errors = Vec::new();
+    let mut error = |field: &str, message: String| {
+        errors.push(RowError::new(row, Some(field), message));
+    };
+
+    let completed = tokens.front() == Some(&"x");
+    let mut completed_on = None;
+    let mut priority = None;
+    if completed {
+        tokens.pop_front();
+        completed_on = parse_day(tokens.front());
+        if completed_on.is_some() {
+            tokens.pop_front();
+        }
+    } else if let Some(letter) = tokens
+        .front()
+        .and_then(|token| token.strip_prefix('('))
+        .and_then(|token| token.strip_suffix(')'))
+    {
+        if let Some(parsed) = parse_priority_letter(letter) {
+            priority = Some(parsed);
+            tokens.pop_front();
+        }
+    }
+    let created_on = parse_day(tokens.front());
+    if created_on.is_some() {
+        tokens.pop_front();
+    }
+
+    let mut title = Vec::new();
+    let mut list_id = None;
+    let mut due_on = None;
+    let mut due_at = None;
+    let mut rrule = None;
+    let mut tz = None;
+    for token in tokens {
+        if let Some(project) = token.strip_prefix('+') {
+            if let (None, Some(id)) = (list_id, projects.get(project)) {
+                list_id = Some(*id);
+                continue;
+            }
+        }
+        match token.split_once(':') {
+            Some(("due", value)) => match NaiveDate::parse_from_str(value, DATE_FORMAT) {
+                Ok(date) => due_on = Some(date),
+                Err(_) => error("due", format!("Invalid date `{}`", value)),
+            },
+            Some(("at", value)) => match NaiveTime::parse_from_str(value, "%H:%M:%S")
+                .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M"))
+            {
+                Ok(time) => due_at = Some(time),
+                Err(_) => error("at", format!("Invalid time `{}`", value)),
+            },
+            Some(("rec", value)) => match parse_rec(value) {
+                Ok(rule) => rrule = Some(rule),
+                Err(err) => error("rec", err),
+            },
+            Some(("rrule", value)) => rrule = Some(value.to_string()),
+            Some(("tz", value)) => match parse_timezone(value) {
+                Ok(parsed) => tz = Some(parsed),
+                Err(err) => error("tz", err),
+            },
+            Some(("pri", value)) if parse_priority_letter(value).is_some() => {
+                priority = parse_priority_letter(value)
+            }
+            // Other projects, contexts and key-values stay part of the title
+            _ => title.push(token),
+        }
+    }
+
+    if title.is_empty() {
+        error("title", "Missing title".to_string());
+    }
+    if due_at.is_some() && due_on.is_none() {
+        error("at", "'at' requires 'due'".to_string());
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let local_tz = tz.unwrap_or(Tz::UTC);
+    let deadline = due_on.map(|date| {
+        to_utc(
+            &local_tz,
+            date.and_time(due_at.unwrap_or_else(|| NaiveTime::from_hms(0, 0, 0))),
+        )
+    });
+    let mut task = Task::new(title.join(" "), completed, deadline);
+    if let Some(created_on) = created_on {
+        task.created_at = to_utc(&local_tz, created_on.and_hms(0, 0, 0));
+    }
+    if let Some(completed_on) = completed_on {
+        task.completed_at = Some(to_utc(&local_tz, completed_on.and_hms(0, 0, 0)));
+    }
+    task.list_id = list_id;
+    task.priority = priority.unwrap_or_default();
+    task.rrule = rrule;
+    task.timezone = tz.map(|tz| tz.name().to_string());
+    Ok(task)
+}
+
+/// Reads the tasks of a todo.txt file, one per line. A `+project` naming one
+/// of `projects` puts the task in that list; other projects, `@context`s and
+/// unknown `key:value`s stay part of the title.
+pub fn parse(text: &str, projects: &HashMap<String, Uuid>) -> (Vec<ImportedRow>, Vec<RowError>) {
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let row = index as u64 + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(row, line, projects) {
+            Ok(task) => rows.push(ImportedRow { row, task }),
+            Err(line_errors) => errors.extend(line_errors),
+        }
+    }
+    (rows, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(date_time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(date_time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn read(text: &str) -> (Vec<ImportedRow>, Vec<RowError>) {
+        parse(text, &HashMap::new())
+    }
+
+    #[test]
+    fn writes_priorities_dates_and_metadata() {
+        let mut task = Task::new(
+            "Pay  rent @home".to_string(),
+            false,
+            Some(utc("2024-05-31T07:30:00Z")),
+        );
+        task.created_at = utc("2024-05-01T22:30:00Z");
+        task.priority = Priority::High;
+        task.rrule = Some("FREQ=MONTHLY".to_string());
+        task.timezone = Some("Europe/Berlin".to_string());
+        assert_eq!(
+            line(&task, Some(&project("Family Budget"))),
+            "(B) 2024-05-02 Pay rent @home +Family-Budget due:2024-05-31 at:09:30 rec:1m tz:Europe/Berlin"
+        );
+
+        let mut done = Task::new(
+            "Backup".to_string(),
+            true,
+            Some(utc("2024-05-06T00:00:00Z")),
+        );
+        done.created_at = utc("2024-05-01T12:00:00Z");
+        done.completed_at = Some(utc("2024-05-06T18:00:00Z"));
+        done.priority = Priority::Urgent;
+        done.rrule = Some("FREQ=WEEKLY;BYDAY=MO,TH".to_string());
+        assert_eq!(
+            line(&done, None),
+            "x 2024-05-06 2024-05-01 Backup pri:A due:2024-05-06 rrule:FREQ=WEEKLY;BYDAY=MO,TH"
+        );
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let list_id = Uuid::new_v4();
+        let projects = HashMap::from([(project("Family Budget"), list_id)]);
+
+        let mut task = Task::new(
+            "Pay rent @home +elsewhere".to_string(),
+            false,
+            Some(utc("2024-03-31T01:30:45Z")),
+        );
+        task.created_at = utc("2024-03-01T00:00:00+01:00");
+        task.list_id = Some(list_id);
+        task.priority = Priority::Medium;
+        task.rrule = Some("FREQ=WEEKLY;INTERVAL=2".to_string());
+        task.timezone = Some("Europe/Berlin".to_string());
+
+        let mut done = Task::new("Backup".to_string(), true, None);
+        done.created_at = utc("2024-05-01T00:00:00Z");
+        done.completed_at = Some(utc("2024-05-06T00:00:00Z"));
+        done.priority = Priority::Low;
+
+        let text = format!(
+            "{}\n\n{}\n",
+            line(&task, Some(&project("Family Budget"))),
+            line(&done, None)
+        );
+        let (rows, errors) = parse(&text, &projects);
+
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].row, 3);
+        for (imported, task) in rows.iter().map(|row| &row.task).zip([&task, &done]) {
+            assert_eq!(imported.title, task.title);
+            assert_eq!(imported.completed, task.completed);
+            assert_eq!(imported.created_at, task.created_at);
+            assert_eq!(imported.completed_at, task.completed_at);
+            assert_eq!(imported.deadline, task.deadline);
+            assert_eq!(imported.list_id, task.list_id);
+            assert_eq!(imported.priority, task.priority);
+            assert_eq!(imported.timezone, task.timezone);
+        }
+        assert_eq!(
+            rows[0].task.rrule.as_deref(),
+            Some("FREQ=WEEKLY;INTERVAL=2")
+        );
+    }
+
+    #[test]
+    fn reads_priorities_only_where_they_belong() {
+        let (rows, errors) = read(
+            "(A) Urgent\n\
+             (E) Someday\n\
+             (a) Lowercase\n\
+             Call (B) later\n\
+             x (A) Done with pri:C\n\
+             2024-05-01 Created 2024-05-02\n",
+        );
+
+        assert!(errors.is_empty(), "{:?}", errors);
+        let read: Vec<_> = rows
+            .iter()
+            .map(|row| (row.task.title.as_str(), row.task.priority))
+            .collect();
+        assert_eq!(
+            read,
+            [
+                ("Urgent", Priority::Urgent),
+                ("Someday", Priority::Low),
+                ("(a) Lowercase", Priority::None),
+                ("Call (B) later", Priority::None),
+                ("(A) Done with", Priority::Medium),
+                ("Created 2024-05-02", Priority::None),
+            ]
+        );
+        assert!(rows[4].task.completed);
+        assert_eq!(rows[5].task.created_at, utc("2024-05-01T00:00:00Z"));
+        assert_eq!(rows[5].task.deadline, None);
+    }
+
+    #[test]
+    fn reports_malformed_lines_and_keeps_the_others() {
+        let (rows, errors) = read(
+            "Fine due:2024-05-06 rec:+3d\n\
+             Broken due:2024-02-30 at:25:00 rec:0d tz:Mars/Olympus\n\
+             Early at:09:00\n\
+             (A) 2024-05-01 due:2024-05-06\n\
+             Odd rec:2x\n",
+        );
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].task.deadline, Some(utc("2024-05-06T00:00:00Z")));
+        assert_eq!(rows[0].task.rrule.as_deref(), Some("FREQ=DAILY;INTERVAL=3"));
+
+        let fields: Vec<_> = errors
+            .iter()
+            .map(|error| (error.row, error.field.as_deref()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                (2, Some("due")),
+                (2, Some("at")),
+                (2, Some("rec")),
+                (2, Some("tz")),
+                (3, Some("at")),
+                (4, Some("title")),
+                (5, Some("rec")),
+            ]
+        );
+    }
+}
@@ -1,10 +1,15 @@
+#![allow(special_module_name)]
+
 mod api;
 mod dao;
 mod database;
+mod events;
 mod lib;
 mod model;
+mod scheduler;
 
 use crate::database::DbClient;
+use crate::events::bus::EventBus;
 
 use actix_cors::Cors;
 use actix_web::{get, middleware, App, HttpServer, Responder};
@@ -39,14 +44,52 @@ async fn main() -> Result<(), Error> {
     let db = DbClient::init().await?;
 
     let db_data = actix_web::web::Data::new(db);
+    let event_bus = actix_web::web::Data::new(EventBus::new(events::bus::replay_size()));
+
+    tokio::spawn(events::listener::run(
+        db_data.clone(),
+        event_bus.clone(),
+        database::construct_db_uri()?,
+    ));
+    tokio::spawn(scheduler::reminder::run(
+        db_data.clone(),
+        scheduler::notifier::from_env(),
+        scheduler::reminder::poll_interval(),
+    ));
+    tokio::spawn(scheduler::webhook::run(
+        db_data.clone(),
+        scheduler::webhook::poll_interval(),
+    ));
+    tokio::spawn(scheduler::attachment::run(
+        db_data.clone(),
+        scheduler::attachment::poll_interval(),
+    ));
 
     HttpServer::new(move || {
         App::new()
-            .wrap(middleware::Logger::default())
+            // The default format, but without the query string, which may hold
+            // the token of a calendar feed
+            .wrap(middleware::Logger::new(
+                r#"%a "%m %U" %s %b "%{Referer}i" "%{User-Agent}i" %T"#,
+            ))
             .wrap(Cors::permissive())
             .app_data(db_data.clone())
+            .app_data(event_bus.clone())
             .service(health_check)
+            // Before `/todo/{task_id}`, which would match `/todo/events`
+            .configure(api::event::attach_service)
+            .configure(api::transfer::attach_service)
+            .configure(api::quick::attach_service)
             .configure(api::task::attach_service)
+            .configure(api::attachment::attach_service)
+            .configure(api::comment::attach_service)
+            .configure(api::audit::attach_service)
+            .configure(api::admin::attach_service)
+            .configure(api::stats::attach_service)
+            .configure(api::task_list::attach_service)
+            .configure(api::token::attach_service)
+            .configure(api::webhook::attach_service)
+            .configure(api::ws::attach_service)
     })
     .bind(("0.0.0.0", port))?
     .run()
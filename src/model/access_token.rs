@@ -0,0 +1,69 @@
+use entity::access_token::Model as AccessToken;
+
+use chrono::serde::{ts_milliseconds, ts_milliseconds_option};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NewAccessToken {
+    pub name: String,
+
+    pub scopes: Vec<String>,
+
+    #[serde(default)]
+    pub owner: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublicAccessToken {
+    #[serde(rename = "_id")]
+    pub token_id: Uuid,
+
+    pub name: String,
+
+    pub owner: String,
+
+    pub scopes: Vec<String>,
+
+    #[serde(rename = "createdAt", with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+
+    #[serde(
+        rename = "lastUsedAt",
+        skip_serializing_if = "Option::is_none",
+        with = "ts_milliseconds_option"
+    )]
+    pub last_used_at: Option<DateTime<Utc>>,
+
+    #[serde(
+        rename = "revokedAt",
+        skip_serializing_if = "Option::is_none",
+        with = "ts_milliseconds_option"
+    )]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<AccessToken> for PublicAccessToken {
+    fn from(token: AccessToken) -> Self {
+        Self {
+            token_id: token.id,
+            name: token.name,
+            owner: token.owner,
+            scopes: token.scopes.split_whitespace().map(String::from).collect(),
+            created_at: token.created_at,
+            last_used_at: token.last_used_at,
+            revoked_at: token.revoked_at,
+        }
+    }
+}
+
+/// Returned once on issuance; the plain token is never stored.
+#[derive(Debug, Serialize)]
+pub struct IssuedAccessToken {
+    #[serde(flatten)]
+    pub details: PublicAccessToken,
+
+    pub token: String,
+}
@@ -0,0 +1,47 @@
+use entity::attachment::Model as Attachment;
+
+use chrono::serde::ts_milliseconds;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub struct PublicAttachment {
+    #[serde(rename = "_id")]
+    pub attachment_id: Uuid,
+
+    #[serde(rename = "taskId")]
+    pub task_id: Option<Uuid>,
+
+    pub filename: String,
+
+    #[serde(rename = "contentType")]
+    pub content_type: String,
+
+    /// In bytes
+    pub size: i64,
+
+    /// Hex encoded SHA-256 of the contents
+    pub checksum: String,
+
+    #[serde(rename = "uploadedBy")]
+    pub uploaded_by: String,
+
+    #[serde(rename = "createdAt", with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Attachment> for PublicAttachment {
+    fn from(attachment: Attachment) -> Self {
+        Self {
+            attachment_id: attachment.id,
+            task_id: attachment.task_id,
+            filename: attachment.filename,
+            content_type: attachment.content_type,
+            size: attachment.size,
+            checksum: attachment.checksum,
+            uploaded_by: attachment.uploaded_by,
+            created_at: attachment.created_at,
+        }
+    }
+}
@@ -0,0 +1,517 @@
+use entity::access_token::Model as AccessToken;
+use entity::attachment::Model as Attachment;
+use entity::comment::Model as Comment;
+use entity::list_member::{Model as ListMember, Role};
+use entity::reminder::Model as Reminder;
+use entity::sea_orm_active_enums::Priority;
+use entity::task::Model as Task;
+use entity::task_event::Model as TaskEvent;
+use entity::task_list::Model as TaskList;
+use entity::webhook_delivery::{DeliveryStatus, Model as WebhookDelivery};
+use entity::webhook_subscription::Model as WebhookSubscription;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Version of the backup document itself, bumped whenever its shape changes
+/// in a way older servers cannot read.
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Every row of every table, as written by `GET /admin/backup`.
+///
+/// Timestamps are RFC 3339 with their full precision, unlike elsewhere in
+/// the API, so that they survive a round trip unchanged.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Backup {
+    pub version: u32,
+
+    /// Last migration applied to the database the backup was taken from
+    pub schema_version: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub tables: BackupTables,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupTables {
+    pub access_tokens: Vec<BackupAccessToken>,
+    pub task_lists: Vec<BackupTaskList>,
+    pub list_members: Vec<BackupListMember>,
+    pub tasks: Vec<BackupTask>,
+    pub reminders: Vec<BackupReminder>,
+    /// Without their files, which are backed up with the attachments directory
+    pub attachments: Vec<BackupAttachment>,
+    pub comments: Vec<BackupComment>,
+    pub task_events: Vec<BackupTaskEvent>,
+    pub webhook_subscriptions: Vec<BackupWebhookSubscription>,
+    pub webhook_deliveries: Vec<BackupWebhookDelivery>,
+}
+
+/// Number of rows per table, e.g. as the result of a restore.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSummary {
+    pub schema_version: String,
+    pub access_tokens: usize,
+    pub task_lists: usize,
+    pub list_members: usize,
+    pub tasks: usize,
+    pub reminders: usize,
+    pub attachments: usize,
+    pub comments: usize,
+    pub task_events: usize,
+    pub webhook_subscriptions: usize,
+    pub webhook_deliveries: usize,
+}
+
+impl BackupSummary {
+    pub fn new(schema_version: String, tables: &BackupTables) -> Self {
+        Self {
+            schema_version,
+            access_tokens: tables.access_tokens.len(),
+            task_lists: tables.task_lists.len(),
+            list_members: tables.list_members.len(),
+            tasks: tables.tasks.len(),
+            reminders: tables.reminders.len(),
+            attachments: tables.attachments.len(),
+            comments: tables.comments.len(),
+            task_events: tables.task_events.len(),
+            webhook_subscriptions: tables.webhook_subscriptions.len(),
+            webhook_deliveries: tables.webhook_deliveries.len(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupAccessToken {
+    pub id: Uuid,
+    pub name: String,
+    pub owner: String,
+    pub token_hash: String,
+    pub scopes: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<AccessToken> for BackupAccessToken {
+    fn from(token: AccessToken) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            owner: token.owner,
+            token_hash: token.token_hash,
+            scopes: token.scopes,
+            created_at: token.created_at,
+            last_used_at: token.last_used_at,
+            revoked_at: token.revoked_at,
+        }
+    }
+}
+
+impl From<BackupAccessToken> for AccessToken {
+    fn from(token: BackupAccessToken) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            owner: token.owner,
+            token_hash: token.token_hash,
+            scopes: token.scopes,
+            created_at: token.created_at,
+            last_used_at: token.last_used_at,
+            revoked_at: token.revoked_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupTaskList {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<TaskList> for BackupTaskList {
+    fn from(list: TaskList) -> Self {
+        Self {
+            id: list.id,
+            name: list.name,
+            created_at: list.created_at,
+        }
+    }
+}
+
+impl From<BackupTaskList> for TaskList {
+    fn from(list: BackupTaskList) -> Self {
+        Self {
+            id: list.id,
+            name: list.name,
+            created_at: list.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupListMember {
+    pub list_id: Uuid,
+    pub member: String,
+    pub role: Role,
+    pub invited_by: String,
+    pub created_at: DateTime<Utc>,
+    pub accepted_at: Option<DateTime<Utc>>,
+}
+
+impl From<ListMember> for BackupListMember {
+    fn from(member: ListMember) -> Self {
+        Self {
+            list_id: member.list_id,
+            member: member.member,
+            role: member.role,
+            invited_by: member.invited_by,
+            created_at: member.created_at,
+            accepted_at: member.accepted_at,
+        }
+    }
+}
+
+impl From<BackupListMember> for ListMember {
+    fn from(member: BackupListMember) -> Self {
+        Self {
+            list_id: member.list_id,
+            member: member.member,
+            role: member.role,
+            invited_by: member.invited_by,
+            created_at: member.created_at,
+            accepted_at: member.accepted_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupTask {
+    pub id: Uuid,
+    pub title: String,
+    pub completed: bool,
+    pub created_at: DateTime<Utc>,
+    pub deadline: Option<DateTime<Utc>>,
+    pub list_id: Option<Uuid>,
+    pub priority: Priority,
+    pub rrule: Option<String>,
+    pub timezone: Option<String>,
+    pub series_id: Option<Uuid>,
+    pub occurrence: i32,
+    pub parent_id: Option<Uuid>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub position: String,
+    pub notes: Option<String>,
+}
+
+impl From<Task> for BackupTask {
+    fn from(task: Task) -> Self {
+        Self {
+            id: task.id,
+            title: task.title,
+            completed: task.completed,
+            created_at: task.created_at,
+            deadline: task.deadline,
+            list_id: task.list_id,
+            priority: task.priority,
+            rrule: task.rrule,
+            timezone: task.timezone,
+            series_id: task.series_id,
+            occurrence: task.occurrence,
+            parent_id: task.parent_id,
+            completed_at: task.completed_at,
+            position: task.position,
+            notes: task.notes,
+        }
+    }
+}
+
+impl From<BackupTask> for Task {
+    fn from(task: BackupTask) -> Self {
+        Self {
+            id: task.id,
+            title: task.title,
+            completed: task.completed,
+            created_at: task.created_at,
+            deadline: task.deadline,
+            list_id: task.list_id,
+            priority: task.priority,
+            rrule: task.rrule,
+            timezone: task.timezone,
+            series_id: task.series_id,
+            occurrence: task.occurrence,
+            parent_id: task.parent_id,
+            completed_at: task.completed_at,
+            position: task.position,
+            notes: task.notes,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupReminder {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub offset_ms: i64,
+    pub remind_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub attempts: i32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Reminder> for BackupReminder {
+    fn from(reminder: Reminder) -> Self {
+        Self {
+            id: reminder.id,
+            task_id: reminder.task_id,
+            offset_ms: reminder.offset_ms,
+            remind_at: reminder.remind_at,
+            sent_at: reminder.sent_at,
+            attempts: reminder.attempts,
+            next_attempt_at: reminder.next_attempt_at,
+            created_at: reminder.created_at,
+        }
+    }
+}
+
+impl From<BackupReminder> for Reminder {
+    fn from(reminder: BackupReminder) -> Self {
+        Self {
+            id: reminder.id,
+            task_id: reminder.task_id,
+            offset_ms: reminder.offset_ms,
+            remind_at: reminder.remind_at,
+            sent_at: reminder.sent_at,
+            attempts: reminder.attempts,
+            next_attempt_at: reminder.next_attempt_at,
+            created_at: reminder.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupAttachment {
+    pub id: Uuid,
+    pub task_id: Option<Uuid>,
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+    pub checksum: String,
+    pub uploaded_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Attachment> for BackupAttachment {
+    fn from(attachment: Attachment) -> Self {
+        Self {
+            id: attachment.id,
+            task_id: attachment.task_id,
+            filename: attachment.filename,
+            content_type: attachment.content_type,
+            size: attachment.size,
+            checksum: attachment.checksum,
+            uploaded_by: attachment.uploaded_by,
+            created_at: attachment.created_at,
+        }
+    }
+}
+
+impl From<BackupAttachment> for Attachment {
+    fn from(attachment: BackupAttachment) -> Self {
+        Self {
+            id: attachment.id,
+            task_id: attachment.task_id,
+            filename: attachment.filename,
+            content_type: attachment.content_type,
+            size: attachment.size,
+            checksum: attachment.checksum,
+            uploaded_by: attachment.uploaded_by,
+            created_at: attachment.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupComment {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub author: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<Comment> for BackupComment {
+    fn from(comment: Comment) -> Self {
+        Self {
+            id: comment.id,
+            task_id: comment.task_id,
+            author: comment.author,
+            body: comment.body,
+            created_at: comment.created_at,
+            updated_at: comment.updated_at,
+        }
+    }
+}
+
+impl From<BackupComment> for Comment {
+    fn from(comment: BackupComment) -> Self {
+        Self {
+            id: comment.id,
+            task_id: comment.task_id,
+            author: comment.author,
+            body: comment.body,
+            created_at: comment.created_at,
+            updated_at: comment.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupTaskEvent {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub list_id: Option<Uuid>,
+    pub actor: String,
+    pub event: String,
+    pub changes: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub mutation_id: Uuid,
+    pub reverts: Option<Uuid>,
+}
+
+impl From<TaskEvent> for BackupTaskEvent {
+    fn from(event: TaskEvent) -> Self {
+        Self {
+            id: event.id,
+            task_id: event.task_id,
+            list_id: event.list_id,
+            actor: event.actor,
+            event: event.event,
+            changes: event.changes,
+            created_at: event.created_at,
+            mutation_id: event.mutation_id,
+            reverts: event.reverts,
+        }
+    }
+}
+
+impl From<BackupTaskEvent> for TaskEvent {
+    fn from(event: BackupTaskEvent) -> Self {
+        Self {
+            id: event.id,
+            task_id: event.task_id,
+            list_id: event.list_id,
+            actor: event.actor,
+            event: event.event,
+            changes: event.changes,
+            created_at: event.created_at,
+            mutation_id: event.mutation_id,
+            reverts: event.reverts,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupWebhookSubscription {
+    pub id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub events: String,
+    pub owner: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<WebhookSubscription> for BackupWebhookSubscription {
+    fn from(subscription: WebhookSubscription) -> Self {
+        Self {
+            id: subscription.id,
+            url: subscription.url,
+            secret: subscription.secret,
+            events: subscription.events,
+            owner: subscription.owner,
+            created_at: subscription.created_at,
+        }
+    }
+}
+
+impl From<BackupWebhookSubscription> for WebhookSubscription {
+    fn from(subscription: BackupWebhookSubscription) -> Self {
+        Self {
+            id: subscription.id,
+            url: subscription.url,
+            secret: subscription.secret,
+            events: subscription.events,
+            owner: subscription.owner,
+            created_at: subscription.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupWebhookDelivery {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub event: String,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl From<WebhookDelivery> for BackupWebhookDelivery {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id,
+            subscription_id: delivery.subscription_id,
+            event: delivery.event,
+            payload: delivery.payload,
+            status: delivery.status,
+            attempts: delivery.attempts,
+            next_attempt_at: delivery.next_attempt_at,
+            response_status: delivery.response_status,
+            last_error: delivery.last_error,
+            created_at: delivery.created_at,
+            delivered_at: delivery.delivered_at,
+        }
+    }
+}
+
+impl From<BackupWebhookDelivery> for WebhookDelivery {
+    fn from(delivery: BackupWebhookDelivery) -> Self {
+        Self {
+            id: delivery.id,
+            subscription_id: delivery.subscription_id,
+            event: delivery.event,
+            payload: delivery.payload,
+            status: delivery.status,
+            attempts: delivery.attempts,
+            next_attempt_at: delivery.next_attempt_at,
+            response_status: delivery.response_status,
+            last_error: delivery.last_error,
+            created_at: delivery.created_at,
+            delivered_at: delivery.delivered_at,
+        }
+    }
+}
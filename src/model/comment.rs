@@ -0,0 +1,48 @@
+use entity::comment::Model as Comment;
+
+use chrono::serde::{ts_milliseconds, ts_milliseconds_option};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct NewComment {
+    pub body: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublicComment {
+    #[serde(rename = "_id")]
+    pub comment_id: Uuid,
+
+    #[serde(rename = "taskId")]
+    pub task_id: Uuid,
+
+    pub author: String,
+
+    pub body: String,
+
+    #[serde(rename = "createdAt", with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+
+    /// Set once the comment was edited
+    #[serde(
+        rename = "updatedAt",
+        skip_serializing_if = "Option::is_none",
+        with = "ts_milliseconds_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<Comment> for PublicComment {
+    fn from(comment: Comment) -> Self {
+        Self {
+            comment_id: comment.id,
+            task_id: comment.task_id,
+            author: comment.author,
+            body: comment.body,
+            created_at: comment.created_at,
+            updated_at: comment.updated_at,
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::lib::icalendar::SkippedComponent;
+use crate::model::task::PublicTask;
+use entity::task::Model as Task;
+
+use serde::Serialize;
+
+/// A problem with one line of an import, the header of a CSV file being
+/// line 1.
+#[derive(Serialize, Debug, Clone)]
+pub struct RowError {
+    pub row: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+
+    pub message: String,
+}
+
+impl RowError {
+    pub fn new(row: u64, field: Option<&str>, message: String) -> Self {
+        RowError {
+            row,
+            field: field.map(str::to_string),
+            message,
+        }
+    }
+}
+
+/// A task read from a line of an import, not yet validated against the lists
+/// and recurrence rules.
+pub struct ImportedRow {
+    pub row: u64,
+    pub task: Task,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
+
+    /// Tasks created, or that would be created by a dry run
+    pub imported: usize,
+
+    pub tasks: Vec<PublicTask>,
+
+    /// Rows that were skipped, ordered by row
+    pub errors: Vec<RowError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IcsImportReport {
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
+
+    pub imported: usize,
+
+    pub tasks: Vec<PublicTask>,
+
+    /// Components that were not imported, e.g. `VEVENT`s or `VTODO`s whose
+    /// `UID` was imported before
+    pub skipped: Vec<SkippedComponent>,
+}
@@ -1,2 +1,14 @@
+pub mod access_token;
+pub mod attachment;
+pub mod backup;
+pub mod comment;
+pub mod import;
+pub mod quick;
+pub mod reminder;
 pub mod results;
+pub mod stats;
 pub mod task;
+pub mod task_event;
+pub mod task_list;
+pub mod webhook;
+pub mod ws;
@@ -0,0 +1,61 @@
+use crate::model::task::PublicTask;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct QuickTask {
+    /// e.g. `Pay rent tomorrow 9am #home !high every month`
+    pub text: String,
+
+    /// Timezone relative dates and times are read in, UTC by default
+    pub timezone: Option<String>,
+
+    /// List of the task unless the text names one with a `#tag`
+    #[serde(rename = "listId")]
+    pub list_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum QuickTokenKind {
+    Date,
+    Time,
+    List,
+    Priority,
+    Recurrence,
+}
+
+/// Words of the text that were understood, and what they were taken for.
+#[derive(Serialize, Debug, Clone)]
+pub struct QuickToken {
+    /// The words as they were typed
+    pub text: String,
+
+    pub kind: QuickTokenKind,
+
+    /// e.g. `2024-05-02` for a date, `09:00` for a time, the id of a list,
+    /// a priority or an RRULE
+    pub value: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Interpretation {
+    /// What is left of the text once the tokens are taken out
+    pub title: String,
+
+    pub timezone: String,
+
+    pub tokens: Vec<QuickToken>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct QuickTaskResult {
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
+
+    /// The task created, or that would be created by a dry run
+    pub task: PublicTask,
+
+    pub interpretation: Interpretation,
+}
@@ -0,0 +1,61 @@
+use crate::model::task::PublicTask;
+use entity::reminder::Model as Reminder;
+use entity::task::Model as Task;
+
+use chrono::serde::{ts_milliseconds, ts_milliseconds_option};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub struct PublicReminder {
+    #[serde(rename = "_id")]
+    pub reminder_id: Uuid,
+
+    #[serde(rename = "taskId")]
+    pub task_id: Uuid,
+
+    /// Milliseconds before the deadline
+    pub offset: i64,
+
+    #[serde(rename = "remindAt", with = "ts_milliseconds")]
+    pub remind_at: DateTime<Utc>,
+
+    #[serde(
+        rename = "sentAt",
+        skip_serializing_if = "Option::is_none",
+        with = "ts_milliseconds_option"
+    )]
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+impl From<Reminder> for PublicReminder {
+    fn from(reminder: Reminder) -> Self {
+        Self {
+            reminder_id: reminder.id,
+            task_id: reminder.task_id,
+            offset: reminder.offset_ms,
+            remind_at: reminder.remind_at,
+            sent_at: reminder.sent_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReminderNotification {
+    pub event: &'static str,
+
+    pub reminder: PublicReminder,
+
+    pub task: PublicTask,
+}
+
+impl ReminderNotification {
+    pub fn new(reminder: Reminder, task: Task) -> Self {
+        Self {
+            event: "task.reminder",
+            reminder: PublicReminder::from(reminder),
+            task: PublicTask::from(task),
+        }
+    }
+}
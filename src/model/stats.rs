@@ -0,0 +1,44 @@
+use chrono::serde::ts_milliseconds;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    #[serde(with = "ts_milliseconds")]
+    pub from: DateTime<Utc>,
+
+    #[serde(with = "ts_milliseconds")]
+    pub to: DateTime<Utc>,
+
+    pub bucket: String,
+
+    pub timezone: String,
+
+    /// Tasks not completed yet, regardless of `from` and `to`
+    pub open: i64,
+
+    /// Tasks completed so far, regardless of `from` and `to`
+    pub completed: i64,
+
+    /// Open tasks past their deadline
+    pub overdue: i64,
+
+    /// Mean time from creation to completion of the tasks completed within
+    /// the range
+    #[serde(rename = "averageCompletionSeconds")]
+    pub average_completion_seconds: Option<f64>,
+
+    pub buckets: Vec<StatsBucket>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct StatsBucket {
+    #[serde(with = "ts_milliseconds")]
+    pub start: DateTime<Utc>,
+
+    /// Tasks created within the bucket
+    pub created: i64,
+
+    /// Tasks completed within the bucket
+    pub completed: i64,
+}
@@ -1,12 +1,36 @@
+use crate::lib::notes::{self, Rendering};
 use crate::lib::time::get_current_time;
+use entity::sea_orm_active_enums::Priority;
 use entity::task;
 use entity::task::Model as Task;
 
 use chrono::serde::{ts_milliseconds, ts_milliseconds_option};
-use chrono::{self, DateTime, Utc};
+use chrono::{self, DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct NewTask {
+    #[serde(flatten)]
+    pub task: Task,
+
+    /// Offsets in milliseconds before the deadline
+    #[serde(default)]
+    pub reminders: Vec<i64>,
+}
+
+/// Neighbours of a task moved by `POST /todo/{task_id}/move`, at least one
+/// of which is required.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MoveTask {
+    /// Task to come right after
+    pub after: Option<Uuid>,
+
+    /// Task to come right before
+    pub before: Option<Uuid>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct OptionalTask {
@@ -23,6 +47,60 @@ pub struct OptionalTask {
         default = "task::default_dealine"
     )]
     pub task_deadline: Option<DateTime<Utc>>,
+
+    #[serde(rename = "listId", skip_serializing_if = "Option::is_none", default)]
+    pub task_list: Option<Uuid>,
+
+    #[serde(rename = "priority", skip_serializing_if = "Option::is_none", default)]
+    pub task_priority: Option<Priority>,
+
+    /// An empty string stops the recurrence
+    #[serde(rename = "rrule", skip_serializing_if = "Option::is_none", default)]
+    pub task_rrule: Option<String>,
+
+    /// An empty string resets the timezone to UTC
+    #[serde(rename = "timezone", skip_serializing_if = "Option::is_none", default)]
+    pub task_timezone: Option<String>,
+
+    /// Markdown, an empty string removes the notes
+    #[serde(rename = "notes", skip_serializing_if = "Option::is_none", default)]
+    pub task_notes: Option<String>,
+
+    /// Replaces the reminders, in milliseconds before the deadline
+    #[serde(rename = "reminders", skip_serializing_if = "Option::is_none", default)]
+    pub task_reminders: Option<Vec<i64>>,
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    Some(value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+impl OptionalTask {
+    /// Copies the fields that were provided onto `task`, except for `listId`
+    /// which requires its own authorization.
+    pub fn apply_to(&self, task: &mut Task) {
+        if let Some(title) = self.task_title.clone() {
+            task.title = title;
+        }
+        if let Some(state) = self.task_state {
+            task.completed = state;
+        }
+        if let Some(deadline) = self.task_deadline {
+            task.deadline = Option::from(deadline);
+        }
+        if let Some(priority) = self.task_priority {
+            task.priority = priority;
+        }
+        if let Some(rrule) = &self.task_rrule {
+            task.rrule = non_empty(rrule);
+        }
+        if let Some(timezone) = &self.task_timezone {
+            task.timezone = non_empty(timezone);
+        }
+        if let Some(notes) = &self.task_notes {
+            task.notes = Some(notes.clone()).filter(|notes| !notes.trim().is_empty());
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -49,16 +127,128 @@ pub struct PublicTask {
         with = "ts_milliseconds_option"
     )]
     pub task_deadline: Option<DateTime<Utc>>,
+
+    #[serde(rename = "listId", skip_serializing_if = "Option::is_none", default)]
+    pub task_list: Option<Uuid>,
+
+    #[serde(rename = "priority", default)]
+    pub task_priority: Priority,
+
+    #[serde(rename = "rrule", skip_serializing_if = "Option::is_none", default)]
+    pub task_rrule: Option<String>,
+
+    #[serde(rename = "timezone", skip_serializing_if = "Option::is_none", default)]
+    pub task_timezone: Option<String>,
+
+    #[serde(rename = "seriesId", skip_serializing_if = "Option::is_none", default)]
+    pub task_series: Option<Uuid>,
+
+    #[serde(rename = "occurrence", default = "task::default_occurrence")]
+    pub task_occurrence: i32,
+
+    #[serde(rename = "parentId", skip_serializing_if = "Option::is_none", default)]
+    pub task_parent: Option<Uuid>,
+
+    #[serde(
+        rename = "completedAt",
+        skip_serializing_if = "Option::is_none",
+        with = "ts_milliseconds_option",
+        default
+    )]
+    pub task_completed_at: Option<DateTime<Utc>>,
+
+    #[serde(rename = "position", default)]
+    pub task_position: String,
+
+    /// Description in Markdown
+    #[serde(rename = "notes", skip_serializing_if = "Option::is_none", default)]
+    pub task_notes: Option<String>,
+
+    /// `notes` rendered as sanitized HTML, if requested with `?render=html`
+    #[serde(rename = "notesHtml", skip_serializing_if = "Option::is_none", default)]
+    pub task_notes_html: Option<String>,
+
+    /// Not completed and past its deadline, as of serialization
+    #[serde(rename = "overdue", default)]
+    pub task_overdue: bool,
+
+    /// Until the deadline, negative once it has passed
+    #[serde(
+        rename = "dueInSeconds",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub task_due_in_seconds: Option<i64>,
+
+    /// Number of comments, on `GET /todo` and `GET /todo/{task_id}`
+    #[serde(
+        rename = "commentCount",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub task_comment_count: Option<u64>,
+}
+
+impl PublicTask {
+    /// Adds the renderings of the notes asked for with `?render=`.
+    pub fn rendered(mut self, rendering: Option<Rendering>) -> Self {
+        if rendering == Some(Rendering::Html) {
+            self.task_notes_html = self.task_notes.as_deref().map(notes::render_html);
+        }
+        self
+    }
+
+    pub fn with_comment_count(mut self, count: u64) -> Self {
+        self.task_comment_count = Some(count);
+        self
+    }
 }
 
 impl From<Task> for PublicTask {
     fn from(task: Task) -> Self {
+        let due_in = task.deadline.map(|deadline| deadline - get_current_time());
         Self {
+            task_overdue: !task.completed && due_in.is_some_and(|due_in| due_in < Duration::zero()),
+            task_due_in_seconds: due_in.map(|due_in| due_in.num_seconds()),
             task_id: Some(task.id),
-            task_title: task.title.clone(),
+            task_title: task.title,
             task_state: task.completed,
             task_created_at: task.created_at,
             task_deadline: task.deadline,
+            task_list: task.list_id,
+            task_priority: task.priority,
+            task_rrule: task.rrule,
+            task_timezone: task.timezone,
+            task_series: task.series_id,
+            task_occurrence: task.occurrence,
+            task_parent: task.parent_id,
+            task_completed_at: task.completed_at,
+            task_position: task.position,
+            task_notes: task.notes,
+            task_notes_html: None,
+            task_comment_count: None,
+        }
+    }
+}
+
+impl From<PublicTask> for Task {
+    fn from(task: PublicTask) -> Self {
+        Self {
+            id: task.task_id.unwrap_or_else(Uuid::new_v4),
+            title: task.task_title,
+            completed: task.task_state,
+            created_at: task.task_created_at,
+            deadline: task.task_deadline,
+            list_id: task.task_list,
+            priority: task.task_priority,
+            rrule: task.task_rrule,
+            timezone: task.task_timezone,
+            series_id: task.task_series,
+            occurrence: task.task_occurrence,
+            parent_id: task.task_parent,
+            completed_at: task.task_completed_at,
+            position: task.task_position,
+            notes: task.task_notes,
         }
     }
 }
@@ -0,0 +1,58 @@
+use crate::model::task::PublicTask;
+use entity::task_event::Model as TaskEventRecord;
+
+use chrono::serde::ts_milliseconds;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub struct PublicTaskEvent {
+    #[serde(rename = "_id")]
+    pub event_id: Uuid,
+
+    #[serde(rename = "taskId")]
+    pub task_id: Uuid,
+
+    #[serde(rename = "listId", skip_serializing_if = "Option::is_none")]
+    pub list_id: Option<Uuid>,
+
+    pub actor: String,
+
+    pub event: String,
+
+    /// `{"<field>": {"before": ..., "after": ...}}`
+    pub changes: serde_json::Value,
+
+    #[serde(rename = "createdAt", with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<TaskEventRecord> for PublicTaskEvent {
+    fn from(event: TaskEventRecord) -> Self {
+        Self {
+            event_id: event.id,
+            task_id: event.task_id,
+            list_id: event.list_id,
+            actor: event.actor,
+            event: event.event,
+            changes: event.changes,
+            created_at: event.created_at,
+        }
+    }
+}
+
+/// Outcome of reverting a task or undoing a mutation.
+#[derive(Debug, Serialize)]
+pub struct RevertResult {
+    #[serde(rename = "mutationId")]
+    pub mutation_id: Uuid,
+
+    pub restored: Vec<PublicTask>,
+
+    pub deleted: Vec<Uuid>,
+
+    /// What recreated tasks lost along with their deletion and lack now
+    #[serde(rename = "notRestored", skip_serializing_if = "Vec::is_empty")]
+    pub not_restored: Vec<&'static str>,
+}
@@ -0,0 +1,82 @@
+use entity::list_member::{Model as ListMember, Role};
+use entity::task_list::Model as TaskList;
+
+use chrono::serde::{ts_milliseconds, ts_milliseconds_option};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NewTaskList {
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NewListMember {
+    pub member: String,
+
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublicTaskList {
+    #[serde(rename = "_id")]
+    pub list_id: Uuid,
+
+    pub name: String,
+
+    #[serde(rename = "createdAt", with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+
+    /// Role of the caller in this list
+    pub role: Role,
+}
+
+impl PublicTaskList {
+    pub fn new(list: TaskList, role: Role) -> Self {
+        Self {
+            list_id: list.id,
+            name: list.name,
+            created_at: list.created_at,
+            role,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublicListMember {
+    #[serde(rename = "listId")]
+    pub list_id: Uuid,
+
+    pub member: String,
+
+    pub role: Role,
+
+    #[serde(rename = "invitedBy")]
+    pub invited_by: String,
+
+    #[serde(rename = "createdAt", with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+
+    #[serde(
+        rename = "acceptedAt",
+        skip_serializing_if = "Option::is_none",
+        with = "ts_milliseconds_option"
+    )]
+    pub accepted_at: Option<DateTime<Utc>>,
+}
+
+impl From<ListMember> for PublicListMember {
+    fn from(membership: ListMember) -> Self {
+        Self {
+            list_id: membership.list_id,
+            member: membership.member,
+            role: membership.role,
+            invited_by: membership.invited_by,
+            created_at: membership.created_at,
+            accepted_at: membership.accepted_at,
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::events::TaskEvent;
+use crate::model::task::PublicTask;
+use entity::task::Model as Task;
+use entity::webhook_delivery::{DeliveryStatus, Model as WebhookDelivery};
+use entity::webhook_subscription::Model as WebhookSubscription;
+
+use chrono::serde::{ts_milliseconds, ts_milliseconds_option};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NewWebhookSubscription {
+    pub url: String,
+
+    /// Generated if not given
+    #[serde(default)]
+    pub secret: Option<String>,
+
+    pub events: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublicWebhookSubscription {
+    #[serde(rename = "_id")]
+    pub subscription_id: Uuid,
+
+    pub url: String,
+
+    pub events: Vec<String>,
+
+    pub owner: String,
+
+    #[serde(rename = "createdAt", with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<WebhookSubscription> for PublicWebhookSubscription {
+    fn from(subscription: WebhookSubscription) -> Self {
+        Self {
+            subscription_id: subscription.id,
+            url: subscription.url,
+            events: subscription
+                .events
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            owner: subscription.owner,
+            created_at: subscription.created_at,
+        }
+    }
+}
+
+/// Returned once on creation, the only time the secret is shown.
+#[derive(Debug, Serialize)]
+pub struct CreatedWebhookSubscription {
+    #[serde(flatten)]
+    pub subscription: PublicWebhookSubscription,
+
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublicWebhookDelivery {
+    #[serde(rename = "_id")]
+    pub delivery_id: Uuid,
+
+    #[serde(rename = "subscriptionId")]
+    pub subscription_id: Uuid,
+
+    pub event: String,
+
+    pub payload: serde_json::Value,
+
+    pub status: DeliveryStatus,
+
+    pub attempts: i32,
+
+    #[serde(rename = "nextAttemptAt", with = "ts_milliseconds")]
+    pub next_attempt_at: DateTime<Utc>,
+
+    #[serde(rename = "responseStatus", skip_serializing_if = "Option::is_none")]
+    pub response_status: Option<i32>,
+
+    #[serde(rename = "lastError", skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+
+    #[serde(rename = "createdAt", with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+
+    #[serde(
+        rename = "deliveredAt",
+        skip_serializing_if = "Option::is_none",
+        with = "ts_milliseconds_option"
+    )]
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl From<WebhookDelivery> for PublicWebhookDelivery {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            delivery_id: delivery.id,
+            subscription_id: delivery.subscription_id,
+            event: delivery.event,
+            payload: serde_json::from_str(&delivery.payload).unwrap_or_default(),
+            status: delivery.status,
+            attempts: delivery.attempts,
+            next_attempt_at: delivery.next_attempt_at,
+            response_status: delivery.response_status,
+            last_error: delivery.last_error,
+            created_at: delivery.created_at,
+            delivered_at: delivery.delivered_at,
+        }
+    }
+}
+
+/// Body of every webhook delivery.
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload {
+    /// Same as the `X-Webhook-Delivery` header, for deduplication
+    pub id: Uuid,
+
+    pub event: String,
+
+    #[serde(rename = "createdAt", with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+
+    pub task: PublicTask,
+}
+
+impl WebhookPayload {
+    pub fn new(id: Uuid, event: TaskEvent, task: Task) -> Self {
+        Self {
+            id,
+            event: event.to_string(),
+            created_at: Utc::now(),
+            task: PublicTask::from(task),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::model::task::{NewTask, OptionalTask, PublicTask};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Messages sent by WebSocket clients. The optional `requestId` is echoed in
+/// the reply, to match it with the request.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ClientMessage {
+    /// Receive changes of a list's tasks, or of tasks outside of any list
+    /// if `listId` is missing
+    Subscribe {
+        #[serde(rename = "requestId", default)]
+        request_id: Option<String>,
+
+        #[serde(rename = "listId", default)]
+        list_id: Option<Uuid>,
+    },
+
+    Unsubscribe {
+        #[serde(rename = "requestId", default)]
+        request_id: Option<String>,
+
+        #[serde(rename = "listId", default)]
+        list_id: Option<Uuid>,
+    },
+
+    Create {
+        #[serde(rename = "requestId", default)]
+        request_id: Option<String>,
+
+        task: NewTask,
+    },
+
+    Update {
+        #[serde(rename = "requestId", default)]
+        request_id: Option<String>,
+
+        #[serde(rename = "taskId")]
+        task_id: Uuid,
+
+        changes: OptionalTask,
+    },
+
+    Delete {
+        #[serde(rename = "requestId", default)]
+        request_id: Option<String>,
+
+        #[serde(rename = "taskId")]
+        task_id: Uuid,
+    },
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ServerMessage {
+    /// A change to a task of a subscribed list
+    Event {
+        id: u64,
+
+        event: String,
+
+        task: PublicTask,
+    },
+
+    Subscribed {
+        #[serde(rename = "requestId", skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+
+        #[serde(rename = "listId")]
+        list_id: Option<Uuid>,
+    },
+
+    Unsubscribed {
+        #[serde(rename = "requestId", skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+
+        #[serde(rename = "listId")]
+        list_id: Option<Uuid>,
+    },
+
+    /// Outcome of a mutation, with the status the REST API would respond with
+    Result {
+        #[serde(rename = "requestId", skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+
+        status: u16,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        task: Option<PublicTask>,
+
+        #[serde(rename = "rowsAffected", skip_serializing_if = "Option::is_none")]
+        rows_affected: Option<u64>,
+    },
+
+    Error {
+        #[serde(rename = "requestId", skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+
+        status: u16,
+
+        message: String,
+    },
+
+    /// Events were dropped because the client could not keep up, so it has
+    /// to fetch the tasks again
+    Resync,
+}
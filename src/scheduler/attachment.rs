@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use crate::api::attachment::{purge_detached, remove_unreferenced};
+use crate::database::DbClient;
+use crate::lib::attachments;
+use crate::lib::errors::Error;
+
+use actix_web::web::Data;
+
+pub fn poll_interval() -> Duration {
+    let seconds = std::env::var("ATTACHMENT_CLEANUP_SECONDS")
+        .ok()
+        .and_then(|seconds| seconds.parse::<u64>().ok())
+        .unwrap_or(60 * 60);
+    Duration::from_secs(seconds.max(1))
+}
+
+/// Removes the stored files no attachment references, e.g. those left by a
+/// restore or kept through their grace period, and abandoned uploads.
+async fn collect_garbage(db: &DbClient) -> Result<usize, Error> {
+    let removed = remove_unreferenced(db, attachments::stale_checksums().await?).await?;
+    let abandoned = attachments::remove_abandoned_uploads().await?;
+    Ok(removed + abandoned)
+}
+
+pub async fn run(db: Data<DbClient>, interval: Duration) {
+    info!("Cleaning up attachments every {:?}", interval);
+
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+
+        // Deleting tasks purges them already, unless that failed
+        purge_detached(&db).await;
+        match collect_garbage(&db).await {
+            Ok(0) => {}
+            Ok(removed) => debug!("Removed {} unreferenced attachment files", removed),
+            Err(err) => error!("Failed to clean up attachment files: {}", err),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod attachment;
+pub mod notifier;
+pub mod reminder;
+pub mod webhook;
@@ -0,0 +1,69 @@
+use crate::lib::errors::Error;
+use crate::model::reminder::ReminderNotification;
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delivers due reminders to the outside world.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, notification: &ReminderNotification) -> Result<(), Error>;
+}
+
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn notify(&self, notification: &ReminderNotification) -> Result<(), Error> {
+        info!(
+            "Reminder: `{}` is due at {:?}",
+            notification.task.task_title, notification.task.task_deadline
+        );
+        Ok(())
+    }
+}
+
+/// POSTs every reminder as JSON to a fixed URL.
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String) -> Self {
+        WebhookNotifier {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("HTTP client can be built"),
+            url,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, notification: &ReminderNotification) -> Result<(), Error> {
+        self.client
+            .post(&self.url)
+            .json(notification)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// `WebhookNotifier` if `REMINDER_WEBHOOK_URL` is set, `LogNotifier` otherwise.
+pub fn from_env() -> Box<dyn Notifier> {
+    match std::env::var("REMINDER_WEBHOOK_URL") {
+        Ok(url) if !url.is_empty() => {
+            info!("Sending reminders to {}", url);
+            Box::new(WebhookNotifier::new(url))
+        }
+        _ => Box::new(LogNotifier),
+    }
+}
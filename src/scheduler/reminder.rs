@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use crate::database::DbClient;
+use crate::lib::errors::Error;
+use crate::lib::time::get_current_time;
+use crate::model::reminder::ReminderNotification;
+use crate::scheduler::notifier::Notifier;
+
+use actix_web::web::Data;
+
+const BATCH_SIZE: u64 = 100;
+
+/// Failed attempts after which a reminder is given up on.
+const MAX_ATTEMPTS: i32 = 8;
+
+/// Delay before the second attempt, doubled for every further one.
+const BASE_BACKOFF_SECONDS: i64 = 10;
+
+/// How long claimed reminders are left to the instance sending them, long
+/// enough for a whole batch of notifications to time out.
+const CLAIM_LEASE_SECONDS: i64 = 20 * 60;
+
+pub fn poll_interval() -> Duration {
+    let seconds = std::env::var("REMINDER_POLL_SECONDS")
+        .ok()
+        .and_then(|seconds| seconds.parse::<u64>().ok())
+        .unwrap_or(30);
+    Duration::from_secs(seconds.max(1))
+}
+
+/// Claims due reminders and dispatches them, recording the outcome of each
+/// as soon as it is known. Claimed reminders are leased rather than locked
+/// while they are sent, so concurrent instances never send one twice.
+async fn dispatch_due(db: &DbClient, notifier: &dyn Notifier) -> Result<usize, Error> {
+    let reminders = db
+        .reminder_dao
+        .claim_due(
+            BATCH_SIZE,
+            MAX_ATTEMPTS,
+            chrono::Duration::seconds(CLAIM_LEASE_SECONDS),
+        )
+        .await?;
+
+    let mut sent = 0;
+    for reminder in reminders {
+        let task = match db.task_dao.find_id(reminder.task_id).await {
+            Ok(task) => task,
+            // Reminders are removed along with their task
+            Err(Error::NotFound(_)) => continue,
+            Err(err) => return Err(err),
+        };
+
+        // Reminders of completed tasks are dropped rather than sent
+        if task.completed {
+            db.reminder_dao.mark_sent(reminder).await?;
+            continue;
+        }
+
+        let notification = ReminderNotification::new(reminder.clone(), task);
+        match notifier.notify(&notification).await {
+            Ok(()) => {
+                sent += 1;
+                db.reminder_dao.mark_sent(reminder).await?;
+            }
+            Err(err) => {
+                warn!("Failed to send Reminder <id: {}>: {}", reminder.id, err);
+                let attempts = reminder.attempts + 1;
+                let retry_at = (attempts < MAX_ATTEMPTS).then(|| {
+                    get_current_time()
+                        + chrono::Duration::seconds(BASE_BACKOFF_SECONDS << (attempts - 1))
+                });
+                db.reminder_dao.record_failure(reminder, retry_at).await?;
+            }
+        }
+    }
+
+    Ok(sent)
+}
+
+pub async fn run(db: Data<DbClient>, notifier: Box<dyn Notifier>, interval: Duration) {
+    info!("Polling for due reminders every {:?}", interval);
+
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+
+        match dispatch_due(&db, notifier.as_ref()).await {
+            Ok(0) => {}
+            Ok(sent) => debug!("Sent {} reminders", sent),
+            Err(err) => error!("Failed to dispatch reminders: {}", err),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use crate::database::DbClient;
+use crate::lib::errors::Error;
+use crate::lib::time::get_current_time;
+use entity::webhook_delivery::Model as WebhookDelivery;
+
+use actix_web::web::Data;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+const BATCH_SIZE: u64 = 50;
+
+/// Attempts after which a delivery is given up on and marked dead.
+const MAX_ATTEMPTS: i32 = 8;
+
+/// Delay before the second attempt, doubled for every further one.
+const BASE_BACKOFF_SECONDS: i64 = 10;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long claimed deliveries are left to the instance sending them, long
+/// enough for a whole batch of requests to time out.
+const CLAIM_LEASE_SECONDS: i64 = 15 * 60;
+
+pub fn poll_interval() -> Duration {
+    let seconds = std::env::var("WEBHOOK_POLL_SECONDS")
+        .ok()
+        .and_then(|seconds| seconds.parse::<u64>().ok())
+        .unwrap_or(5);
+    Duration::from_secs(seconds.max(1))
+}
+
+/// Hex encoded HMAC-SHA256 of `body`, keyed with the subscription's secret.
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Sends a single delivery, returning the response status if there was one.
+async fn send(
+    client: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    delivery: &WebhookDelivery,
+) -> (Option<i32>, Option<String>) {
+    let res = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header("X-Webhook-Event", &delivery.event)
+        .header("X-Webhook-Delivery", delivery.id.to_string())
+        .header(
+            "X-Webhook-Signature-256",
+            format!("sha256={}", sign(secret, &delivery.payload)),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    match res {
+        Ok(res) if res.status().is_success() => (Some(res.status().as_u16() as i32), None),
+        Ok(res) => (
+            Some(res.status().as_u16() as i32),
+            Some(format!("Responded with {}", res.status())),
+        ),
+        Err(err) => (None, Some(err.to_string())),
+    }
+}
+
+/// Claims due deliveries and attempts them, recording the outcome of each
+/// as soon as it is known. Claimed deliveries are leased rather than locked
+/// while they are sent, so concurrent instances never send one twice.
+async fn dispatch_due(db: &DbClient, client: &reqwest::Client) -> Result<usize, Error> {
+    let deliveries = db
+        .webhook_delivery_dao
+        .claim_due(BATCH_SIZE, chrono::Duration::seconds(CLAIM_LEASE_SECONDS))
+        .await?;
+
+    let mut delivered = 0;
+    for delivery in deliveries {
+        // Deliveries are removed along with their subscription
+        let subscription = match db
+            .webhook_subscription_dao
+            .find_id(delivery.subscription_id)
+            .await
+        {
+            Ok(subscription) => subscription,
+            Err(Error::NotFound(_)) => continue,
+            Err(err) => return Err(err),
+        };
+
+        let (response_status, error) =
+            send(client, &subscription.url, &subscription.secret, &delivery).await;
+
+        let retry_at = match error {
+            Some(ref error) => {
+                warn!("Failed to deliver Webhook <id: {}>: {}", delivery.id, error);
+                let attempts = delivery.attempts + 1;
+                (attempts < MAX_ATTEMPTS).then(|| {
+                    get_current_time()
+                        + chrono::Duration::seconds(BASE_BACKOFF_SECONDS << (attempts - 1))
+                })
+            }
+            None => {
+                delivered += 1;
+                None
+            }
+        };
+
+        db.webhook_delivery_dao
+            .record_attempt(delivery, response_status, error, retry_at)
+            .await?;
+    }
+
+    Ok(delivered)
+}
+
+pub async fn run(db: Data<DbClient>, interval: Duration) {
+    info!("Polling for webhook deliveries every {:?}", interval);
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("HTTP client can be built");
+
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+
+        match dispatch_due(&db, &client).await {
+            Ok(0) => {}
+            Ok(delivered) => debug!("Delivered {} webhooks", delivered),
+            Err(err) => error!("Failed to dispatch webhooks: {}", err),
+        }
+    }
+}